@@ -1,77 +1,754 @@
-use clap::{Parser, ValueEnum};
-use lineup::{ItemSpan, LineSeparator};
+use clap::{CommandFactory, FromArgMatches, Parser, ValueEnum};
+use lineup::{
+    ItemSpanBuilder, LineSeparator, NumberFormatBuilder, PageSeparator, Quoting, Selector,
+    StyleRule, TabStops,
+};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
 pub struct Config {
     in_fmt: lineup::InFormat,
     out_fmt: lineup::OutFormat,
+    auto_span: bool,
+    fit_line: bool,
+    detect_in_format: bool,
+    stages: Vec<lineup::Stage>,
+    transpose: Option<usize>,
+    /// Whether items of each input record start a new output line, pipeline stages applying
+    /// to each record
+    rows: bool,
+    stats: bool,
+    check: bool,
+    /// Input files, standard input for None, read in sequence
+    inputs: Vec<Option<PathBuf>>,
+    reset_per_file: bool,
+    /// Output file, if not standard output
+    output: Option<PathBuf>,
+    #[cfg(feature = "rayon")]
+    jobs: Option<usize>,
+    #[cfg(feature = "encoding")]
+    in_encoding: Option<&'static encoding_rs::Encoding>,
+    #[cfg(feature = "encoding")]
+    out_encoding: Option<&'static encoding_rs::Encoding>,
 }
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
 struct Args {
+    #[arg(long, num_args = 1.., default_value = "-")]
+    /// Files to read input from, in sequence as a single input, '-' for standard input; can be
+    /// repeated
+    input: Vec<PathBuf>,
+
+    #[arg(long)]
+    /// Read each input file on its own: IN format lines to skip, header, comments and line
+    /// numbers, and pipeline stages apply to each file, whose items start a new output line;
+    /// items of all files are transposed together
+    reset_per_file: bool,
+
+    #[arg(long, default_value = "-")]
+    /// File to write output to, '-' for standard output; it is created if missing, truncated
+    /// otherwise, and cannot be the input file
+    output: PathBuf,
+
+    #[cfg(feature = "toml")]
+    #[arg(long, requires = "preset")]
+    /// TOML file defining presets (see 'preset'), $XDG_CONFIG_HOME/lineup.toml, or
+    /// ~/.config/lineup.toml, by default
+    config: Option<PathBuf>,
+
+    #[cfg(feature = "toml")]
+    #[arg(long)]
+    /// Preset of arguments to apply, defined in 'config' file as a table of argument long
+    /// names and values, e.g. [presets.NAME] in-mode = "csv", out-span = "auto"; flags are set
+    /// by true, repeated arguments by arrays; arguments given on the command line take
+    /// precedence over preset ones, or add to them if they can be repeated; flags set by a
+    /// preset cannot be unset on the command line
+    preset: Option<String>,
+
     #[arg(long, value_parser = InputItemSeparator::parse, default_value = ",", long_help = InputItemSeparator::LONG_HELP)]
     /// IN format: input item separator
     in_separator: InputItemSeparator,
 
+    #[arg(long)]
+    /// IN format: split input items on any run of whitespace, ignoring leading and trailing
+    /// whitespace; it takes precedence over 'in-separator'
+    in_split_whitespace: bool,
+
+    #[cfg(feature = "regex")]
+    #[arg(long, value_parser = parse_regex)]
+    /// IN format: regular expression matching input item separators, e.g. '[ \t]+'; it takes
+    /// precedence over 'in-separator' and 'in-split-whitespace'
+    in_separator_regex: Option<String>,
+
+    #[arg(long, value_delimiter = ',', value_parser = parse_field_width)]
+    /// IN format: comma separated widths, in characters, of the fixed width fields of a record
+    /// on each line, e.g. '8,4,12'; it takes precedence over any other input item separator
+    in_field_widths: Vec<usize>,
+
     #[arg(long, default_value = "0")]
     /// IN format, line: number of items per line; if 0 provided all items are on a single line
     in_line_n: usize, // 0 means no line separaion
 
-    #[arg(long, default_value = "")]
+    #[arg(long, default_value = "\n")]
     /// IN format, line: separator string between lines
     in_line_separator: String,
 
+    #[arg(long, value_parser = parse_record_length, conflicts_with = "in_line_n")]
+    /// IN format, line: fixed length of lines, followed by 'in-line-separator', in bytes (N) or
+    /// characters (Nc), rather than a number of items per line
+    in_record_length: Option<lineup::RecordLength>,
+
+    #[arg(long)]
+    /// IN format, quoting: quote character; separators within items wrapped in it are not item
+    /// boundaries, and quotes are removed from items
+    in_quote: Option<char>,
+
+    #[arg(long, value_enum, default_value = "doubled")]
+    /// IN format, quoting: quote characters within quoted items are escaped by doubling them
+    /// (doubled) or preceding them, and backslashes, with a backslash (backslash)
+    in_quote_escape: QuoteEscape,
+
+    #[arg(long)]
+    /// IN format: trim leading and trailing whitespace from items
+    trim: bool,
+
+    #[arg(long, value_enum, default_value = "terminate")]
+    /// IN format: empty items between separators stop reading (terminate), are skipped (skip) or
+    /// are read as empty items (yield)
+    in_empty_items: EmptyItems,
+
+    #[arg(long, value_enum, default_value = "drop")]
+    /// IN format: a trailing chunk shorter than fixed size items (see 'in-separator') is dropped
+    /// (drop), read as a short item (yield) or is an error (error)
+    in_partial_chunk: PartialChunk,
+
+    #[arg(long, value_enum, default_value = "error")]
+    /// IN format: invalid UTF-8, or input encoding, in input is an error (error) or is replaced
+    /// by replacement characters (replace)
+    in_invalid_utf8: InvalidUtf8,
+
+    #[cfg(feature = "encoding")]
+    #[arg(long, value_parser = parse_encoding)]
+    /// IN format: encoding of input, e.g. 'latin1', 'shift_jis' or 'utf-16le', rather than
+    /// UTF-8; a byte order mark, if any, takes precedence
+    in_encoding: Option<&'static encoding_rs::Encoding>,
+
+    #[arg(long, value_enum, default_value = "error")]
+    /// IN format: a boundary of fixed byte size items (see 'in-separator') within a UTF-8 code
+    /// point is an error (error) or is moved after (forward) or before (backward) it
+    in_byte_rounding: ByteRounding,
+
+    #[arg(long, value_enum, default_value = "plain", alias = "in-format")]
+    /// IN format: input items are separated as per 'in-separator' and 'in-line-separator'
+    /// (plain), are the fields of RFC 4180 CSV (csv) or TSV (tsv) records or the values in a
+    /// JSON array, nested arrays flattened (json), or in the arrays or objects on each line of
+    /// JSON Lines (json-lines)
+    in_mode: InputMode,
+
     #[arg(long, default_value = "0")]
+    /// IN format: number of input lines to be skipped before reading items
+    in_skip_lines: usize,
+
+    #[arg(long)]
+    /// IN format: the first input line, after skipped ones, is read as header labels, emitted
+    /// as per 'out-header' unless it is given
+    in_header: bool,
+
+    #[arg(long, num_args = 0..=1, default_missing_value = "#")]
+    /// IN format: prefix of comment lines, '#' if none given; input lines starting with it are
+    /// ignored, where items start at the beginning of a line
+    in_comment: Option<String>,
+
+    #[arg(long, default_value = "0")]
+    /// IN format: number of leading input items to be discarded
+    in_skip_items: usize,
+
+    #[arg(long)]
+    /// IN format: maximum number of input items to be read, after discarded ones
+    in_max_items: Option<usize>,
+
+    #[arg(long)]
+    /// IN format: maximum length of input items, in characters; longer ones are an error
+    in_max_item_length: Option<usize>,
+
+    #[arg(long)]
+    /// IN format: expected number of input items; more or fewer are an error
+    in_expect_items: Option<usize>,
+
+    #[arg(long)]
+    /// IN format: expected number of items on each input line with any, lines ending with a new
+    /// line (not in JSON modes); more or fewer are an error
+    in_expect_line_items: Option<usize>,
+
+    #[arg(long)]
+    /// IN format: keep a leading byte order mark (U+FEFF) in the first item, instead of
+    /// stripping it
+    in_keep_bom: bool,
+
+    #[arg(long, conflicts_with_all = ["in_separator", "in_mode"])]
+    /// IN format: input items are separated (or terminated) by NUL characters, e.g. from
+    /// 'find -print0', empty ones skipped
+    in_null: bool,
+
+    #[arg(long, conflicts_with_all = ["out_separator", "out_line_separator"])]
+    /// OUT format: output items are terminated by NUL characters, e.g. for 'xargs -0'
+    out_null: bool,
+
+    #[arg(long, conflicts_with_all = ["in_mode", "out_separator", "out_line_separator"])]
+    /// IN and OUT format: TSV, i.e. tab separated fields of records on lines of their own as
+    /// input (see 'in-mode') and tab separated items, 'out-line-n' on each line, or those of
+    /// each record if 0, as output
+    tsv: bool,
+
+    #[arg(long, value_parser = parse_format)]
+    /// IN and OUT format: format spec, e.g. "in:sep=',';out:span=8,anchor=right,cols=4" (see
+    /// lineup::InFormat::from_str); it replaces IN and OUT formats set by other arguments, but
+    /// for OUT settings format specs do not cover: column spans, anchors, pads and separators,
+    /// number formats, styles, width, header and footer
+    format: Option<FormatSpec>,
+
+    #[arg(long)]
+    /// IN format: escape character; separators following it outside quoted items are not item
+    /// boundaries, and escape characters are removed from items
+    in_escape: Option<char>,
+
+    #[cfg(feature = "regex")]
+    #[arg(long, value_parser = compile_regex)]
+    /// PIPELINE: keep only items matching given regular expression, e.g. '^[0-9]+$', before
+    /// other stages; can be repeated, items then having to match all of them
+    filter: Vec<regex::Regex>,
+
+    #[cfg(feature = "regex")]
+    #[arg(long, value_parser = compile_regex)]
+    /// PIPELINE: discard items matching given regular expression, before other stages; can be
+    /// repeated
+    filter_out: Vec<regex::Regex>,
+
+    #[arg(long, value_parser = parse_stage)]
+    /// PIPELINE: stage items go through between reading and writing, in the order given:
+    /// 'trim', 'keep=REGEX' or 'discard=REGEX' to keep or discard items matching a regular
+    /// expression, 'skip=N' or 'take=N' to discard or keep the first N items; may be repeated
+    stage: Vec<lineup::Stage>,
+
+    #[arg(long, value_parser = parse_sort, num_args = 0..=1, default_missing_value = "")]
+    /// PIPELINE: sort items once all are read, after other stages, as per a comma separated list
+    /// of options: 'lexical' (default) or 'numeric' order, 'reverse', 'column=K' to sort rows of
+    /// 'out-line-n' items (or 'row=N') by their item at column K, from 0
+    sort: Option<lineup::Sort>,
+
+    #[arg(long, conflicts_with = "out_line_width")]
+    /// PIPELINE: take items as rows of 'out-line-n' items, one row if 0, and turn them into rows
+    /// of the items at the same column position, before formatting; missing items of a short
+    /// last row are empty; all items are read before writing
+    transpose: bool,
+
+    #[arg(long, value_parser = OutputSpan::parse, default_value = "0")]
     /// OUT format, span: max characters an item would need; shorter representations would be padded with 'pad'
     /// and anchored according to 'anchor';
-    /// if 0, items will not be padded so 'pad' and 'anchor' are not used
-    out_span: usize,
+    /// if 0, items will not be padded so 'pad' and 'anchor' are not used;
+    /// if a comma separated list (e.g. 10,4), spans are applied cyclically by column position within a line;
+    /// if 'auto', span is computed from the widest item (all items are read before writing)
+    out_span: OutputSpan,
 
     #[arg(long, default_value = " ")]
     /// OUT format, span: pad character (see 'span')
     out_pad: char,
 
-    #[arg(long, value_enum, default_value = "left")]
-    /// OUT format, span: anchor items to the left or right when padding is needed (see 'span')
-    out_anchor: Anchor,
+    #[arg(long, value_delimiter = ',')]
+    /// OUT format, span: comma separated pad characters by column position within a line (e.g. '0, ');
+    /// columns beyond the list are padded with 'pad'
+    out_column_pads: Vec<char>,
+
+    #[arg(long, value_enum)]
+    /// OUT format, span: anchor items to the left (default), right, center or on their decimal
+    /// point when padding is needed (see 'span'); if not given with 'span' auto, columns of
+    /// numbers are anchored to the right and other columns to the left
+    out_anchor: Option<Anchor>,
+
+    #[arg(long, value_enum, value_delimiter = ',')]
+    /// OUT format, span: comma separated anchors by column position within a line (e.g. left,right);
+    /// columns beyond the list are anchored according to 'anchor'
+    out_column_anchors: Vec<Anchor>,
+
+    #[arg(long, value_enum, default_value = "right")]
+    /// OUT format, span: side getting the extra pad character when a centered item has an odd
+    /// number of pad characters (see 'anchor')
+    out_odd_pad: Side,
+
+    #[arg(long, default_value = ".")]
+    /// OUT format, span: character decimal anchored items are aligned on (see 'anchor')
+    out_decimal_point: char,
+
+    #[arg(long, default_value = "0")]
+    /// OUT format, span: number of characters reserved after the decimal point of decimal
+    /// anchored items (see 'anchor')
+    out_decimal_fraction: usize,
+
+    #[arg(long, value_enum, default_value = "overflow")]
+    /// OUT format, span: policy for items longer than 'span': write them as they are (overflow),
+    /// cut them on the side opposite to 'anchor' (truncate), fail (error) or wrap them onto
+    /// continuation lines within the same column (wrap)
+    out_overflow: OverflowPolicy,
+
+    #[arg(long)]
+    /// OUT format, span: marker replacing the characters cut from truncated items (see 'overflow');
+    /// it counts toward 'span'
+    out_ellipsis: Option<String>,
+
+    #[arg(long, value_enum, default_value = "chars")]
+    /// OUT format, span: measure of item width against 'span': number of characters (chars),
+    /// of terminal display cells, so that wide characters count as two (cells)
+    /// or of grapheme clusters, so that combining sequences count as one (graphemes)
+    out_width: Width,
+
+    #[arg(long)]
+    /// OUT format, number: number of fraction digits items looking like numbers are rounded to
+    out_number_precision: Option<usize>,
+
+    #[arg(long)]
+    /// OUT format, number: separator between groups of three integer digits of items looking
+    /// like numbers
+    out_number_grouping: Option<char>,
+
+    #[arg(long, value_enum)]
+    /// OUT format, number: notation items looking like numbers are written in
+    out_number_notation: Option<Notation>,
+
+    #[cfg(feature = "locale")]
+    #[arg(long, value_parser = parse_locale)]
+    /// OUT format, number: locale (e.g. de or en_US) items looking like numbers are written
+    /// as per, with its decimal point and digit grouping separator; 'out-number-grouping' takes
+    /// precedence over the latter
+    locale: Option<String>,
+
+    #[cfg(feature = "encoding")]
+    #[arg(long, value_parser = parse_encoding)]
+    /// OUT format: encoding of output, e.g. 'latin1', 'shift_jis' or 'utf-16le', rather than
+    /// UTF-8; characters it cannot represent are written as HTML numeric character references
+    out_encoding: Option<&'static encoding_rs::Encoding>,
+
+    #[arg(long, value_enum, default_value = "preserve")]
+    /// OUT format: case transform applied to every item, before decorations and padding
+    out_case: Case,
+
+    #[arg(long, default_value = "")]
+    /// OUT format: string prepended to every item, before padding
+    out_item_prefix: String,
+
+    #[arg(long, default_value = "")]
+    /// OUT format: string appended to every item, before padding
+    out_item_suffix: String,
+
+    #[arg(long, value_parser = parse_template)]
+    /// OUT format: template every item is rendered through, before item prefix and suffix, e.g.
+    /// '{index}: {item}' or '[{item:>8}]'; variables are item, index, line and column, each
+    /// optionally followed by ':[[fill]align][width]', align being '<', '>' or '^'
+    out_template: Option<lineup::Template>,
 
     #[arg(long, default_value = " ")]
     /// OUT format: separator string for items within a line
     out_separator: String,
 
-    #[arg(long, default_value = "0")]
-    /// OUT format, line: number of items per line; if 0 provided put all items on a single line
-    out_line_n: usize, // 0 means no line separaion
+    #[arg(long, conflicts_with = "out_tabs")]
+    /// OUT format: instead of 'separator', pad with spaces up to the next multiple of given
+    /// tab-stop width within the line
+    out_tab_stops: Option<usize>,
+
+    #[arg(long)]
+    /// OUT format: instead of 'separator', emit a literal tab between items
+    out_tabs: bool,
+
+    #[arg(long, value_parser = OutputLineN::parse, default_value = "0")]
+    /// OUT format, line: number of items per line; if 0 provided put all items on a single line;
+    /// if 'fit' provided put as many padded items as fit the terminal width, reading all items
+    /// before writing, as with 'auto' span, which is implied when no span is given
+    out_line_n: OutputLineN,
+
+    #[arg(long, conflicts_with = "out_line_n")]
+    /// OUT format, line: instead of 'line-n', start a new line whenever the next item would make
+    /// the line exceed given width, padding and separators included
+    out_line_width: Option<usize>,
 
     #[arg(long, default_value = "")]
     /// OUT format, line: separator string between lines
     out_line_separator: String,
+
+    #[arg(long, default_value = "")]
+    /// OUT format, line: string emitted at the start of every line, e.g. to indent output
+    out_line_prefix: String,
+
+    #[arg(long, default_value = "")]
+    /// OUT format, line: string emitted before every line separator, e.g. a shell line
+    /// continuation
+    out_line_suffix: String,
+
+    #[arg(long, default_value = "0")]
+    /// OUT format, page: number of lines per page; if 0 provided lines are not paginated
+    out_page_n: usize,
+
+    #[arg(long, default_value = "\u{c}")]
+    /// OUT format, page: separator string between pages, emitted after the line separator;
+    /// defaults to form feed
+    out_page_separator: String,
+
+    #[arg(long, value_enum, default_value = "row")]
+    /// OUT format, line: fill lines with items across (row) or down the columns first (column),
+    /// given 'line-n' items per line; column fill reads all items before writing
+    out_fill: FillOrder,
+
+    #[arg(long, value_enum, default_value = "plain")]
+    /// OUT format: output mode, either items as they are (plain), items quoted as per RFC 4180
+    /// when they contain separators, double quotes or line breaks (csv) or items as JSON strings
+    /// in an array, nested per line if 'line-n' is not 0 (json) or each line as a JSON array, or
+    /// as a JSON object keyed by 'header' labels if any (json-lines)
+    out_mode: OutputMode,
+
+    #[arg(long, value_enum, default_value = "never")]
+    /// OUT format, quoting: quote items never, only when they contain separators, quote characters
+    /// or line breaks (when-needed) or always; applies to plain output mode
+    out_quote: QuotePolicy,
+
+    #[arg(long, default_value = "\"")]
+    /// OUT format, quoting: quote character (see 'quote')
+    out_quote_char: char,
+
+    #[arg(long, value_enum, default_value = "doubled")]
+    /// OUT format, quoting: escape quote characters within quoted items by doubling them (doubled)
+    /// or preceding them, and backslashes, with a backslash (backslash) (see 'quote')
+    out_quote_escape: QuoteEscape,
+
+    #[arg(long)]
+    /// OUT format: wrap items in Unicode directional isolates, so that right-to-left items are
+    /// aligned as the others
+    out_bidi_isolate: bool,
+
+    #[arg(long)]
+    /// OUT format, line: complete the last line with empty padded cells, so that output is a
+    /// rectangle
+    out_fill_last_line: bool,
+
+    #[arg(long)]
+    /// OUT format: emit the item (or line) separator after the last item as well
+    out_trailing_separator: bool,
+
+    #[arg(long, value_delimiter = ',')]
+    /// OUT format: comma separated header labels, emitted as the first line and formatted
+    /// as items at the same column positions
+    out_header: Vec<String>,
+
+    #[arg(long, value_delimiter = ',')]
+    /// OUT format: comma separated footer labels, emitted as the last line and formatted
+    /// as items at the same column positions
+    out_footer: Vec<String>,
+
+    #[arg(long, value_enum, default_value = "auto")]
+    /// Emit styles (see 'out-style-column' and 'out-style-match') always, never or only when
+    /// output is a terminal and NO_COLOR environment variable is not set (auto)
+    color: ColorWhen,
+
+    #[arg(long, value_parser = parse_column_style)]
+    /// OUT format, style: COLUMN=STYLE applies STYLE to items at COLUMN position within a line,
+    /// STYLE being a comma separated list of: bold, underline, a color for the text, on-COLOR for
+    /// the background; colors are black, red, green, yellow, blue, magenta, cyan and white;
+    /// can be repeated
+    out_style_column: Vec<(usize, lineup::Style)>,
+
+    #[cfg(feature = "regex")]
+    #[arg(long, value_parser = parse_match_style)]
+    /// OUT format, style: REGEX=STYLE applies STYLE (see 'out-style-column') to items matching
+    /// REGEX; it takes precedence over column styles; can be repeated
+    out_style_match: Vec<(regex::Regex, lineup::Style)>,
+
+    #[arg(long)]
+    /// Report item count, line count, min, max and mean item width, measured as per
+    /// 'out-width', and a histogram of item widths, rather than writing items; items are
+    /// counted once through 'filter', 'filter-out' and 'stage', lines as read
+    stats: bool,
+
+    #[arg(long, conflicts_with = "stats")]
+    /// Only check input conforms to IN format, e.g. to 'in-expect-line-items',
+    /// 'in-max-item-length' or to fixed byte size items not splitting UTF-8 code points
+    /// (see 'in-byte-rounding'), exiting with an error at the line and column of the first
+    /// violation, if any
+    check: bool,
+
+    #[cfg(feature = "rayon")]
+    #[arg(long, value_parser = parse_jobs)]
+    /// Number of threads reading and formatting chunks of items in parallel, or 'auto' for as
+    /// many as CPUs; all items are read before writing
+    jobs: Option<usize>,
+}
+
+#[cfg(feature = "toml")]
+/// Arguments set by `preset`, as defined in configuration file at `path`, or at the default
+/// path (see 'config' argument), but those `given` otherwise, by long name
+fn preset_args(
+    path: Option<&Path>,
+    preset: &str,
+    given: impl Fn(&str) -> bool,
+) -> Result<Vec<std::ffi::OsString>, String> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".config")))
+            .ok_or("no config file: neither XDG_CONFIG_HOME nor HOME is set")?
+            .join("lineup.toml"),
+    };
+    let config = std::fs::read_to_string(&path)
+        .map_err(|error| format!("{}: {error}", path.display()))?
+        .parse::<toml::Table>()
+        .map_err(|error| format!("{}: {error}", path.display()))?;
+    let arguments = config
+        .get("presets")
+        .and_then(toml::Value::as_table)
+        .and_then(|presets| presets.get(preset))
+        .and_then(toml::Value::as_table)
+        .ok_or_else(|| format!("{}: no preset {preset}", path.display()))?;
+    let mut args = Vec::new();
+    for (name, value) in arguments {
+        if name == "config" || name == "preset" {
+            return Err(format!(
+                "preset {preset}: '{name}' cannot be set by a preset"
+            ));
+        }
+        if given(name) {
+            continue;
+        }
+        let values = match value {
+            toml::Value::Array(values) => values.iter().collect(),
+            value => vec![value],
+        };
+        for value in values {
+            match value {
+                toml::Value::Boolean(true) => args.push(format!("--{name}")),
+                toml::Value::Boolean(false) => {}
+                toml::Value::String(value) => args.push(format!("--{name}={value}")),
+                toml::Value::Integer(value) => args.push(format!("--{name}={value}")),
+                toml::Value::Float(value) => args.push(format!("--{name}={value}")),
+                _ => return Err(format!("preset {preset}: unsupported value of '{name}'")),
+            }
+        }
+    }
+    Ok(args.into_iter().map(Into::into).collect())
+}
+
+#[cfg(feature = "encoding")]
+/// Parse an encoding label, as per the WHATWG Encoding Standard
+fn parse_encoding(arg: &str) -> Result<&'static encoding_rs::Encoding, String> {
+    encoding_rs::Encoding::for_label(arg.as_bytes())
+        .ok_or_else(|| format!("unknown encoding {arg}"))
+}
+
+#[cfg(feature = "rayon")]
+/// Parse a number of threads, 'auto' being 0 for as many as CPUs
+fn parse_jobs(arg: &str) -> Result<usize, String> {
+    match arg {
+        "auto" => Ok(0),
+        _ => match arg.parse() {
+            Ok(jobs) if jobs > 0 => Ok(jobs),
+            _ => Err("jobs must be a number > 0 or 'auto'".to_string()),
+        },
+    }
+}
+
+#[cfg(feature = "locale")]
+/// Parse a locale name, checking it is known
+fn parse_locale(arg: &str) -> Result<String, String> {
+    NumberFormatBuilder::default()
+        .locale(arg)
+        .map(|_| arg.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Parse a comma separated list of style attributes
+fn parse_style(arg: &str) -> Result<lineup::Style, String> {
+    let color = |name: &str| match name {
+        "black" => Ok(lineup::Color::Black),
+        "red" => Ok(lineup::Color::Red),
+        "green" => Ok(lineup::Color::Green),
+        "yellow" => Ok(lineup::Color::Yellow),
+        "blue" => Ok(lineup::Color::Blue),
+        "magenta" => Ok(lineup::Color::Magenta),
+        "cyan" => Ok(lineup::Color::Cyan),
+        "white" => Ok(lineup::Color::White),
+        _ => Err(format!("unknown style attribute '{name}'")),
+    };
+    let mut style = lineup::StyleBuilder::default();
+    for attribute in arg.split(',') {
+        match attribute {
+            "bold" => style.bold(true),
+            "underline" => style.underline(true),
+            _ => match attribute.strip_prefix("on-") {
+                Some(background) => style.background(Some(color(background)?)),
+                None => style.foreground(Some(color(attribute)?)),
+            },
+        };
+    }
+    style.build().map_err(|e| e.to_string())
+}
+
+fn parse_column_style(arg: &str) -> Result<(usize, lineup::Style), String> {
+    let (column, style) = arg
+        .split_once('=')
+        .ok_or("column style must be COLUMN=STYLE")?;
+    let column = column
+        .parse()
+        .map_err(|_| "column must be a number".to_string())?;
+    Ok((column, parse_style(style)?))
+}
+
+#[cfg(feature = "regex")]
+fn parse_match_style(arg: &str) -> Result<(regex::Regex, lineup::Style), String> {
+    let (regex, style) = arg
+        .rsplit_once('=')
+        .ok_or("match style must be REGEX=STYLE")?;
+    let regex = regex::Regex::new(regex).map_err(|e| e.to_string())?;
+    Ok((regex, parse_style(style)?))
+}
+
+/// Parse a pipeline stage, e.g. 'take=10'
+fn parse_stage(arg: &str) -> Result<lineup::Stage, String> {
+    arg.parse().map_err(|e: lineup::LineupError| e.to_string())
+}
+
+/// Parse sort options, e.g. 'numeric,reverse'
+fn parse_sort(arg: &str) -> Result<lineup::Sort, String> {
+    arg.parse().map_err(|e: lineup::LineupError| e.to_string())
+}
+
+/// Parse an item template, checking it is valid
+fn parse_template(arg: &str) -> Result<lineup::Template, String> {
+    lineup::Template::new(arg).map_err(|e| e.to_string())
+}
+
+#[derive(Clone, Debug)]
+/// Input and output formats out of a format spec
+struct FormatSpec(lineup::InFormat, lineup::OutFormat);
+
+/// Parse a format spec into input and output formats
+fn parse_format(arg: &str) -> Result<FormatSpec, String> {
+    let in_fmt = arg
+        .parse()
+        .map_err(|e: lineup::LineupError| e.to_string())?;
+    let out_fmt = arg
+        .parse()
+        .map_err(|e: lineup::LineupError| e.to_string())?;
+    Ok(FormatSpec(in_fmt, out_fmt))
+}
+
+#[cfg(feature = "regex")]
+/// Parse a regular expression, checking it is valid
+fn parse_regex(arg: &str) -> Result<String, String> {
+    compile_regex(arg).map(|_| arg.to_string())
+}
+
+#[cfg(feature = "regex")]
+/// Parse a regular expression into the matcher of it
+fn compile_regex(arg: &str) -> Result<regex::Regex, String> {
+    regex::Regex::new(arg).map_err(|e| e.to_string())
+}
+
+/// Parse a record length, in bytes (N) or characters (Nc), checking it is > 0
+fn parse_record_length(arg: &str) -> Result<lineup::RecordLength, String> {
+    let length = match arg.strip_suffix('c') {
+        Some(chars) => chars.parse().map(lineup::RecordLength::Chars),
+        None => arg.parse().map(lineup::RecordLength::Bytes),
+    };
+    match length {
+        Ok(lineup::RecordLength::Bytes(0) | lineup::RecordLength::Chars(0)) | Err(_) => {
+            Err("record length must be a number > 0, optionally followed by 'c'".to_string())
+        }
+        Ok(length) => Ok(length),
+    }
+}
+
+/// Parse a field width, checking it is > 0
+fn parse_field_width(arg: &str) -> Result<usize, String> {
+    match arg.parse() {
+        Ok(width) if width > 0 => Ok(width),
+        _ => Err("field width must be a number > 0".to_string()),
+    }
 }
 
 impl InputItemSeparator {
     pub const LONG_HELP: &'static str = r#"IN FORMAT: input item separator, possible values:
+  auto: detected from the beginning of input, among comma (CSV), tab (TSV), semicolon, pipe and whitespace
   N:   N is fixed number of bytes per item, no explicit item separator; NOTE N must be > 0 and boundary of a UTF-8 code point for each item
-  SEP: SEP is a string used to separate items; SEP cannot start with a digit"#;
+  Nc:  N is fixed number of characters (Unicode scalar values) per item, no explicit item separator; NOTE N must be > 0
+  Ng:  N is fixed number of grapheme clusters per item, no explicit item separator; NOTE N must be > 0
+  SEP: SEP is a string used to separate items; SEP cannot be empty nor start with a digit"#;
 
     pub fn parse(arg: &str) -> Result<Self, String> {
-        if let Ok(char_count) = arg.parse() {
-            if char_count > 0 {
-                Ok(Self::ByteCount(char_count))
+        if arg == "auto" {
+            return Ok(Self::Auto);
+        }
+        #[cfg(feature = "unicode-segmentation")]
+        if let Some(Ok(grapheme_count)) = arg.strip_suffix('g').map(str::parse) {
+            return if grapheme_count > 0 {
+                Ok(Self::GraphemeCount(grapheme_count))
+            } else {
+                Err("number of grapheme clusters per item must be > 0".to_string())
+            };
+        }
+        if let Ok(byte_count) = arg.parse() {
+            if byte_count > 0 {
+                Ok(Self::ByteCount(byte_count))
             } else {
                 Err("number of bytes per item must be > 0".to_string())
             }
+        } else if let Some(Ok(char_count)) = arg.strip_suffix('c').map(str::parse) {
+            if char_count > 0 {
+                Ok(Self::CharCount(char_count))
+            } else {
+                Err("number of characters per item must be > 0".to_string())
+            }
+        } else if arg.is_empty() {
+            Err("item separator must not be empty".to_string())
         } else {
             Ok(Self::Explicit(arg.to_string()))
         }
     }
 }
 
+impl OutputSpan {
+    pub fn parse(arg: &str) -> Result<Self, String> {
+        if arg == "auto" {
+            Ok(Self::Auto)
+        } else {
+            arg.split(',')
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map(Self::Fixed)
+                .map_err(|_| "span must be a number, a list of numbers or 'auto'".to_string())
+        }
+    }
+}
+
+impl OutputLineN {
+    pub fn parse(arg: &str) -> Result<Self, String> {
+        if arg == "fit" {
+            Ok(Self::Fit)
+        } else {
+            arg.parse()
+                .map(Self::Fixed)
+                .map_err(|_| "line-n must be a number or 'fit'".to_string())
+        }
+    }
+}
+
 impl From<InputItemSeparator> for lineup::ItemSeparator {
     fn from(s: InputItemSeparator) -> Self {
         match s {
             InputItemSeparator::Explicit(e) => lineup::ItemSeparator::Explicit(e),
             InputItemSeparator::ByteCount(b) => lineup::ItemSeparator::ByteCount(b),
+            InputItemSeparator::CharCount(c) => lineup::ItemSeparator::CharCount(c),
+            #[cfg(feature = "unicode-segmentation")]
+            InputItemSeparator::GraphemeCount(g) => lineup::ItemSeparator::GraphemeCount(g),
+            InputItemSeparator::Auto => lineup::ItemSeparator::default(),
         }
     }
 }
@@ -80,6 +757,8 @@ impl From<InputItemSeparator> for lineup::ItemSeparator {
 enum Anchor {
     Right,
     Left,
+    Center,
+    Decimal,
 }
 
 impl From<lineup::Anchor> for Anchor {
@@ -87,53 +766,647 @@ impl From<lineup::Anchor> for Anchor {
         match a {
             lineup::Anchor::Left => Anchor::Left,
             lineup::Anchor::Right => Anchor::Right,
+            lineup::Anchor::Center(_) => Anchor::Center,
+            lineup::Anchor::Decimal { .. } => Anchor::Decimal,
         }
     }
 }
 
-impl From<Anchor> for lineup::Anchor {
-    fn from(a: Anchor) -> Self {
-        match a {
+impl Args {
+    /// Input item separator, field widths first, then regular expression, then whitespace
+    fn item_separator(&self) -> lineup::ItemSeparator {
+        if !self.in_field_widths.is_empty() {
+            return lineup::ItemSeparator::FieldWidths(self.in_field_widths.clone());
+        }
+        #[cfg(feature = "regex")]
+        if let Some(regex) = &self.in_separator_regex {
+            return lineup::ItemSeparator::Regex(regex.clone());
+        }
+        if self.in_split_whitespace {
+            return lineup::ItemSeparator::Whitespace;
+        }
+        self.in_separator.clone().into()
+    }
+
+    /// Numeric formatting, if any setting is given
+    fn number(&self) -> Option<lineup::NumberFormat> {
+        let mut number = NumberFormatBuilder::default();
+        let mut enabled = false;
+        #[cfg(feature = "locale")]
+        if let Some(locale) = &self.locale {
+            number.locale(locale).expect("locale validated by parser");
+            enabled = true;
+        }
+        if let Some(grouping) = self.out_number_grouping {
+            number.grouping(Some(grouping));
+            enabled = true;
+        }
+        if !enabled && self.out_number_precision.is_none() && self.out_number_notation.is_none() {
+            return None;
+        }
+        Some(
+            number
+                .precision(self.out_number_precision)
+                .notation(self.out_number_notation.unwrap_or(Notation::Fixed).into())
+                .build()
+                .unwrap(),
+        )
+    }
+
+    /// Style rules, match rules first, if styles are to be emitted at all
+    fn styles(&self) -> Vec<StyleRule> {
+        let enabled = match self.color {
+            ColorWhen::Always => true,
+            ColorWhen::Never => false,
+            ColorWhen::Auto => {
+                self.output == Path::new("-")
+                    && std::io::stdout().is_terminal()
+                    && std::env::var_os("NO_COLOR").is_none()
+            }
+        };
+        if !enabled {
+            return Vec::new();
+        }
+        let column_rules = self
+            .out_style_column
+            .iter()
+            .map(|(column, style)| StyleRule::new(Selector::Column(*column), *style));
+        #[cfg(feature = "regex")]
+        let column_rules = self
+            .out_style_match
+            .iter()
+            .map(|(regex, style)| StyleRule::new(Selector::Matches(regex.clone()), *style))
+            .chain(column_rules);
+        column_rules.collect()
+    }
+
+    fn anchor(&self, anchor: Anchor) -> lineup::Anchor {
+        match anchor {
             Anchor::Left => lineup::Anchor::Left,
             Anchor::Right => lineup::Anchor::Right,
+            Anchor::Center => lineup::Anchor::Center(self.out_odd_pad.into()),
+            Anchor::Decimal => lineup::Anchor::Decimal {
+                point: self.out_decimal_point,
+                fraction: self.out_decimal_fraction,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
+enum Side {
+    Left,
+    Right,
+}
+
+impl From<Side> for lineup::Side {
+    fn from(s: Side) -> Self {
+        match s {
+            Side::Left => lineup::Side::Left,
+            Side::Right => lineup::Side::Right,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
+enum OverflowPolicy {
+    Overflow,
+    Truncate,
+    Error,
+    Wrap,
+}
+
+impl From<OverflowPolicy> for lineup::OverflowPolicy {
+    fn from(o: OverflowPolicy) -> Self {
+        match o {
+            OverflowPolicy::Overflow => lineup::OverflowPolicy::Overflow,
+            OverflowPolicy::Truncate => lineup::OverflowPolicy::Truncate,
+            OverflowPolicy::Error => lineup::OverflowPolicy::Error,
+            OverflowPolicy::Wrap => lineup::OverflowPolicy::Wrap,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
+enum FillOrder {
+    Row,
+    Column,
+}
+
+impl From<FillOrder> for lineup::FillOrder {
+    fn from(f: FillOrder) -> Self {
+        match f {
+            FillOrder::Row => lineup::FillOrder::RowMajor,
+            FillOrder::Column => lineup::FillOrder::ColumnMajor,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
+enum OutputMode {
+    Plain,
+    Csv,
+    Json,
+    JsonLines,
+}
+
+impl From<OutputMode> for lineup::OutputMode {
+    fn from(m: OutputMode) -> Self {
+        match m {
+            OutputMode::Plain => lineup::OutputMode::Plain,
+            OutputMode::Csv => lineup::OutputMode::Csv,
+            OutputMode::Json => lineup::OutputMode::Json,
+            OutputMode::JsonLines => lineup::OutputMode::JsonLines,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
+enum Notation {
+    Fixed,
+    Scientific,
+}
+
+impl From<Notation> for lineup::Notation {
+    fn from(n: Notation) -> Self {
+        match n {
+            Notation::Fixed => lineup::Notation::Fixed,
+            Notation::Scientific => lineup::Notation::Scientific,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
+enum Case {
+    Preserve,
+    Upper,
+    Lower,
+    Title,
+}
+
+impl From<Case> for lineup::Case {
+    fn from(c: Case) -> Self {
+        match c {
+            Case::Preserve => lineup::Case::Preserve,
+            Case::Upper => lineup::Case::Upper,
+            Case::Lower => lineup::Case::Lower,
+            Case::Title => lineup::Case::Title,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
+enum EmptyItems {
+    Terminate,
+    Skip,
+    Yield,
+}
+
+impl From<EmptyItems> for lineup::EmptyItems {
+    fn from(e: EmptyItems) -> Self {
+        match e {
+            EmptyItems::Terminate => lineup::EmptyItems::Terminate,
+            EmptyItems::Skip => lineup::EmptyItems::Skip,
+            EmptyItems::Yield => lineup::EmptyItems::Yield,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
+enum InputMode {
+    Plain,
+    Csv,
+    Tsv,
+    Json,
+    JsonLines,
+}
+
+impl From<InputMode> for lineup::InputMode {
+    fn from(m: InputMode) -> Self {
+        match m {
+            InputMode::Plain => lineup::InputMode::Plain,
+            InputMode::Csv => lineup::InputMode::Csv,
+            InputMode::Tsv => lineup::InputMode::Tsv,
+            InputMode::Json => lineup::InputMode::Json,
+            InputMode::JsonLines => lineup::InputMode::JsonLines,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
+enum PartialChunk {
+    Drop,
+    Yield,
+    Error,
+}
+
+impl From<PartialChunk> for lineup::PartialChunk {
+    fn from(p: PartialChunk) -> Self {
+        match p {
+            PartialChunk::Drop => lineup::PartialChunk::Drop,
+            PartialChunk::Yield => lineup::PartialChunk::Yield,
+            PartialChunk::Error => lineup::PartialChunk::Error,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
+enum InvalidUtf8 {
+    Error,
+    Replace,
+}
+
+impl From<InvalidUtf8> for lineup::InvalidUtf8 {
+    fn from(i: InvalidUtf8) -> Self {
+        match i {
+            InvalidUtf8::Error => lineup::InvalidUtf8::Error,
+            InvalidUtf8::Replace => lineup::InvalidUtf8::Replace,
         }
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
+enum ByteRounding {
+    Error,
+    Forward,
+    Backward,
+}
+
+impl From<ByteRounding> for lineup::ByteRounding {
+    fn from(r: ByteRounding) -> Self {
+        match r {
+            ByteRounding::Error => lineup::ByteRounding::Error,
+            ByteRounding::Forward => lineup::ByteRounding::Forward,
+            ByteRounding::Backward => lineup::ByteRounding::Backward,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
+enum ColorWhen {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
+enum QuotePolicy {
+    Never,
+    WhenNeeded,
+    Always,
+}
+
+impl From<QuotePolicy> for lineup::QuotePolicy {
+    fn from(q: QuotePolicy) -> Self {
+        match q {
+            QuotePolicy::Never => lineup::QuotePolicy::Never,
+            QuotePolicy::WhenNeeded => lineup::QuotePolicy::WhenNeeded,
+            QuotePolicy::Always => lineup::QuotePolicy::Always,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
+enum QuoteEscape {
+    Doubled,
+    Backslash,
+}
+
+impl From<QuoteEscape> for lineup::QuoteEscape {
+    fn from(e: QuoteEscape) -> Self {
+        match e {
+            QuoteEscape::Doubled => lineup::QuoteEscape::Doubled,
+            QuoteEscape::Backslash => lineup::QuoteEscape::Backslash,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
+enum Width {
+    Chars,
+    #[cfg(feature = "unicode-width")]
+    Cells,
+    #[cfg(feature = "unicode-segmentation")]
+    Graphemes,
+}
+
+impl From<Width> for lineup::Width {
+    fn from(w: Width) -> Self {
+        match w {
+            Width::Chars => lineup::Width::Chars,
+            #[cfg(feature = "unicode-width")]
+            Width::Cells => lineup::Width::Cells,
+            #[cfg(feature = "unicode-segmentation")]
+            Width::Graphemes => lineup::Width::Graphemes,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum OutputSpan {
+    /// fixed spans, one per column position; a single 0 means no span
+    Fixed(Vec<usize>),
+    /// span computed from the widest item
+    Auto,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum OutputLineN {
+    /// fixed number of items per line; 0 means no line separation
+    Fixed(usize),
+    /// as many items as fit the terminal width
+    Fit,
+}
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 enum InputItemSeparator {
     /// explicit item separator
     Explicit(String),
     /// item fixed byte size, no explicit separator
     ByteCount(usize),
+    /// item fixed size in characters, no explicit separator
+    CharCount(usize),
+    #[cfg(feature = "unicode-segmentation")]
+    /// item fixed size in grapheme clusters, no explicit separator
+    GraphemeCount(usize),
+    /// detected from input (see [Config::detect_in_format])
+    Auto,
 }
 
 impl Config {
     pub fn new() -> Self {
-        let args = Args::parse();
-        Self {
+        let matches = Args::command().get_matches();
+        let mut args = Args::from_arg_matches(&matches)
+            .unwrap_or_else(|error| error.format(&mut Args::command()).exit());
+        #[cfg(feature = "toml")]
+        if let Some(preset) = &args.preset {
+            // arguments given on the command line take the place of preset ones, unless they
+            // can be repeated
+            let command = Args::command();
+            let given = |name: &str| {
+                let arg = command
+                    .get_arguments()
+                    .find(|arg| arg.get_long() == Some(name));
+                arg.is_some_and(|arg| {
+                    let source = matches.value_source(arg.get_id().as_str());
+                    source == Some(clap::parser::ValueSource::CommandLine)
+                        && !matches!(
+                            arg.get_action(),
+                            clap::ArgAction::Append | clap::ArgAction::Count
+                        )
+                })
+            };
+            let preset_args =
+                preset_args(args.config.as_deref(), preset, given).unwrap_or_else(|error| {
+                    Args::command()
+                        .error(clap::error::ErrorKind::InvalidValue, error)
+                        .exit()
+                });
+            let mut cli_args = std::env::args_os();
+            let program = cli_args.next();
+            args = Args::parse_from(program.into_iter().chain(preset_args).chain(cli_args));
+        }
+        if args.tsv {
+            args.in_mode = InputMode::Tsv;
+            args.out_separator = "\t".to_string();
+            args.out_line_separator = "\n".to_string();
+        }
+        if args.in_null {
+            args.in_separator = InputItemSeparator::Explicit("\0".to_string());
+            args.in_empty_items = EmptyItems::Skip;
+        }
+        if args.out_null {
+            args.out_separator = "\0".to_string();
+            args.out_trailing_separator = true;
+        }
+        let format = args.format.take();
+        let anchor = args.anchor(args.out_anchor.unwrap_or(Anchor::Left));
+        let column_anchors = args
+            .out_column_anchors
+            .iter()
+            .map(|anchor| args.anchor(*anchor))
+            .collect();
+        let item_span = |span| {
+            ItemSpanBuilder::default()
+                .span(span)
+                .pad(args.out_pad)
+                .anchor(anchor)
+                .overflow(args.out_overflow.into())
+                .build()
+                .unwrap()
+        };
+        let styles = args.styles();
+        let number = args.number();
+        let fit_line = args.out_line_n == OutputLineN::Fit;
+        let rows = args.tsv && args.out_line_n == OutputLineN::Fixed(0);
+        let transpose = match (args.transpose, &args.out_line_n) {
+            (false, _) => None,
+            (true, OutputLineN::Fixed(n)) => Some(*n),
+            (true, OutputLineN::Fit) => Args::command()
+                .error(
+                    clap::error::ErrorKind::ArgumentConflict,
+                    "'--transpose' needs a number of items per line, not 'fit'",
+                )
+                .exit(),
+        };
+        let standard = |path: &PathBuf| (path != Path::new("-")).then(|| path.clone());
+        let inputs: Vec<_> = args.input.iter().map(standard).collect();
+        let output = standard(&args.output);
+        let same_file = |input: &Option<PathBuf>| match (input, &output) {
+            (Some(input), Some(output)) => match (input.canonicalize(), output.canonicalize()) {
+                (Ok(input), Ok(output)) => input == output,
+                _ => false,
+            },
+            _ => false,
+        };
+        if let Some(input) = inputs
+            .iter()
+            .flatten()
+            .find(|input| same_file(&Some(input.into())))
+        {
+            Args::command()
+                .error(
+                    clap::error::ErrorKind::ArgumentConflict,
+                    format!("'--output' is input file {}", input.display()),
+                )
+                .exit()
+        }
+        let mut stages = Vec::new();
+        #[cfg(feature = "regex")]
+        {
+            stages.extend(args.filter.drain(..).map(lineup::Stage::Keep));
+            stages.extend(args.filter_out.drain(..).map(lineup::Stage::Discard));
+        }
+        stages.append(&mut args.stage);
+        if let Some(mut sort) = args.sort {
+            if let (Some(_), 0, OutputLineN::Fixed(n)) =
+                (sort.column, sort.row_length, &args.out_line_n)
+            {
+                sort.row_length = *n;
+            }
+            stages.push(lineup::Stage::Sort(sort));
+        }
+        let single_row = |stage: &lineup::Stage| match stage {
+            lineup::Stage::Sort(sort) => sort.column.is_some() && sort.row_length == 0,
+            _ => false,
+        };
+        if stages.iter().any(single_row) {
+            Args::command()
+                .error(
+                    clap::error::ErrorKind::ArgumentConflict,
+                    "sorting rows by 'column' needs a number of items per row, 'row=N' or \
+                     '--out-line-n N' with '--sort'",
+                )
+                .exit()
+        }
+        let auto_span = args.out_span == OutputSpan::Auto
+            || (fit_line && args.out_span == OutputSpan::Fixed(vec![0]));
+        let detect_in_format = format.is_none()
+            && args.in_separator == InputItemSeparator::Auto
+            && args.item_separator() == lineup::ItemSeparator::default()
+            && matches!(args.in_mode, InputMode::Plain);
+        let mut config = Self {
             in_fmt: lineup::InFormatBuilder::default()
-                .item_separator(args.in_separator.into())
-                .line_separator(Self::line_separator(args.in_line_n, args.in_line_separator))
+                .item_separator(args.item_separator())
+                .line_separator(match args.in_record_length {
+                    Some(length) => Some(LineSeparator::by_length(length, args.in_line_separator)),
+                    None => Self::line_separator(args.in_line_n, args.in_line_separator),
+                })
+                .quote(args.in_quote)
+                .quote_escape(args.in_quote_escape.into())
+                .escape(args.in_escape)
+                .trim(args.trim)
+                .empty_items(args.in_empty_items.into())
+                .partial_chunk(args.in_partial_chunk.into())
+                .byte_rounding(args.in_byte_rounding.into())
+                .invalid_utf8(args.in_invalid_utf8.into())
+                .mode(args.in_mode.into())
+                .skip_lines(args.in_skip_lines)
+                .header(args.in_header)
+                .comment(args.in_comment)
+                .skip_items(args.in_skip_items)
+                .max_items(args.in_max_items)
+                .strip_bom(!args.in_keep_bom)
+                .max_item_length(args.in_max_item_length)
+                .expected_items(args.in_expect_items)
+                .expected_items_per_line(args.in_expect_line_items)
                 .build()
-                .unwrap(),
+                .unwrap_or_else(|error| {
+                    Args::command()
+                        .error(clap::error::ErrorKind::ValueValidation, error)
+                        .exit()
+                }),
             out_fmt: lineup::OutFormatBuilder::default()
-                .span(if args.out_span == 0 {
-                    None
-                } else {
-                    Some(ItemSpan::new(
-                        args.out_span,
-                        args.out_pad,
-                        args.out_anchor.into(),
-                    ))
+                // span width is replaced by the widest item's once aligned
+                .span(match &args.out_span {
+                    _ if auto_span => Some(item_span(1)),
+                    OutputSpan::Fixed(spans) if spans.len() > 1 || spans[0] == 0 => None,
+                    OutputSpan::Fixed(spans) => Some(item_span(spans[0])),
+                    OutputSpan::Auto => Some(item_span(1)),
                 })
-                .line_separator(Self::line_separator(
-                    args.out_line_n,
-                    args.out_line_separator,
+                .column_spans(match &args.out_span {
+                    OutputSpan::Fixed(spans) if spans.len() > 1 => {
+                        spans.iter().copied().map(item_span).collect()
+                    }
+                    _ => Vec::new(),
+                })
+                .column_pads(args.out_column_pads)
+                .column_anchors(column_anchors)
+                .detect_anchors(auto_span && args.out_anchor.is_none())
+                .line_separator(match (args.out_line_width, args.out_line_n) {
+                    (Some(width), _) => {
+                        Some(LineSeparator::by_width(width, args.out_line_separator))
+                    }
+                    (None, OutputLineN::Fixed(n)) => {
+                        Self::line_separator(n, args.out_line_separator)
+                    }
+                    // items per line are only known once laid out (see line_width)
+                    (None, OutputLineN::Fit) => {
+                        Some(LineSeparator::new(1, args.out_line_separator))
+                    }
+                })
+                .number(number)
+                .case(args.out_case.into())
+                .line_prefix(args.out_line_prefix)
+                .line_suffix(args.out_line_suffix)
+                .page_separator(
+                    (args.out_page_n > 0)
+                        .then(|| PageSeparator::new(args.out_page_n, args.out_page_separator)),
+                )
+                .item_prefix(args.out_item_prefix)
+                .item_suffix(args.out_item_suffix)
+                .template(args.out_template)
+                .fill_order(args.out_fill.into())
+                .mode(args.out_mode.into())
+                .quoting(Quoting::new(
+                    args.out_quote.into(),
+                    args.out_quote_char,
+                    args.out_quote_escape.into(),
                 ))
+                .styles(styles)
+                .fill_last_line(args.out_fill_last_line)
+                .bidi_isolate(args.out_bidi_isolate)
+                .trailing_separator(args.out_trailing_separator)
+                .header(args.out_header)
+                .footer(args.out_footer)
+                .width(args.out_width.into())
+                .ellipsis(args.out_ellipsis.unwrap_or_default())
                 .item_separator(args.out_separator)
+                .tab_stops(match (args.out_tab_stops, args.out_tabs) {
+                    (Some(width), _) => Some(TabStops::Spaces(width)),
+                    (None, true) => Some(TabStops::Tabs),
+                    (None, false) => None,
+                })
                 .build()
                 .unwrap(),
+            auto_span,
+            fit_line,
+            detect_in_format,
+            stages,
+            transpose,
+            rows,
+            stats: args.stats,
+            check: args.check,
+            inputs,
+            reset_per_file: args.reset_per_file,
+            output,
+            #[cfg(feature = "rayon")]
+            jobs: args.jobs,
+            #[cfg(feature = "encoding")]
+            in_encoding: args.in_encoding,
+            #[cfg(feature = "encoding")]
+            out_encoding: args.out_encoding,
+        };
+        if let Some(FormatSpec(in_fmt, out_fmt)) = format {
+            config.in_fmt = in_fmt;
+            config.out_fmt = lineup::OutFormat {
+                column_spans: config.out_fmt.column_spans,
+                column_anchors: config.out_fmt.column_anchors,
+                detect_anchors: config.out_fmt.detect_anchors,
+                column_pads: config.out_fmt.column_pads,
+                width: config.out_fmt.width,
+                column_separators: config.out_fmt.column_separators,
+                number: config.out_fmt.number,
+                styles: config.out_fmt.styles,
+                header: config.out_fmt.header,
+                footer: config.out_fmt.footer,
+                ..out_fmt
+            };
+        }
+        config
+    }
+
+    /// Detect input item separator, line separator and mode from a `sample` of input, if they
+    /// are to be detected; they are left as they are if none is found
+    pub fn detect_in_format(&mut self, sample: &str) {
+        if !self.detect_in_format {
+            return;
+        }
+        if let Some(detected) = lineup::detect_format(sample) {
+            self.in_fmt.item_separator = detected.item_separator;
+            self.in_fmt.line_separator = detected.line_separator;
+            self.in_fmt.mode = detected.mode;
+            self.in_fmt.quote = self.in_fmt.quote.or(detected.quote);
         }
     }
 
@@ -145,12 +1418,162 @@ impl Config {
         self.out_fmt.clone()
     }
 
-    pub fn istream(&self) -> impl std::io::Read {
-        std::io::stdin()
+    /// Pipeline items go through between reading and writing
+    pub fn pipeline(&self) -> lineup::Pipeline {
+        self.stages.iter().cloned().collect()
+    }
+
+    /// Number of items per row, 0 for a single row, if items are to be transposed
+    pub fn transpose(&self) -> Option<usize> {
+        self.transpose
+    }
+
+    /// Whether items can be written as soon as they are read, in constant memory, rather than
+    /// once all of input is read: nothing requires all items, nor whole input to be decoded
+    pub fn streaming(&self) -> bool {
+        #[cfg(feature = "rayon")]
+        if self.jobs.is_some() {
+            return false;
+        }
+        #[cfg(feature = "encoding")]
+        if self.in_encoding.is_some() || self.out_encoding.is_some() {
+            return false;
+        }
+        !(self.auto_span
+            || self.fit_line
+            || self.detect_in_format
+            || self.transpose.is_some()
+            || self.stats
+            || self.check
+            || self.in_fmt.invalid_utf8 == lineup::InvalidUtf8::Replace)
+    }
+
+    /// Whether items of each input record are to be written on a line of their own, going
+    /// through the pipeline on their own
+    pub fn rows(&self) -> bool {
+        self.rows
     }
 
-    pub fn ostream(&self) -> impl std::io::Write {
-        std::io::stdout()
+    /// Whether input items are to be reported on rather than written
+    pub fn stats(&self) -> bool {
+        self.stats
+    }
+
+    /// Whether input is only to be checked against input format
+    pub fn check(&self) -> bool {
+        self.check
+    }
+
+    #[cfg(feature = "rayon")]
+    /// Number of threads items are to be read and formatted by in parallel, 0 for as many as
+    /// CPUs, if any
+    pub fn jobs(&self) -> Option<usize> {
+        self.jobs
+    }
+
+    pub fn auto_span(&self) -> bool {
+        self.auto_span
+    }
+
+    /// Width lines are to fit in, if items per line are to be computed from it: the terminal
+    /// width when output is a terminal, otherwise COLUMNS environment variable or 80
+    pub fn line_width(&self) -> Option<usize> {
+        self.fit_line.then(|| {
+            self.output
+                .is_none()
+                .then(terminal_size::terminal_size)
+                .flatten()
+                .map(|(terminal_size::Width(width), _)| width as usize)
+                .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+                .unwrap_or(80)
+        })
+    }
+
+    /// Input streams to be read on their own: one per input file, if reset per file, otherwise
+    /// a single one of all input files in sequence; each file is opened once the previous one
+    /// is read
+    pub fn istreams(
+        &self,
+    ) -> impl Iterator<Item = Result<Box<dyn std::io::Read>, std::io::Error>> + '_ {
+        let (each, all) = if self.reset_per_file {
+            (Some(self.inputs.iter().map(open)), None)
+        } else {
+            let all: Box<dyn std::io::Read> = Box::new(Concat {
+                inputs: self.inputs.clone().into_iter(),
+                current: None,
+            });
+            (None, Some(Ok(all)))
+        };
+        each.into_iter().flatten().chain(all)
+    }
+
+    /// Whole input of each input stream (see [Config::istreams]), decoded as per input
+    /// encoding, if any, or UTF-8; invalid input is an error or is replaced as per input format
+    pub fn inputs(&self) -> Result<Vec<String>, std::io::Error> {
+        self.istreams()
+            .map(|istream| self.decode(istream?))
+            .collect()
+    }
+
+    /// Name of each input file, in the order they are read
+    pub fn input_names(&self) -> impl Iterator<Item = String> + '_ {
+        self.inputs.iter().map(|input| match input {
+            Some(path) => path.display().to_string(),
+            None => "-".to_string(),
+        })
+    }
+
+    /// Whole `istream`, decoded (see [Config::inputs])
+    fn decode(&self, mut istream: impl std::io::Read) -> Result<String, std::io::Error> {
+        let mut bytes = Vec::new();
+        istream.read_to_end(&mut bytes)?;
+        let replace = self.in_fmt.invalid_utf8 == lineup::InvalidUtf8::Replace;
+        #[cfg(feature = "encoding")]
+        if let Some(encoding) = self.in_encoding {
+            let (input, encoding, malformed) = encoding.decode(&bytes);
+            if malformed && !replace {
+                let error = format!("input is not valid {}", encoding.name());
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, error));
+            }
+            return Ok(input.into_owned());
+        }
+        if replace {
+            Ok(String::from_utf8_lossy(&bytes).into_owned())
+        } else {
+            String::from_utf8(bytes)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+        }
+    }
+
+    #[cfg(feature = "encoding")]
+    pub fn out_encoding(&self) -> Option<&'static encoding_rs::Encoding> {
+        self.out_encoding
+    }
+
+    #[cfg(feature = "encoding")]
+    /// `output` encoded as per output encoding, if any
+    pub fn encode<'o>(&self, output: &'o str) -> std::borrow::Cow<'o, [u8]> {
+        use std::borrow::Cow;
+        match self.out_encoding {
+            Some(encoding) if encoding == encoding_rs::UTF_16LE => {
+                Cow::Owned(output.encode_utf16().flat_map(u16::to_le_bytes).collect())
+            }
+            Some(encoding) if encoding == encoding_rs::UTF_16BE => {
+                Cow::Owned(output.encode_utf16().flat_map(u16::to_be_bytes).collect())
+            }
+            Some(encoding) => encoding.encode(output).0,
+            None => Cow::Borrowed(output.as_bytes()),
+        }
+    }
+
+    /// Output file, or standard output locked once, buffered as items are written in many
+    /// small pieces; it is to be flushed once done
+    pub fn ostream(&self) -> Result<impl std::io::Write, std::io::Error> {
+        let ostream: Box<dyn std::io::Write> = match &self.output {
+            Some(path) => Box::new(std::fs::File::create(path).map_err(|e| at(path, e))?),
+            None => Box::new(std::io::stdout().lock()),
+        };
+        Ok(std::io::BufWriter::new(ostream))
     }
 
     fn line_separator(n: usize, sep: String) -> Option<LineSeparator> {
@@ -161,3 +1584,43 @@ impl Config {
         }
     }
 }
+
+/// I/O `error` on file at `path`, mentioning it
+fn at(path: &Path, error: std::io::Error) -> std::io::Error {
+    std::io::Error::new(error.kind(), format!("{}: {error}", path.display()))
+}
+
+/// Input file at `path`, or standard input
+fn open(path: &Option<PathBuf>) -> Result<Box<dyn std::io::Read>, std::io::Error> {
+    match path {
+        Some(path) => Ok(Box::new(
+            std::fs::File::open(path).map_err(|e| at(path, e))?,
+        )),
+        None => Ok(Box::new(std::io::stdin())),
+    }
+}
+
+/// Input files read in sequence as a single input, each opened once the previous one is read
+struct Concat {
+    inputs: std::vec::IntoIter<Option<PathBuf>>,
+    current: Option<Box<dyn std::io::Read>>,
+}
+
+impl std::io::Read for Concat {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
+        loop {
+            let current = match &mut self.current {
+                Some(current) => current,
+                None => match self.inputs.next() {
+                    Some(input) => self.current.insert(open(&input)?),
+                    None => return Ok(0),
+                },
+            };
+            let read = current.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            self.current = None;
+        }
+    }
+}
@@ -1,24 +1,179 @@
-#![feature(let_chains)]
 #![feature(type_alias_impl_trait)]
 
 use derive_new::new as New;
+use std::borrow::Cow;
 use std::fmt::Display;
+use std::sync::Arc;
 
 #[macro_use]
 extern crate derive_builder;
 
 #[derive(Clone, Debug, Builder)]
-#[builder(derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "InFormatBuilder"),
+    builder_struct_attr(derive(serde::Deserialize))
+)]
+#[builder(
+    derive(Debug),
+    build_fn(validate = "Self::validate", error = "LineupError")
+)]
 pub struct InFormat {
     #[builder(default = "ItemSeparator::default()")]
     pub item_separator: ItemSeparator,
 
     #[builder(default = "None")]
     pub line_separator: Option<LineSeparator>,
+
+    #[builder(default = "None")]
+    /// Quote character: item and line separators within items wrapped in it are not boundaries;
+    /// items are read as in input, quotes included (see [unquote])
+    ///
+    /// [unquote]: InFormat::unquote
+    pub quote: Option<char>,
+
+    #[builder(default = "QuoteEscape::default()")]
+    /// How quote characters within quoted items are escaped
+    pub quote_escape: QuoteEscape,
+
+    #[builder(default = "None")]
+    /// Escape character: item and line separators, and escape characters, following it outside
+    /// quoted items are not boundaries; items are read as in input, escape characters included
+    /// (see [unquote])
+    ///
+    /// [unquote]: InFormat::unquote
+    pub escape: Option<char>,
+
+    #[builder(default = "false")]
+    /// Trim leading and trailing whitespace from items read
+    pub trim: bool,
+
+    #[builder(default = "EmptyItems::default()")]
+    /// What empty items between separators, trimmed ones included when skipping, amount to
+    pub empty_items: EmptyItems,
+
+    #[builder(default = "PartialChunk::default()")]
+    /// What a trailing chunk of input shorter than fixed size items amounts to
+    pub partial_chunk: PartialChunk,
+
+    #[builder(default = "InvalidUtf8::default()")]
+    /// What invalid UTF-8 within items read from bytes amounts to (see [read_bytes])
+    pub invalid_utf8: InvalidUtf8,
+
+    #[builder(default = "ByteRounding::default()")]
+    /// What fixed byte size item boundaries within a UTF-8 code point amount to
+    pub byte_rounding: ByteRounding,
+
+    #[builder(default = "InputMode::default()")]
+    /// How input is made of items (see [InputMode])
+    ///
+    /// [InputMode]: crate::InputMode
+    pub mode: InputMode,
+
+    #[builder(default = "0")]
+    /// Number of input lines, ended by a new line, to be skipped before reading items
+    pub skip_lines: usize,
+
+    #[builder(default = "false")]
+    /// The first input line, after skipped ones, is read as header labels instead of items, as
+    /// per format (see [ItemReader::header])
+    pub header: bool,
+
+    #[builder(default = "None")]
+    /// Prefix of comment lines, e.g. "#": input lines starting with it are ignored, where items
+    /// start at the beginning of a line
+    pub comment: Option<String>,
+
+    #[builder(default = "0")]
+    /// Number of leading items to be discarded
+    pub skip_items: usize,
+
+    #[builder(default = "None")]
+    /// Maximum number of items to be read, after discarded ones; reading stops then
+    pub max_items: Option<usize>,
+
+    #[builder(default = "false")]
+    /// Strip a leading byte order mark (U+FEFF), so that it does not end up in the first item
+    pub strip_bom: bool,
+
+    #[builder(default = "None")]
+    /// Maximum length of items, in chars, as read (quotes included); longer ones are an error
+    /// (see [ReadError::ItemTooLong])
+    pub max_item_length: Option<usize>,
+
+    #[builder(default = "None")]
+    /// Expected number of items in input, skipped empty ones excluded; more or fewer are an
+    /// error (see [ReadError::ItemCount])
+    pub expected_items: Option<usize>,
+
+    #[builder(default = "None")]
+    /// Expected number of items on each input line with any, where lines end with a new line
+    /// ending their last item, i.e. not in JSON modes; more or fewer are an error (see
+    /// [ReadError::LineItemCount])
+    pub expected_items_per_line: Option<usize>,
+}
+
+impl InFormatBuilder {
+    /// Fail building formats whose separators cannot split input
+    fn validate(&self) -> Result<(), LineupError> {
+        let invalid = |error: &str| Err(LineupError::Validation(error.to_string()));
+        match &self.item_separator {
+            Some(ItemSeparator::Explicit(separator)) if separator.is_empty() => {
+                return invalid("explicit item separator is empty");
+            }
+            Some(ItemSeparator::ByteCount(0) | ItemSeparator::CharCount(0)) => {
+                return invalid("item size is 0");
+            }
+            #[cfg(feature = "unicode-segmentation")]
+            Some(ItemSeparator::GraphemeCount(0)) => return invalid("item size is 0"),
+            Some(ItemSeparator::FieldWidths(widths)) if widths.is_empty() => {
+                return invalid("no field widths");
+            }
+            #[cfg(feature = "regex")]
+            Some(ItemSeparator::Regex(pattern)) => {
+                if let Err(error) = regex::Regex::new(pattern) {
+                    return invalid(&format!("invalid item separator: {error}"));
+                }
+            }
+            _ => {}
+        }
+        match &self.line_separator {
+            // records of a fixed length need no separator after them
+            Some(Some(line_separator))
+                if line_separator.line_separator.is_empty()
+                    && line_separator.record_length.is_none() =>
+            {
+                invalid("line separator is empty")
+            }
+            Some(Some(line_separator)) => line_separator.validate(),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+/// Formats are deserialized through their builder, so that they are validated and missing
+/// settings get their default
+impl TryFrom<InFormatBuilder> for InFormat {
+    type Error = LineupError;
+
+    fn try_from(builder: InFormatBuilder) -> Result<Self, Self::Error> {
+        builder.build()
+    }
 }
 
 #[derive(Clone, Debug, Builder)]
-#[builder(derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "OutFormatBuilder"),
+    builder_struct_attr(derive(serde::Deserialize))
+)]
+#[builder(
+    derive(Debug),
+    build_fn(validate = "Self::validate", error = "LineupError")
+)]
 /// Output format
 ///
 /// conveniently FormatBuilder struct can be used for construction:
@@ -41,17 +196,225 @@ pub struct OutFormat {
     /// [ItemSpan]: crate::ItemSpan
     pub span: Option<ItemSpan>,
 
+    #[builder(default = "Vec::new()")]
+    /// Per column item spans, applied cyclically by column position within a line; when not
+    /// empty they take precedence over [span]
+    ///
+    /// [span]: crate::OutFormat::span
+    pub column_spans: Vec<ItemSpan>,
+
+    #[builder(default = "Vec::new()")]
+    /// Per column anchors, by column position within a line; columns beyond this list fall back
+    /// to the anchor of their span
+    pub column_anchors: Vec<Anchor>,
+
+    #[builder(default = "false")]
+    /// When computing spans from items (see [align] and [Table]), anchor columns beyond
+    /// [column_anchors] to the right if all of their non-empty items look like numbers, to the
+    /// left otherwise; header and footer labels are not taken into account
+    ///
+    /// [align]: crate::OutFormat::align
+    /// [Table]: crate::Table
+    /// [column_anchors]: crate::OutFormat::column_anchors
+    pub detect_anchors: bool,
+
+    #[builder(default = "Vec::new()")]
+    /// Per column pad characters, by column position within a line; columns beyond this list
+    /// fall back to the pad character of their span
+    pub column_pads: Vec<char>,
+
+    #[builder(default = "Width::default()")]
+    /// How item width is measured against spans (see [Width])
+    ///
+    /// [Width]: crate::Width
+    pub width: Width,
+
+    #[builder(default = "String::new()")]
+    /// Marker put in place of the characters dropped from items truncated to their span (e.g.
+    /// "…"); it counts toward the span and is appended or prepended depending on the side items
+    /// are cut (see [OverflowPolicy::Truncate])
+    ///
+    /// [OverflowPolicy::Truncate]: crate::OverflowPolicy::Truncate
+    pub ellipsis: String,
+
     #[builder(default = "String::from(\" \")")]
     /// Separator for items within a line
     pub item_separator: String,
 
+    #[builder(default = "Vec::new()")]
+    /// Per column separators, emitted after the item at given column position within a line
+    /// instead of [item_separator]; columns beyond this list fall back to it
+    ///
+    /// [item_separator]: crate::OutFormat::item_separator
+    pub column_separators: Vec<String>,
+
     #[builder(default = "None")]
     /// Separator for lines
     pub line_separator: Option<LineSeparator>,
+
+    #[builder(default = "String::new()")]
+    /// String emitted at the start of every line, header, footer and continuation lines of
+    /// wrapped items included, e.g. to indent output; it does not count toward line width and it
+    /// is not used in JSON output modes
+    pub line_prefix: String,
+
+    #[builder(default = "String::new()")]
+    /// String emitted before every line separator, including the new lines ending header and
+    /// continuation lines of wrapped items, e.g. a shell line continuation; as there is no line
+    /// separator after the last line, it is not emitted there, unless [trailing_separator] is
+    /// set; it does not count toward line width and it is not used in JSON output modes
+    ///
+    /// [trailing_separator]: crate::OutFormat::trailing_separator
+    pub line_suffix: String,
+
+    #[builder(default = "None")]
+    /// Separator for pages, emitted after the line separator every given number of lines (see
+    /// [PageSeparator]); not used in JSON output modes
+    ///
+    /// [PageSeparator]: crate::PageSeparator
+    pub page_separator: Option<PageSeparator>,
+
+    #[builder(default = "None")]
+    /// Tab stops between items within a line, replacing [item_separator] when given (see
+    /// [TabStops])
+    ///
+    /// [item_separator]: crate::OutFormat::item_separator
+    /// [TabStops]: crate::TabStops
+    pub tab_stops: Option<TabStops>,
+
+    #[builder(default = "None")]
+    /// Numeric formatting applied to items looking like numbers, before case transform,
+    /// decorations and padding (see [NumberFormat]); other items are left as they are
+    ///
+    /// [NumberFormat]: crate::NumberFormat
+    pub number: Option<NumberFormat>,
+
+    #[builder(default = "Case::default()")]
+    /// Case transform applied to every item, before decorations and padding (see [Case])
+    ///
+    /// [Case]: crate::Case
+    pub case: Case,
+
+    #[builder(default = "String::new()")]
+    /// String prepended to every item, before padding
+    pub item_prefix: String,
+
+    #[builder(default = "String::new()")]
+    /// String appended to every item, before padding
+    pub item_suffix: String,
+
+    #[builder(default = "None")]
+    /// Template every item is rendered through, after case transform and before item prefix
+    /// and suffix (see [Template]); header and footer labels are not
+    ///
+    /// [Template]: crate::Template
+    pub template: Option<Template>,
+
+    #[builder(default = "FillOrder::default()")]
+    /// Order lines are filled with items (see [FillOrder])
+    ///
+    /// [FillOrder]: crate::FillOrder
+    pub fill_order: FillOrder,
+
+    #[builder(default = "OutputMode::default()")]
+    /// Output mode (see [OutputMode])
+    ///
+    /// [OutputMode]: crate::OutputMode
+    pub mode: OutputMode,
+
+    #[builder(default = "Quoting::default()")]
+    /// Items quoting in plain output mode (see [Quoting])
+    ///
+    /// [Quoting]: crate::Quoting
+    pub quoting: Quoting,
+
+    #[builder(default = "Vec::new()")]
+    /// Style rules for items, emitted as ANSI escape sequences around them; the first rule
+    /// selecting an item applies (see [StyleRule]); not applied in JSON output modes
+    ///
+    /// [StyleRule]: crate::StyleRule
+    pub styles: Vec<StyleRule>,
+
+    #[builder(default = "false")]
+    /// Wrap items in Unicode directional isolates (FSI and PDI), so that right-to-left items
+    /// (e.g. Hebrew or Arabic) neither reorder nor are reordered with their padding and
+    /// neighbours; isolates are not counted in item width; in plain output mode only
+    pub bidi_isolate: bool,
+
+    #[builder(default = "false")]
+    /// Complete the last line, when shorter than the others, with empty cells padded as per
+    /// their span, so that output is a rectangle; in column-major fill order, lines shorter for
+    /// the last column are completed as well; no effect when lines are broken by width
+    pub fill_last_line: bool,
+
+    #[builder(default = "false")]
+    /// Emit the item (or line) separator after the last item as well, as a terminator
+    pub trailing_separator: bool,
+
+    #[builder(default = "Vec::new()")]
+    /// Header labels, emitted as the first line before items and formatted as items at the
+    /// same column positions; the line separator (or a new line if none) follows the header
+    pub header: Vec<String>,
+
+    #[builder(default = "Vec::new()")]
+    /// Footer labels, emitted as the last line after items once writing is finished and
+    /// formatted as items at the same column positions
+    pub footer: Vec<String>,
 }
 
-#[derive(New, Clone, Copy, Debug, PartialEq, Eq)]
+impl OutFormatBuilder {
+    /// Fail building formats whose span pad, line or page separators would never apply; column
+    /// spans of 0 are left as they are, for unpadded columns among padded ones
+    fn validate(&self) -> Result<(), LineupError> {
+        if let Some(Some(span)) = &self.span {
+            if span.span == 0 && span.pad != ' ' {
+                let error = format!("span 0 with pad '{}': items are never padded", span.pad);
+                return Err(LineupError::Validation(error));
+            }
+        }
+        if let Some(Some(page_separator)) = &self.page_separator {
+            if page_separator.lines_per_page == 0 {
+                let error = "page separator has 0 lines per page";
+                return Err(LineupError::Validation(error.to_string()));
+            }
+        }
+        match &self.line_separator {
+            Some(Some(line_separator)) => line_separator.validate(),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<OutFormatBuilder> for OutFormat {
+    type Error = LineupError;
+
+    fn try_from(builder: OutFormatBuilder) -> Result<Self, Self::Error> {
+        builder.build()
+    }
+}
+
+#[derive(New, Clone, Copy, Debug, PartialEq, Eq, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[builder(derive(Debug), build_fn(error = "LineupError"))]
 /// Output items span
+///
+/// besides [new], ItemSpanBuilder struct can be used for construction when non default
+/// settings are needed:
+///
+/// # Examples
+///
+/// ```
+/// let span = lineup::ItemSpanBuilder::default()
+///     .span(4)
+///     .pad('_')
+///     .anchor(lineup::Anchor::Right)
+///     .overflow(lineup::OverflowPolicy::Truncate)
+///     .build()
+///     .unwrap();
+/// ```
+///
+/// [new]: crate::ItemSpan::new
 pub struct ItemSpan {
     /// Max characters an item would need; shorter represantions would be padded with [pad]
     /// and anchored as per [anchor];
@@ -60,372 +423,9961 @@ pub struct ItemSpan {
     /// [anchor]: crate::ItemSpan::anchor
     span: usize,
 
+    #[builder(default = "' '")]
     /// Pad character to use for items whose length is less than [span]
     ///
     /// [span]: crate::ItemSpan::span
     pad: char,
 
+    #[builder(default = "Anchor::Left")]
     /// Anchor type for items when padding is needed (see [span])
     ///
     /// [span]: crate::ItemSpan::span
     anchor: Anchor,
+
+    #[new(value = "OverflowPolicy::default()")]
+    #[builder(default = "OverflowPolicy::default()")]
+    /// What to do with items whose length is greater than [span] (see [OverflowPolicy])
+    ///
+    /// [span]: crate::ItemSpan::span
+    /// [OverflowPolicy]: crate::OverflowPolicy
+    overflow: OverflowPolicy,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-/// Anchor type for items when padding is needed
-pub enum Anchor {
-    /// Anchor items to the right
-    Right,
-    /// Anchor items to the left
-    Left,
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Policy for items longer than their span
+pub enum OverflowPolicy {
+    /// Write items as they are, exceeding the span
+    #[default]
+    Overflow,
+    /// Cut items to the span, dropping characters on the side opposite to the anchor
+    /// (the end of the item for left, centered and decimal anchors, the beginning for right
+    /// anchor)
+    Truncate,
+    /// Fail writing with an [Overflow] error
+    ///
+    /// [Overflow]: crate::LineupError::Overflow
+    Error,
+    /// Wrap the remainder of items onto continuation lines within the same column; lines are
+    /// buffered until complete, then written as many new line separated rows as their tallest
+    /// item needs; in JSON output modes items are written as they are
+    Wrap,
 }
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
-pub enum ItemSeparator {
-    /// explicit item separator
-    Explicit(String),
-    /// item fixed byte size, no explicit separator
-    ByteCount(usize),
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Case transform of items
+pub enum Case {
+    /// Keep items as they are
+    #[default]
+    Preserve,
+    /// Upper case items
+    Upper,
+    /// Lower case items
+    Lower,
+    /// Upper case the first letter of every word and lower case the others, words being runs
+    /// of alphanumeric characters
+    Title,
 }
 
-#[derive(New, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
-pub struct LineSeparator {
-    items_per_line: usize,
-    line_separator: String,
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[builder(derive(Debug), build_fn(error = "LineupError"))]
+/// Numeric formatting of items looking like numbers: an optional sign, digits with an optional
+/// '.' decimal point, and an optional exponent (e.g. "-12", "3.25", ".5", "1e-3")
+///
+/// Numbers are rewritten digit by digit, so exactly, rounding half away from zero; those of
+/// magnitudes beyond the range of `f64` (e.g. "1e400") are left as they are
+///
+/// NumberFormatBuilder struct can be used for construction:
+///
+/// # Examples
+///
+/// ```
+/// let number = lineup::NumberFormatBuilder::default()
+///     .precision(Some(2))
+///     .grouping(Some(','))
+///     .build()
+///     .unwrap();
+/// let format = lineup::OutFormatBuilder::default()
+///     .number(Some(number))
+///     .build()
+///     .unwrap();
+/// let mut output = Vec::new();
+/// lineup::write(["1234567.891", "n/a", "-5"].into_iter(), &mut output, format).unwrap();
+/// assert_eq!(String::from_utf8(output).unwrap(), "1,234,567.89 n/a -5.00");
+/// ```
+pub struct NumberFormat {
+    #[builder(default = "None")]
+    /// Number of fraction digits, rounding numbers as needed; numbers are kept as they are
+    /// if not given, unless written in another notation
+    precision: Option<usize>,
+    #[builder(default = "None")]
+    /// Separator between groups of three integer digits, in fixed notation
+    grouping: Option<char>,
+    #[builder(default = "'.'")]
+    /// Decimal point numbers are written with; items are always read with '.'
+    decimal_point: char,
+    #[builder(default = "Notation::default()")]
+    /// Notation numbers are written in
+    notation: Notation,
 }
 
-impl Default for ItemSeparator {
-    fn default() -> Self {
-        Self::Explicit(",".to_string())
+#[cfg(feature = "locale")]
+impl NumberFormatBuilder {
+    /// Set decimal point and digit grouping separator as per locale `name`, e.g. "de" or
+    /// "en-IN"; names with a region not known are looked up by language (e.g. "de_DE" as "de")
+    ///
+    /// locales grouping digits other than by three (e.g. Indian numbering) are approximated by
+    /// groups of three
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let number = lineup::NumberFormatBuilder::default()
+    ///     .locale("de_DE")
+    ///     .unwrap()
+    ///     .precision(Some(2))
+    ///     .build()
+    ///     .unwrap();
+    /// let format = lineup::OutFormatBuilder::default()
+    ///     .number(Some(number))
+    ///     .build()
+    ///     .unwrap();
+    /// let mut output = Vec::new();
+    /// lineup::write(["1234.56"].into_iter(), &mut output, format).unwrap();
+    /// assert_eq!(String::from_utf8(output).unwrap(), "1.234,56");
+    /// ```
+    pub fn locale(&mut self, name: &str) -> Result<&mut Self, LineupError> {
+        let name = name.replace('_', "-");
+        let language = name.split('-').next().unwrap_or_default();
+        let locale = num_format::Locale::from_name(&name)
+            .or_else(|_| num_format::Locale::from_name(language))
+            .map_err(|_| LineupError::Build(format!("unknown locale '{name}'")))?;
+        let grouping = match locale.grouping() {
+            num_format::Grouping::Posix => None,
+            num_format::Grouping::Standard | num_format::Grouping::Indian => {
+                locale.separator().chars().next()
+            }
+        };
+        let decimal_point = locale.decimal().chars().next().unwrap_or('.');
+        Ok(self.grouping(grouping).decimal_point(decimal_point))
     }
 }
 
-impl Display for ItemSeparator {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{self:?}")
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Notation numbers are written in
+pub enum Notation {
+    /// Digits with an optional decimal point, e.g. "1234.5"
+    #[default]
+    Fixed,
+    /// Mantissa and exponent, e.g. "1.2345e3"
+    Scientific,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Order lines are filled with items
+pub enum FillOrder {
+    /// Items are laid out across lines first: each line is filled before the next one
+    #[default]
+    RowMajor,
+    /// Items are laid out down the columns first (like `ls`), given the number of items per
+    /// line; this needs all items to be known, so they are buffered until writing is finished
+    ColumnMajor,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// How items are encoded on output
+pub enum OutputMode {
+    /// Items are written as they are
+    #[default]
+    Plain,
+    /// Items are quoted as per RFC 4180 when they contain item or line separators, double quotes
+    /// or line breaks; embedded double quotes are doubled (see [Quoting::csv])
+    ///
+    /// [Quoting::csv]: crate::Quoting::csv
+    Csv,
+    /// Items are written as JSON strings within an array, or within an array of arrays (one per
+    /// line) when line separation is configured; item and line separator strings are not used
+    /// and neither is trailing separator
+    Json,
+    /// Each line is written as a JSON array of strings followed by a new line; when header labels
+    /// are given, each line is written as a JSON object instead, with header labels as keys
+    /// (columns beyond header labels are keyed by their position) and header is not written
+    /// as a line; item and line separator strings are not used and neither is trailing separator
+    JsonLines,
+}
+
+impl OutputMode {
+    fn is_json(self) -> bool {
+        matches!(self, OutputMode::Json | OutputMode::JsonLines)
+    }
+
+    /// Output for no items at all
+    fn empty_output(self) -> &'static str {
+        match self {
+            OutputMode::Plain | OutputMode::Csv | OutputMode::JsonLines => "",
+            OutputMode::Json => "[]",
+        }
     }
 }
 
-/// Write all input items as per provided format
+#[derive(New, Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Output items quoting
+pub struct Quoting {
+    /// When items are quoted
+    policy: QuotePolicy,
+    /// Quote character, wrapping quoted items
+    quote: char,
+    /// How quote characters within quoted items are escaped
+    escape: QuoteEscape,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// When items are quoted
+pub enum QuotePolicy {
+    /// Never quote items
+    #[default]
+    Never,
+    /// Quote items containing item or line separators, quote characters or line breaks
+    WhenNeeded,
+    /// Quote all items
+    Always,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// How quote characters within quoted items are escaped
+pub enum QuoteEscape {
+    /// Quote characters are doubled
+    #[default]
+    Doubled,
+    /// Quote and backslash characters are preceded by a backslash
+    Backslash,
+}
+
+impl Quoting {
+    /// Quoting as per RFC 4180: double quotes, when needed, doubled
+    pub fn csv() -> Self {
+        Self::new(QuotePolicy::WhenNeeded, '"', QuoteEscape::Doubled)
+    }
+}
+
+impl Default for Quoting {
+    fn default() -> Self {
+        Self::new(QuotePolicy::Never, '"', QuoteEscape::Doubled)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Tab stops between items within a line, as `expand` and `unexpand` would lay them out; they
+/// apply to plain and CSV output modes
+pub enum TabStops {
+    /// Pad with spaces up to the next multiple of given width from line start, at least one
+    Spaces(usize),
+    /// Emit a literal tab character, leaving expansion to the output device
+    Tabs,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "String", into = "String")
+)]
+/// Template items are rendered through, e.g. `"[{item:>8}]"` or `"{index}: {item}"`
+///
+/// text out of braces is written as it is, `{{` and `}}` being literal braces; fields in braces
+/// are replaced by the value of a variable:
+/// - `item`: the item
+/// - `index`: index of the item, starting from 0
+/// - `line`: index of the line of the item, starting from 0
+/// - `column`: column position of the item within its line, starting from 0
+///
+/// a variable can be followed by `:` and a `[[fill]align][width]` spec, as per [std::fmt]:
+/// values narrower than `width` are padded with `fill` (a space by default) and aligned to the
+/// left (`<`), right (`>`) or center (`^`); items are aligned to the left by default, indices
+/// to the right; a width with a leading `0` pads values with zeros on the left; widths are
+/// measured as per output format (see [Width])
 ///
 /// # Examples
 ///
 /// ```
-/// let input = ["😊😊", "👶", "💼💼💼"];
+/// let template: lineup::Template = "{index:02}:[{item:_^5}]".parse().unwrap();
 /// let format = lineup::OutFormatBuilder::default()
-///     .span(Some(lineup::ItemSpan::new(4, '👉', lineup::Anchor::Right)))
-///     .item_separator("🖖".to_string())
-///     .line_separator(Some(lineup::LineSeparator::new(2, "🔩\n".to_string())))
+///     .template(Some(template))
 ///     .build()
 ///     .unwrap();
-/// let expected = "👉👉😊😊🖖👉👉👉👶🔩\n👉💼💼💼";
-/// let mut output = vec![0u8; 100 ];
-/// lineup::write(input.into_iter(), output.as_mut_slice(), format).unwrap();
-/// let eof = output.iter().position(|x| *x == 0u8).unwrap_or(output.len());
-/// let output = output.split_at(eof).0;
-/// assert_eq!(String::from_utf8(output.to_vec()).unwrap(), expected);
+/// let mut output = Vec::new();
+/// lineup::write(["a", "bb"].into_iter(), &mut output, format).unwrap();
+/// assert_eq!(String::from_utf8(output).unwrap(), "00:[__a__] 01:[_bb__]");
 /// ```
-///
-pub fn write<'i, In, Out>(
-    istream: In,
-    mut ostream: Out,
-    format: OutFormat,
-) -> Result<(), std::io::Error>
-where
-    In: Iterator<Item = &'i str>,
-    Out: std::io::Write,
-{
-    let mut writer = ItemWriter::new(format);
-    for item in istream {
-        writer.write(item, &mut ostream)?;
+pub struct Template {
+    source: String,
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Part of a template, either literal text or a field
+enum TemplatePart {
+    Text(String),
+    Field {
+        variable: TemplateVariable,
+        fill: char,
+        align: Option<TemplateAlign>,
+        width: usize,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Variable of a template field
+enum TemplateVariable {
+    Item,
+    Index,
+    Line,
+    Column,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Alignment of a template field value narrower than the field
+enum TemplateAlign {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(New, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Style applied to the items a selector selects
+pub struct StyleRule {
+    selector: Selector,
+    style: Style,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Which items a style rule applies to
+pub enum Selector {
+    /// Items at given column position within a line
+    Column(usize),
+    /// Items matching given regular expression, as written before padding
+    #[cfg(feature = "regex")]
+    #[cfg_attr(feature = "serde", serde(with = "serde_regex"))]
+    Matches(regex::Regex),
+}
+
+#[cfg(all(feature = "serde", feature = "regex"))]
+/// (De)serialization of regular expressions as their patterns
+mod serde_regex {
+    pub fn serialize<S: serde::Serializer>(
+        regex: &regex::Regex,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(regex.as_str())
+    }
+
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<regex::Regex, D::Error> {
+        let pattern: String = serde::Deserialize::deserialize(deserializer)?;
+        regex::Regex::new(&pattern).map_err(serde::de::Error::custom)
     }
-    Ok(())
 }
 
-/// Opaque type definition around [ItemReader], as returned from [read]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[builder(derive(Debug), build_fn(error = "LineupError"))]
+/// Text style of items
 ///
-/// [ItemReader]: crate::ItemReader
-/// [read]: crate::read
-pub type ItemIterator<'i> = impl Iterator<Item = &'i str> + std::fmt::Debug;
-
-/// Get an iterator over &str items
+/// StyleBuilder struct can be used for construction:
 ///
 /// # Examples
 ///
-/// separate by byte count for each item
 /// ```
-/// let input = "aabbccdd";
-/// let fmt = lineup::InFormatBuilder::default()
-///     .item_separator(lineup::ItemSeparator::ByteCount(2))
+/// let style = lineup::StyleBuilder::default()
+///     .foreground(Some(lineup::Color::Red))
+///     .bold(true)
 ///     .build()
 ///     .unwrap();
-/// let mut it = lineup::read(input, fmt);
-/// assert_eq!(Some("aa"), it.next());
-/// assert_eq!(Some("bb"), it.next());
-/// assert_eq!(Some("cc"), it.next());
-/// assert_eq!(Some("dd"), it.next());
-/// assert_eq!(None, it.next());
 /// ```
-///
-/// separate by explicit string
-/// ```
-/// let input = "👉👉👉SEP😊😊SEP🖖SEP💼💼💼";
-/// let fmt = lineup::InFormatBuilder::default()
-///     .item_separator(lineup::ItemSeparator::Explicit("SEP".to_string()))
-///     .build()
-///     .unwrap();
-/// let mut it = lineup::read(input, fmt);
-/// assert_eq!(Some("👉👉👉"), it.next());
-/// assert_eq!(Some("😊😊"), it.next());
-/// assert_eq!(Some("🖖"), it.next());
-/// assert_eq!(Some("💼💼💼"), it.next());
-/// assert_eq!(None, it.next());
-/// ```
-///
-pub fn read(input: &str, format: InFormat) -> ItemIterator {
-    ItemReader::new(input, format)
+pub struct Style {
+    #[builder(default = "None")]
+    /// Text color
+    foreground: Option<Color>,
+    #[builder(default = "None")]
+    /// Background color
+    background: Option<Color>,
+    #[builder(default = "false")]
+    /// Bold text
+    bold: bool,
+    #[builder(default = "false")]
+    /// Underlined text
+    underline: bool,
 }
 
-enum EmittingSeparator {
-    None,
-    Item,
-    Line,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Terminal color, as in the standard ANSI palette
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
 }
 
-/// Write input items as per provided format (see [write])
-///
-/// [write]: ItemWriter::write
-#[derive(New)]
-pub struct ItemWriter {
-    #[new(value = "EmittingSeparator::None")]
-    separator: EmittingSeparator,
-    fmt: OutFormat,
-    #[new(value = "0")]
-    items_in_line: usize,
+impl Selector {
+    #[cfg_attr(not(feature = "regex"), allow(unused_variables))]
+    fn selects(&self, item: &str, column: usize) -> bool {
+        match self {
+            Selector::Column(selected) => *selected == column,
+            #[cfg(feature = "regex")]
+            Selector::Matches(regex) => regex.is_match(item),
+        }
+    }
+}
+
+impl Style {
+    /// ANSI SGR escape sequence setting this style, empty for the default style
+    fn escape(&self) -> String {
+        let codes: Vec<String> = self
+            .bold
+            .then(|| "1".to_string())
+            .into_iter()
+            .chain(self.underline.then(|| "4".to_string()))
+            .chain(self.foreground.map(|color| (30 + color as u8).to_string()))
+            .chain(self.background.map(|color| (40 + color as u8).to_string()))
+            .collect();
+        if codes.is_empty() {
+            String::new()
+        } else {
+            format!("\x1b[{}m", codes.join(";"))
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Measure of item width, used for padding and truncation
+pub enum Width {
+    /// Number of chars (Unicode scalar values)
+    #[default]
+    Chars,
+    /// Number of terminal display cells, so that wide (e.g. CJK, emoji) characters count as two
+    /// and zero width characters are not counted
+    #[cfg(feature = "unicode-width")]
+    Cells,
+    /// Number of extended grapheme clusters, so that combining characters and emoji sequences
+    /// count as a single user-perceived character
+    #[cfg(feature = "unicode-segmentation")]
+    Graphemes,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Anchor type for items when padding is needed
+pub enum Anchor {
+    /// Anchor items to the right
+    Right,
+    /// Anchor items to the left
+    Left,
+    /// Center items, padding on both sides; when padding is odd the extra pad character goes
+    /// to the given [Side]
+    ///
+    /// [Side]: crate::Side
+    Center(Side),
+    /// Align items on the first occurrence of `point` character, reserving `fraction`
+    /// characters after it, so that numeric items line up on their decimal point; items not
+    /// containing `point` are anchored to the right
+    Decimal { point: char, fraction: usize },
+}
+
+impl Anchor {
+    /// Align items on '.' decimal point, reserving `fraction` characters after it (see
+    /// [Anchor::Decimal])
+    ///
+    /// [Anchor::Decimal]: crate::Anchor::Decimal
+    pub fn decimal(fraction: usize) -> Self {
+        Anchor::Decimal {
+            point: '.',
+            fraction,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Side of an item
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ItemSeparator {
+    /// explicit item separator
+    Explicit(String),
+    /// item fixed byte size, no explicit separator
+    ByteCount(usize),
+    /// item fixed size in Unicode scalar values (chars), no explicit separator
+    CharCount(usize),
+    #[cfg(feature = "unicode-segmentation")]
+    /// item fixed size in extended grapheme clusters, no explicit separator
+    GraphemeCount(usize),
+    /// any run of whitespace, as per [str::split_whitespace]; leading and trailing whitespace is
+    /// ignored; a line separator, if any, is not to contain whitespace
+    Whitespace,
+    #[cfg(feature = "regex")]
+    /// regular expression matching item separators, e.g. "[ \t]+"; building a format with an
+    /// invalid one fails
+    Regex(String),
+    /// fixed width fields of a record on each line, of given widths in chars, e.g. [8, 4, 12];
+    /// fields are cut short by line breaks (LF or CRLF), whatever follows the last field up to
+    /// the next line is ignored, and line separator of format is ignored
+    FieldWidths(Vec<usize>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// How input is made of items
+pub enum InputMode {
+    /// Items between item separators, grouped in lines by line separators, as per format
+    #[default]
+    Plain,
+    /// RFC 4180 CSV: items are the fields of all records, separated by commas and line breaks
+    /// (CRLF or LF) out of double quoted fields, where double quotes are doubled; item and line
+    /// separators and quoting of format are ignored; empty fields are read as empty items, unless
+    /// they are to be skipped
+    Csv,
+    /// TSV: items are the fields of all records, separated by tabs and line breaks (CRLF or LF);
+    /// item and line separators of format are ignored; empty fields are read as empty items,
+    /// unless they are to be skipped
+    Tsv,
+    /// JSON: items are the strings, numbers, booleans and nulls in an array, nested arrays
+    /// flattened; strings are read as in input, quotes included (see [InFormat::unquote]); all
+    /// separators and quoting of format are ignored
+    Json,
+    /// JSON Lines: as [InputMode::Json], with each line an array or an object, whose values
+    /// are taken in order and keys ignored
+    JsonLines,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// What empty items between separators amount to when reading
+pub enum EmptyItems {
+    /// The end of items, reading stops at the first one
+    #[default]
+    Terminate,
+    /// Nothing, they are skipped and reading goes on
+    Skip,
+    /// Empty items, so that items keep their position
+    Yield,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// What a trailing chunk of input shorter than fixed size items (see [ItemSeparator::ByteCount],
+/// [ItemSeparator::CharCount]) amounts to when reading
+pub enum PartialChunk {
+    /// Nothing, it is dropped
+    #[default]
+    Drop,
+    /// A short item
+    Yield,
+    /// A read error (see [ReadError::PartialChunk])
+    Error,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// What invalid UTF-8 within items read from bytes (see [read_bytes]) amounts to
+pub enum InvalidUtf8 {
+    /// A read error (see [ReadError::InvalidUtf8])
+    #[default]
+    Error,
+    /// Replacement characters (U+FFFD), as per [String::from_utf8_lossy]
+    Replace,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// What an item boundary of fixed byte size items (see [ItemSeparator::ByteCount]) within a
+/// UTF-8 code point amounts to when reading
+pub enum ByteRounding {
+    /// A read error (see [ReadError::InvalidBoundary])
+    #[default]
+    Error,
+    /// The boundary after the code point, for a longer item
+    Forward,
+    /// The boundary before the code point, for a shorter item, unless it would be empty
+    Backward,
+}
+
+#[derive(New, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "LineSeparatorFields")
+)]
+pub struct LineSeparator {
+    items_per_line: usize,
+    line_separator: String,
+    #[new(default)]
+    max_width: Option<usize>,
+    #[new(default)]
+    record_length: Option<RecordLength>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+/// Fields of a [LineSeparator] as deserialized, before it is validated
+struct LineSeparatorFields {
+    items_per_line: usize,
+    line_separator: String,
+    #[serde(default)]
+    max_width: Option<usize>,
+    #[serde(default)]
+    record_length: Option<RecordLength>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<LineSeparatorFields> for LineSeparator {
+    type Error = LineupError;
+
+    fn try_from(fields: LineSeparatorFields) -> Result<Self, Self::Error> {
+        let line_separator = Self {
+            items_per_line: fields.items_per_line,
+            line_separator: fields.line_separator,
+            max_width: fields.max_width,
+            record_length: fields.record_length,
+        };
+        line_separator.validate()?;
+        Ok(line_separator)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Fixed length of input lines, i.e. records
+pub enum RecordLength {
+    /// Number of bytes; a record is not to end within a UTF-8 code point
+    Bytes(usize),
+    /// Number of chars (Unicode scalar values)
+    Chars(usize),
+}
+
+#[derive(New, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Separator between pages of lines, e.g. a form feed or an empty line; header and footer lines
+/// and continuation lines of wrapped items are not counted
+pub struct PageSeparator {
+    lines_per_page: usize,
+    page_separator: String,
+}
+
+impl LineSeparator {
+    /// Start a new line whenever the next item would make the line exceed `max_width`, rather
+    /// than after a given number of items; lines hold at least one item
+    ///
+    /// line width is measured as item width (see [Width]), separators and padding included;
+    /// column-major fill order is not supported, all items being laid out on a single line
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let format = lineup::OutFormatBuilder::default()
+    ///     .line_separator(Some(lineup::LineSeparator::by_width(6, "\n".to_string())))
+    ///     .build()
+    ///     .unwrap();
+    /// let mut output = Vec::new();
+    /// lineup::write(["a", "bb", "ccc", "ddddddd", "e"].into_iter(), &mut output, format).unwrap();
+    /// assert_eq!(String::from_utf8(output).unwrap(), "a bb\nccc\nddddddd\ne");
+    /// ```
+    ///
+    /// [Width]: crate::Width
+    pub fn by_width(max_width: usize, line_separator: String) -> Self {
+        Self {
+            items_per_line: usize::MAX,
+            line_separator,
+            max_width: Some(max_width),
+            record_length: None,
+        }
+    }
+
+    /// End an input line after a fixed `record_length` of input, followed by `line_separator`,
+    /// possibly empty, rather than after a given number of items; the last item of a line ends
+    /// with it; input only, no line is ended on output
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let record_length = lineup::RecordLength::Bytes(6);
+    /// let fmt = lineup::InFormatBuilder::default()
+    ///     .line_separator(Some(lineup::LineSeparator::by_length(record_length, "".to_string())))
+    ///     .build()
+    ///     .unwrap();
+    /// let items: Vec<_> = lineup::read("ab,cdeABCDEF12,3", fmt).collect();
+    /// assert_eq!(items, ["ab", "cde", "ABCDEF", "12", "3"]);
+    /// ```
+    pub fn by_length(record_length: RecordLength, line_separator: String) -> Self {
+        Self {
+            items_per_line: usize::MAX,
+            line_separator,
+            max_width: None,
+            record_length: Some(record_length),
+        }
+    }
+
+    /// Fail on lines that could hold no item
+    fn validate(&self) -> Result<(), LineupError> {
+        let error = match self.record_length {
+            _ if self.items_per_line == 0 => "line separator has 0 items per line",
+            Some(RecordLength::Bytes(0) | RecordLength::Chars(0)) => "record length is 0",
+            _ => return Ok(()),
+        };
+        Err(LineupError::Validation(error.to_string()))
+    }
+}
+
+impl InFormat {
+    /// Format of TSV input: tab separated fields of records on lines of their own
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut it = lineup::read("a\tb\nc\t\n", lineup::InFormat::tsv());
+    /// assert_eq!(Some("a"), it.next());
+    /// assert_eq!(Some("b"), it.next());
+    /// assert_eq!(Some("c"), it.next());
+    /// assert_eq!(Some(""), it.next());
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn tsv() -> Self {
+        InFormatBuilder::default()
+            .mode(InputMode::Tsv)
+            .build()
+            .expect("valid format")
+    }
+
+    /// Format of NUL separated (or terminated) input, e.g. from `find -print0`; items may
+    /// contain new lines and spaces, empty ones are skipped
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let it = lineup::read("a b\0c\nd\0\0", lineup::InFormat::null());
+    /// assert_eq!(vec!["a b", "c\nd"], it.collect::<Vec<_>>());
+    /// ```
+    pub fn null() -> Self {
+        InFormatBuilder::default()
+            .item_separator(ItemSeparator::Explicit("\0".to_string()))
+            .empty_items(EmptyItems::Skip)
+            .build()
+            .expect("valid format")
+    }
+
+    /// Item without its enclosing quotes, if quoted, and with quote characters within it
+    /// unescaped; otherwise, with characters following the escape character unescaped
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let fmt = lineup::InFormatBuilder::default()
+    ///     .quote(Some('"'))
+    ///     .build()
+    ///     .unwrap();
+    /// let input = r#"a,"b,c","say ""hi"" now""#;
+    /// let mut it = lineup::read(input, fmt.clone()).map(|item| fmt.unquote(item));
+    /// assert_eq!(Some("a".into()), it.next());
+    /// assert_eq!(Some("b,c".into()), it.next());
+    /// assert_eq!(Some("say \"hi\" now".into()), it.next());
+    /// assert_eq!(None, it.next());
+    /// ```
+    ///
+    /// ```
+    /// let fmt = lineup::InFormatBuilder::default()
+    ///     .escape(Some('\\'))
+    ///     .build()
+    ///     .unwrap();
+    /// let mut it = lineup::read(r"a\,b,c", fmt.clone()).map(|item| fmt.unquote(item));
+    /// assert_eq!(Some("a,b".into()), it.next());
+    /// assert_eq!(Some("c".into()), it.next());
+    /// assert_eq!(None, it.next());
+    /// ```
+    pub fn unquote<'a>(&self, item: &'a str) -> Cow<'a, str> {
+        if matches!(self.mode, InputMode::Json | InputMode::JsonLines) {
+            return match item
+                .strip_prefix('"')
+                .and_then(|item| item.strip_suffix('"'))
+            {
+                Some(string) if string.contains('\\') => Cow::Owned(json_unescape(string)),
+                Some(string) => Cow::Borrowed(string),
+                None => Cow::Borrowed(item),
+            };
+        }
+        let quoted = self.quoting().and_then(|(quote, quote_escape)| {
+            let quoted = item.strip_prefix(quote)?.strip_suffix(quote)?;
+            match quote_escape {
+                QuoteEscape::Doubled => Some((quoted, quote)),
+                QuoteEscape::Backslash => Some((quoted, '\\')),
+            }
+        });
+        let (item, escape) = match quoted {
+            Some((quoted, escape)) => (quoted, Some(escape)),
+            None => (item, self.escape),
+        };
+        let Some(escape) = escape.filter(|escape| item.contains(*escape)) else {
+            return Cow::Borrowed(item);
+        };
+        let mut unquoted = String::with_capacity(item.len());
+        let mut chars = item.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                c if c == escape => unquoted.extend(chars.next()),
+                c => unquoted.push(c),
+            }
+        }
+        Cow::Owned(unquoted)
+    }
+
+    /// Byte range of the first separator in `input`, as found by `find` from a byte offset on,
+    /// out of the quoted section input starts with and not following the escape character
+    fn find_separator<F>(&self, input: &str, find: F) -> Option<(usize, usize)>
+    where
+        F: Fn(usize) -> Option<(usize, usize)>,
+    {
+        let mut from = self.quoted_len(input);
+        loop {
+            let (start, end) = find(from)?;
+            let escapes = self.escape.map_or(0, |escape| {
+                input[..start]
+                    .chars()
+                    .rev()
+                    .take_while(|c| *c == escape)
+                    .count()
+            });
+            if escapes.is_multiple_of(2) {
+                return Some((start, end));
+            }
+            from = start + input[start..].chars().next()?.len_utf8();
+        }
+    }
+
+    /// Byte length of the quoted section `input` starts with, closing quote included, if any;
+    /// all of it if the quote is not closed
+    fn quoted_len(&self, input: &str) -> usize {
+        let Some((quote, quote_escape)) = self
+            .quoting()
+            .filter(|(quote, _)| input.starts_with(*quote))
+        else {
+            return 0;
+        };
+        let mut chars = input.char_indices().skip(1).peekable();
+        while let Some((index, c)) = chars.next() {
+            match quote_escape {
+                QuoteEscape::Backslash if c == '\\' => {
+                    chars.next();
+                }
+                QuoteEscape::Doubled
+                    if c == quote && chars.peek().is_some_and(|&(_, c)| c == quote) =>
+                {
+                    chars.next();
+                }
+                _ if c == quote => return index + c.len_utf8(),
+                _ => {}
+            }
+        }
+        input.len()
+    }
+
+    /// Quote character and how it is escaped within quoted items, if items may be quoted
+    fn quoting(&self) -> Option<(char, QuoteEscape)> {
+        match self.mode {
+            InputMode::Plain | InputMode::Tsv => self.quote.map(|quote| (quote, self.quote_escape)),
+            InputMode::Csv => Some(('"', QuoteEscape::Doubled)),
+            InputMode::Json | InputMode::JsonLines => None,
+        }
+    }
+
+    /// Whether a separator matched at the end of some input may go on in input following it,
+    /// i.e. whitespace and regex item separators
+    fn extensible_separator(&self) -> bool {
+        match self.item_separator {
+            _ if self.mode != InputMode::Plain => false,
+            ItemSeparator::Whitespace => true,
+            #[cfg(feature = "regex")]
+            ItemSeparator::Regex(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Explicit item separator input can be split on, into chunks to be read on their own, if
+    /// any: it cannot overlap itself, and no item depends on what precedes its chunk
+    #[cfg(feature = "rayon")]
+    fn chunk_separator(&self) -> Option<&str> {
+        let ItemSeparator::Explicit(separator) = &self.item_separator else {
+            return None;
+        };
+        let bytes = separator.as_bytes();
+        let overlapping = (1..bytes.len()).any(|n| bytes[..n] == bytes[bytes.len() - n..]);
+        let independent = self.mode == InputMode::Plain
+            && self.line_separator.is_none()
+            && self.quote.is_none()
+            && self.escape.is_none()
+            && self.skip_lines == 0
+            && !self.header
+            && self.comment.is_none()
+            && self.skip_items == 0
+            && self.max_items.is_none()
+            && self.expected_items.is_none()
+            && self.expected_items_per_line.is_none();
+        (!separator.is_empty() && !overlapping && independent).then_some(separator.as_str())
+    }
 }
 
-#[derive(New, Debug)]
-pub struct ItemReader<'i> {
-    input: &'i str,
-    fmt: InFormat,
-    #[new(value = "0")]
-    items_in_current_line: usize,
-}
+impl OutFormat {
+    /// Format of TSV output: tab separated items, `items_per_line` on each line, if not 0
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut output = Vec::new();
+    /// let format = lineup::OutFormat::tsv(2);
+    /// lineup::write(["a", "b", "c"].into_iter(), &mut output, format).unwrap();
+    /// assert_eq!(String::from_utf8(output).unwrap(), "a\tb\nc");
+    /// ```
+    pub fn tsv(items_per_line: usize) -> Self {
+        OutFormatBuilder::default()
+            .item_separator("\t".to_string())
+            .line_separator(
+                (items_per_line > 0).then(|| LineSeparator::new(items_per_line, "\n".to_string())),
+            )
+            .build()
+            .expect("valid format")
+    }
+
+    /// Format of NUL terminated output, e.g. for `xargs -0`: each item is followed by a NUL
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut output = Vec::new();
+    /// lineup::write(["a b", "c"].into_iter(), &mut output, lineup::OutFormat::null()).unwrap();
+    /// assert_eq!(output, b"a b\0c\0");
+    /// ```
+    pub fn null() -> Self {
+        OutFormatBuilder::default()
+            .item_separator("\0".to_string())
+            .trailing_separator(true)
+            .build()
+            .expect("valid format")
+    }
+
+    /// Compute spans from the widest of `items`, as [write_aligned] does before writing them
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut format = lineup::OutFormatBuilder::default().build().unwrap();
+    /// format.align(&["a", "bbb", "cc"]);
+    /// assert_eq!(format.items_per_line(7), 2);
+    /// ```
+    ///
+    /// [write_aligned]: crate::write_aligned
+    pub fn align(&mut self, items: &[&str]) {
+        // measure items as rendered, labels being at the same column positions as items
+        let labels = self
+            .header
+            .iter()
+            .enumerate()
+            .chain(self.footer.iter().enumerate())
+            .map(|(column, label)| (column, label.as_str()));
+        let label_widths = labels.map(|(column, label)| {
+            let label = self.render(label, Position::new(0, column), None);
+            (column, self.width.of(&label))
+        });
+        let item_widths = items.iter().enumerate().map(|(index, item)| {
+            let position = Position::new(
+                self.line(index, items.len()),
+                self.column(index, items.len()),
+            );
+            let item = self.render(item, position, Some(index));
+            (position.column, self.width.of(&item))
+        });
+        let widths: Vec<_> = label_widths.chain(item_widths).collect();
+        if self.column_spans.is_empty() {
+            let mut span = self
+                .span
+                .take()
+                .unwrap_or_else(|| ItemSpanBuilder::default().span(0).build().unwrap());
+            span.span = widths.iter().map(|(_, width)| *width).max().unwrap_or(0);
+            self.span = Some(span);
+        } else {
+            let mut column_widths = vec![0; self.column_spans.len()];
+            for (column, width) in widths {
+                let column_width = &mut column_widths[column % self.column_spans.len()];
+                *column_width = (*column_width).max(width);
+            }
+            for (span, width) in self.column_spans.iter_mut().zip(column_widths) {
+                span.span = width;
+            }
+        }
+        if self.detect_anchors {
+            let columns = items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| (self.column(index, items.len()), *item));
+            let anchors = detect_anchors(columns);
+            let explicit = self.column_anchors.len();
+            self.column_anchors
+                .extend(anchors.into_iter().skip(explicit));
+        }
+    }
+
+    /// Number of padded items fitting within `line_width`, separators included, at least one;
+    /// items are accounted for at their span, so items without one take no room but separators
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let format = lineup::OutFormatBuilder::default()
+    ///     .span(Some(lineup::ItemSpan::new(4, ' ', lineup::Anchor::Left)))
+    ///     .item_separator(" | ".to_string())
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(format.items_per_line(24), 3);
+    /// assert_eq!(format.items_per_line(25), 4);
+    /// ```
+    pub fn items_per_line(&self, line_width: usize) -> usize {
+        let mut position = 0;
+        let mut count = 0;
+        while count < line_width.max(1) {
+            if count > 0 {
+                position += self.width.of(&self.item_break(count, position));
+            }
+            position += self.item_span(count).map_or(0, |span| span.span);
+            if position > line_width {
+                break;
+            }
+            count += 1;
+        }
+        count.max(1)
+    }
+
+    /// Lay lines out with as many items as fit within `line_width` (see [items_per_line]),
+    /// keeping the line separator string if any, otherwise separating lines with a new line
+    ///
+    /// [items_per_line]: crate::OutFormat::items_per_line
+    pub fn fit_line(&mut self, line_width: usize) {
+        self.set_items_per_line(self.items_per_line(line_width));
+    }
+
+    /// Lay lines out with given number of items each, keeping the line separator string if any,
+    /// otherwise separating lines with a new line
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut format = lineup::OutFormatBuilder::default().build().unwrap();
+    /// format.set_items_per_line(2);
+    /// let mut output = Vec::new();
+    /// lineup::write(["a", "b", "c"], &mut output, format).unwrap();
+    /// assert_eq!(String::from_utf8(output).unwrap(), "a b\nc");
+    /// ```
+    pub fn set_items_per_line(&mut self, items_per_line: usize) {
+        match self.line_separator.as_mut() {
+            Some(line_separator) => line_separator.items_per_line = items_per_line,
+            None => {
+                self.line_separator = Some(LineSeparator::new(items_per_line, "\n".to_string()))
+            }
+        }
+    }
+
+    /// Span for an item at given column position within a line
+    fn item_span(&self, column: usize) -> Option<&ItemSpan> {
+        if self.column_spans.is_empty() {
+            self.span.as_ref()
+        } else {
+            self.column_spans.get(column % self.column_spans.len())
+        }
+    }
+
+    /// Anchor for an item at given column position within a line, as per its span
+    fn item_anchor(&self, column: usize, span: &ItemSpan) -> Anchor {
+        self.column_anchors
+            .get(column)
+            .copied()
+            .unwrap_or(span.anchor)
+    }
+
+    /// Pad character for an item at given column position within a line, as per its span
+    fn item_pad(&self, column: usize, span: &ItemSpan) -> char {
+        self.column_pads.get(column).copied().unwrap_or(span.pad)
+    }
+
+    /// Header labels to be written as a line
+    fn header_labels(&self) -> &[String] {
+        match self.mode {
+            OutputMode::JsonLines => &[],
+            _ => &self.header,
+        }
+    }
+
+    /// Whether lines are written as JSON objects
+    fn json_objects(&self) -> bool {
+        self.mode == OutputMode::JsonLines && !self.header.is_empty()
+    }
+
+    /// Format an item at given position if numeric, transform its case, render it through
+    /// template if an item at given index, not a label, decorate it, then encode it as per output
+    /// mode
+    fn render<'a>(&self, item: &'a str, position: Position, index: Option<usize>) -> Cow<'a, str> {
+        let column = position.column;
+        let item = match self.number {
+            Some(number) => number.apply(item),
+            None => Cow::Borrowed(item),
+        };
+        let item = match item {
+            Cow::Borrowed(item) => self.case.apply(item),
+            Cow::Owned(item) => Cow::Owned(self.case.apply(&item).into_owned()),
+        };
+        let item = match (&self.template, index) {
+            (Some(template), Some(index)) => {
+                Cow::Owned(template.render(&item, index, position, self.width))
+            }
+            _ => item,
+        };
+        let item = if self.item_prefix.is_empty() && self.item_suffix.is_empty() {
+            item
+        } else {
+            Cow::Owned(format!("{}{item}{}", self.item_prefix, self.item_suffix))
+        };
+        match item {
+            Cow::Borrowed(item) => self.encode(item, column),
+            Cow::Owned(item) => match self.encode(&item, column) {
+                Cow::Borrowed(_) => Cow::Owned(item),
+                Cow::Owned(encoded) => Cow::Owned(encoded),
+            },
+        }
+    }
+
+    /// Encode an item at given column position as per output mode
+    fn encode<'a>(&self, item: &'a str, column: usize) -> Cow<'a, str> {
+        match self.mode {
+            OutputMode::Plain => self.quote(item, &self.quoting),
+            OutputMode::Csv => self.quote(item, &Quoting::csv()),
+            OutputMode::Json => Cow::Owned(json_string(item)),
+            OutputMode::JsonLines if self.json_objects() => {
+                let key = match self.header.get(column) {
+                    Some(label) => json_string(label),
+                    None => json_string(&column.to_string()),
+                };
+                Cow::Owned(format!("{key}:{}", json_string(item)))
+            }
+            OutputMode::JsonLines => Cow::Owned(json_string(item)),
+        }
+    }
+
+    /// Quote an item as per given quoting
+    fn quote<'a>(&self, item: &'a str, quoting: &Quoting) -> Cow<'a, str> {
+        let needed = || {
+            let contains = |separator: &str| !separator.is_empty() && item.contains(separator);
+            let line_separator = self
+                .line_separator
+                .as_ref()
+                .map_or("", |line_separator| &line_separator.line_separator);
+            contains(&self.item_separator)
+                || self
+                    .column_separators
+                    .iter()
+                    .any(|separator| contains(separator))
+                || contains(line_separator)
+                || item.contains([quoting.quote, '\n', '\r'])
+        };
+        match quoting.policy {
+            QuotePolicy::Always => {}
+            QuotePolicy::WhenNeeded if needed() => {}
+            QuotePolicy::WhenNeeded | QuotePolicy::Never => return Cow::Borrowed(item),
+        }
+        let mut quoted = String::with_capacity(item.len() + 2);
+        quoted.push(quoting.quote);
+        for c in item.chars() {
+            match (c, quoting.escape) {
+                (c, QuoteEscape::Doubled) if c == quoting.quote => quoted.push(c),
+                (c, QuoteEscape::Backslash) if c == quoting.quote || c == '\\' => quoted.push('\\'),
+                _ => {}
+            }
+            quoted.push(c);
+        }
+        quoted.push(quoting.quote);
+        Cow::Owned(quoted)
+    }
+
+    /// Format of a rendered item at given column, as per overrides
+    fn cell(&self, item: &str, column: usize, item_override: &ItemOverride) -> Cell<'_> {
+        let span = match (self.item_span(column), item_override.span) {
+            (Some(span), width) => Some(CellSpan {
+                span: width.unwrap_or(span.span),
+                anchor: item_override
+                    .anchor
+                    .unwrap_or_else(|| self.item_anchor(column, span)),
+                pad: item_override
+                    .pad
+                    .unwrap_or_else(|| self.item_pad(column, span)),
+                overflow: span.overflow,
+                ellipsis: &self.ellipsis,
+            }),
+            (None, Some(width)) => Some(CellSpan {
+                span: width,
+                anchor: item_override.anchor.unwrap_or(Anchor::Left),
+                pad: item_override.pad.unwrap_or(' '),
+                overflow: OverflowPolicy::default(),
+                ellipsis: "",
+            }),
+            (None, None) => None,
+        };
+        let style = match item_override.style {
+            Some(_) if self.mode.is_json() => None,
+            Some(style) => Some(style),
+            None => self.style(item, column).copied(),
+        };
+        Cell { span, style }
+    }
+
+    /// Whether an item is to be wrapped in directional isolates
+    fn isolates(&self, item: &str) -> bool {
+        self.bidi_isolate && self.mode == OutputMode::Plain && !item.is_empty()
+    }
+
+    /// Style of an item at given column, as per the first rule selecting it
+    fn style(&self, item: &str, column: usize) -> Option<&Style> {
+        if self.mode.is_json() {
+            return None;
+        }
+        self.styles
+            .iter()
+            .find(|rule| rule.selector.selects(item, column))
+            .map(|rule| &rule.style)
+    }
+
+    /// Separator between items within a line, following an item ending at `position` within it
+    /// and preceding the item at given `column`
+    fn item_break(&self, column: usize, position: usize) -> Cow<'_, str> {
+        match (self.mode, self.tab_stops) {
+            (OutputMode::Json | OutputMode::JsonLines, _) => Cow::Borrowed(","),
+            (OutputMode::Plain | OutputMode::Csv, Some(TabStops::Spaces(width))) => {
+                let width = width.max(1);
+                Cow::Owned(" ".repeat(width - position % width))
+            }
+            (OutputMode::Plain | OutputMode::Csv, Some(TabStops::Tabs)) => Cow::Borrowed("\t"),
+            (OutputMode::Plain | OutputMode::Csv, None) => {
+                let separator = column
+                    .checked_sub(1)
+                    .and_then(|column| self.column_separators.get(column))
+                    .unwrap_or(&self.item_separator);
+                Cow::Borrowed(separator)
+            }
+        }
+    }
+
+    /// Separator ending a line, also when no line separation is configured
+    fn line_break(&self) -> &str {
+        match self.mode {
+            OutputMode::Plain | OutputMode::Csv => self
+                .line_separator
+                .as_ref()
+                .map_or("\n", |line_separator| &line_separator.line_separator),
+            OutputMode::Json if self.line_separator.is_some() => "],[",
+            OutputMode::Json => ",",
+            OutputMode::JsonLines if self.json_objects() => "}\n{",
+            OutputMode::JsonLines => "]\n[",
+        }
+    }
+
+    /// Emitted at the start of every line
+    fn line_start(&self) -> &str {
+        match self.mode {
+            OutputMode::Plain | OutputMode::Csv => &self.line_prefix,
+            OutputMode::Json | OutputMode::JsonLines => "",
+        }
+    }
+
+    /// Emitted at the end of every line followed by a line separator
+    fn line_end(&self) -> &str {
+        match self.mode {
+            OutputMode::Plain | OutputMode::Csv => &self.line_suffix,
+            OutputMode::Json | OutputMode::JsonLines => "",
+        }
+    }
+
+    /// Separator between pages, if the line at given index starts a new one
+    fn page_break(&self, line: usize) -> Option<&str> {
+        match (self.mode, &self.page_separator) {
+            (OutputMode::Plain | OutputMode::Csv, Some(page_separator))
+                if line > 0 && line.is_multiple_of(page_separator.lines_per_page.max(1)) =>
+            {
+                Some(&page_separator.page_separator)
+            }
+            _ => None,
+        }
+    }
+
+    /// Emitted before anything else
+    fn prologue(&self) -> &str {
+        match self.mode {
+            OutputMode::Plain | OutputMode::Csv => "",
+            OutputMode::Json if self.line_separator.is_some() => "[[",
+            OutputMode::Json => "[",
+            OutputMode::JsonLines if self.json_objects() => "{",
+            OutputMode::JsonLines => "[",
+        }
+    }
+
+    /// Emitted after everything else
+    fn epilogue(&self) -> &str {
+        match self.mode {
+            OutputMode::Plain | OutputMode::Csv => "",
+            OutputMode::Json if self.line_separator.is_some() => "]]",
+            OutputMode::Json => "]",
+            OutputMode::JsonLines if self.json_objects() => "}\n",
+            OutputMode::JsonLines => "]\n",
+        }
+    }
+
+    /// Line index of the item at given index, out of `count` items, unless lines are broken by
+    /// width
+    fn line(&self, index: usize, count: usize) -> usize {
+        match (&self.line_separator, self.fill_order) {
+            (Some(line_separator), FillOrder::RowMajor) => index / line_separator.items_per_line,
+            (Some(line_separator), FillOrder::ColumnMajor) => {
+                index % count.div_ceil(line_separator.items_per_line)
+            }
+            (None, _) => 0,
+        }
+    }
+
+    /// Column position within a line of the item at given index, out of `count` items
+    fn column(&self, index: usize, count: usize) -> usize {
+        match (&self.line_separator, self.fill_order) {
+            (Some(line_separator), FillOrder::RowMajor) => index % line_separator.items_per_line,
+            (Some(line_separator), FillOrder::ColumnMajor) => {
+                index / count.div_ceil(line_separator.items_per_line)
+            }
+            (None, _) => index,
+        }
+    }
+
+    /// Maximum line width, if lines are broken by width
+    fn max_line_width(&self) -> Option<usize> {
+        self.line_separator
+            .as_ref()
+            .and_then(|line_separator| line_separator.max_width)
+    }
+
+    /// Whether lines are to be buffered, so that items can be wrapped onto continuation lines
+    fn wraps(&self) -> bool {
+        !self.mode.is_json()
+            && self
+                .span
+                .iter()
+                .chain(&self.column_spans)
+                .any(|span| span.overflow == OverflowPolicy::Wrap)
+    }
+
+    /// Whether items are to be buffered and laid out column-major once all of them are known
+    fn column_major(&self) -> bool {
+        self.fill_order == FillOrder::ColumnMajor && self.line_separator.is_some()
+    }
+}
+
+impl NumberFormat {
+    fn apply(self, item: &str) -> Cow<'_, str> {
+        if !is_number(item) {
+            return Cow::Borrowed(item);
+        }
+        let exponent = item.contains(['e', 'E']);
+        let fixed = match (self.notation, self.precision) {
+            (Notation::Fixed, None) if !exponent => Cow::Borrowed(item),
+            (notation, precision) => {
+                let in_range = |decimal: &Decimal| {
+                    decimal.exponent.unsigned_abs() < f64::MAX_10_EXP.unsigned_abs().into()
+                };
+                let Some(mut decimal) = Decimal::parse(item).filter(in_range) else {
+                    return Cow::Borrowed(item);
+                };
+                if notation == Notation::Scientific {
+                    if let Some(precision) = precision {
+                        decimal.round(decimal.exponent - precision as i64);
+                    }
+                    return self.localize(Cow::Owned(decimal.scientific(precision)));
+                }
+                if let Some(precision) = precision {
+                    decimal.round(-(precision as i64));
+                }
+                Cow::Owned(decimal.fixed(precision.unwrap_or(0)))
+            }
+        };
+        match self.grouping {
+            Some(grouping) => Cow::Owned(group_digits(
+                &self.localize(fixed),
+                grouping,
+                self.decimal_point,
+            )),
+            None => self.localize(fixed),
+        }
+    }
+
+    /// Replace '.' decimal point of a formatted number with the configured one
+    fn localize(self, number: Cow<'_, str>) -> Cow<'_, str> {
+        if self.decimal_point != '.' && number.contains('.') {
+            Cow::Owned(number.replace('.', self.decimal_point.encode_utf8(&mut [0; 4])))
+        } else {
+            number
+        }
+    }
+}
+
+impl Case {
+    fn apply(self, item: &str) -> Cow<'_, str> {
+        match self {
+            Case::Preserve => Cow::Borrowed(item),
+            Case::Upper => Cow::Owned(item.to_uppercase()),
+            Case::Lower => Cow::Owned(item.to_lowercase()),
+            Case::Title => {
+                let mut title = String::with_capacity(item.len());
+                let mut in_word = false;
+                for c in item.chars() {
+                    if in_word {
+                        title.extend(c.to_lowercase());
+                    } else {
+                        title.extend(c.to_uppercase());
+                    }
+                    in_word = c.is_alphanumeric();
+                }
+                Cow::Owned(title)
+            }
+        }
+    }
+}
+
+impl Width {
+    /// Width of given item
+    pub fn of(self, item: &str) -> usize {
+        match self {
+            Width::Chars => item.chars().count(),
+            #[cfg(feature = "unicode-width")]
+            Width::Cells => unicode_width::UnicodeWidthStr::width(item),
+            #[cfg(feature = "unicode-segmentation")]
+            Width::Graphemes => {
+                unicode_segmentation::UnicodeSegmentation::graphemes(item, true).count()
+            }
+        }
+    }
+
+    /// Keep at most `width` of `item`, dropping characters on the side opposite to `anchor`
+    fn truncate(self, item: &str, width: usize, anchor: Anchor) -> &str {
+        match self {
+            Width::Chars => truncate(
+                item,
+                item.char_indices().map(|(i, _)| (i, 1)),
+                width,
+                anchor,
+            ),
+            #[cfg(feature = "unicode-width")]
+            Width::Cells => truncate(
+                item,
+                item.char_indices()
+                    .map(|(i, c)| (i, unicode_width::UnicodeWidthChar::width(c).unwrap_or(0))),
+                width,
+                anchor,
+            ),
+            #[cfg(feature = "unicode-segmentation")]
+            Width::Graphemes => truncate(
+                item,
+                unicode_segmentation::UnicodeSegmentation::grapheme_indices(item, true)
+                    .map(|(i, _)| (i, 1)),
+                width,
+                anchor,
+            ),
+        }
+    }
+
+    /// Split `item` into chunks of at most `width`, each holding at least one char
+    fn wrap(self, item: &str, width: usize) -> Vec<&str> {
+        let mut chunks = Vec::new();
+        let mut rest = item;
+        while !rest.is_empty() {
+            let mut chunk = self.truncate(rest, width, Anchor::Left);
+            if chunk.is_empty() {
+                let first = rest.chars().next().map_or(0, char::len_utf8);
+                chunk = &rest[..first];
+            }
+            chunks.push(chunk);
+            rest = &rest[chunk.len()..];
+        }
+        chunks
+    }
+}
+
+impl Template {
+    /// Template out of its `source` (see [Template]); invalid ones are an error
+    ///
+    /// [Template]: crate::Template
+    pub fn new(source: &str) -> Result<Self, LineupError> {
+        let invalid = |error: String| LineupError::Template(error);
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = source.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.next_if_eq(&'{').is_some() => text.push('{'),
+                '}' if chars.next_if_eq(&'}').is_some() => text.push('}'),
+                '}' => return Err(invalid("unmatched '}'".to_string())),
+                '{' => {
+                    let mut field = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => field.push(c),
+                            None => return Err(invalid("unterminated field".to_string())),
+                        }
+                    }
+                    let (name, spec) = field.split_once(':').unwrap_or((&field, ""));
+                    let variable = match name.trim() {
+                        "item" => TemplateVariable::Item,
+                        "index" => TemplateVariable::Index,
+                        "line" => TemplateVariable::Line,
+                        "column" => TemplateVariable::Column,
+                        name => return Err(invalid(format!("unknown variable '{name}'"))),
+                    };
+                    let align = |c| match c {
+                        '<' => Some(TemplateAlign::Left),
+                        '>' => Some(TemplateAlign::Right),
+                        '^' => Some(TemplateAlign::Center),
+                        _ => None,
+                    };
+                    let mut spec_chars = spec.chars();
+                    let (fill, align, width) = match (spec_chars.next(), spec_chars.next()) {
+                        (Some(fill), Some(c)) if align(c).is_some() => {
+                            (fill, align(c), &spec[fill.len_utf8() + 1..])
+                        }
+                        (Some(c), _) if align(c).is_some() => (' ', align(c), &spec[1..]),
+                        (Some('0'), Some(_)) => ('0', Some(TemplateAlign::Right), &spec[1..]),
+                        _ => (' ', None, spec),
+                    };
+                    let width = match width {
+                        "" => 0,
+                        width => width
+                            .parse()
+                            .map_err(|_| invalid(format!("invalid field spec '{spec}'")))?,
+                    };
+                    if !text.is_empty() {
+                        parts.push(TemplatePart::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(TemplatePart::Field {
+                        variable,
+                        fill,
+                        align,
+                        width,
+                    });
+                }
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(TemplatePart::Text(text));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            parts,
+        })
+    }
+
+    /// Render `item`, at given `index` and `position`, through the template, measuring field
+    /// values as per `width`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let template = lineup::Template::new("{line}.{column}:{item:>3}").unwrap();
+    /// let position = lineup::Position::new(1, 2);
+    /// let rendered = template.render("a", 5, position, lineup::Width::Chars);
+    /// assert_eq!(rendered, "1.2:  a");
+    /// ```
+    pub fn render(&self, item: &str, index: usize, position: Position, width: Width) -> String {
+        let mut rendered = String::new();
+        for part in &self.parts {
+            let (variable, fill, align, field_width) = match part {
+                TemplatePart::Text(text) => {
+                    rendered.push_str(text);
+                    continue;
+                }
+                TemplatePart::Field {
+                    variable,
+                    fill,
+                    align,
+                    width,
+                } => (*variable, *fill, *align, *width),
+            };
+            let value = match variable {
+                TemplateVariable::Item => Cow::Borrowed(item),
+                TemplateVariable::Index => Cow::Owned(index.to_string()),
+                TemplateVariable::Line => Cow::Owned(position.line.to_string()),
+                TemplateVariable::Column => Cow::Owned(position.column.to_string()),
+            };
+            let align = align.unwrap_or(match variable {
+                TemplateVariable::Item => TemplateAlign::Left,
+                _ => TemplateAlign::Right,
+            });
+            let pad = field_width.saturating_sub(width.of(&value));
+            let (left, right) = match align {
+                TemplateAlign::Left => (0, pad),
+                TemplateAlign::Right => (pad, 0),
+                TemplateAlign::Center => (pad / 2, pad - pad / 2),
+            };
+            rendered.extend(std::iter::repeat_n(fill, left));
+            rendered.push_str(&value);
+            rendered.extend(std::iter::repeat_n(fill, right));
+        }
+        rendered
+    }
+}
+
+impl std::str::FromStr for Template {
+    type Err = LineupError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Self::new(source)
+    }
+}
+
+impl TryFrom<String> for Template {
+    type Error = LineupError;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        Self::new(&source)
+    }
+}
+
+impl From<Template> for String {
+    fn from(template: Template) -> Self {
+        template.source
+    }
+}
+
+impl Display for Template {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Default for ItemSeparator {
+    fn default() -> Self {
+        Self::Explicit(",".to_string())
+    }
+}
+
+impl Display for ItemSeparator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Value of a format spec entry (see [InFormat::from_str])
+enum SpecValue {
+    /// No value, e.g. `trim`
+    Flag,
+    /// Bare word, e.g. `right` in `anchor=right`
+    Bare(String),
+    /// Single quoted string with backslash escapes, e.g. `'\n'` in `line='\n'`
+    Quoted(String),
+}
+
+#[derive(Clone, Debug)]
+/// Entry of a format spec section, e.g. `cols=4`
+struct SpecEntry {
+    key: String,
+    value: SpecValue,
+}
+
+impl SpecEntry {
+    fn invalid(&self) -> LineupError {
+        LineupError::Spec(format!("invalid value for '{}'", self.key))
+    }
+
+    fn string(&self) -> Result<String, LineupError> {
+        match &self.value {
+            SpecValue::Bare(value) | SpecValue::Quoted(value) => Ok(value.clone()),
+            SpecValue::Flag => Err(self.invalid()),
+        }
+    }
+
+    fn char(&self) -> Result<char, LineupError> {
+        let value = self.string()?;
+        let mut chars = value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(self.invalid()),
+        }
+    }
+
+    fn number(&self) -> Result<usize, LineupError> {
+        match &self.value {
+            SpecValue::Bare(value) => value.parse().map_err(|_| self.invalid()),
+            _ => Err(self.invalid()),
+        }
+    }
+
+    fn flag(&self) -> Result<bool, LineupError> {
+        match &self.value {
+            SpecValue::Flag => Ok(true),
+            SpecValue::Bare(value) => value.parse().map_err(|_| self.invalid()),
+            SpecValue::Quoted(_) => Err(self.invalid()),
+        }
+    }
+
+    /// Value named as one of `values`, e.g. `right` for [Anchor::Right]
+    fn named<T: Copy>(&self, values: &[(&str, T)]) -> Result<T, LineupError> {
+        match &self.value {
+            SpecValue::Bare(name) => values
+                .iter()
+                .find(|(value, _)| value == name)
+                .map(|(_, value)| *value)
+                .ok_or_else(|| self.invalid()),
+            _ => Err(self.invalid()),
+        }
+    }
+
+    fn item_separator(&self) -> Result<ItemSeparator, LineupError> {
+        let count = |count: &str| match count.parse() {
+            Ok(0) | Err(_) => Err(self.invalid()),
+            Ok(count) => Ok(count),
+        };
+        match &self.value {
+            SpecValue::Quoted(separator) => Ok(ItemSeparator::Explicit(separator.clone())),
+            SpecValue::Bare(separator) if separator == "whitespace" => {
+                Ok(ItemSeparator::Whitespace)
+            }
+            #[cfg(feature = "unicode-segmentation")]
+            SpecValue::Bare(separator) if separator.ends_with('g') => Ok(
+                ItemSeparator::GraphemeCount(count(&separator[..separator.len() - 1])?),
+            ),
+            SpecValue::Bare(separator) if separator.ends_with('c') => Ok(ItemSeparator::CharCount(
+                count(&separator[..separator.len() - 1])?,
+            )),
+            SpecValue::Bare(separator) => Ok(ItemSeparator::ByteCount(count(separator)?)),
+            SpecValue::Flag => Err(self.invalid()),
+        }
+    }
+}
+
+const QUOTE_ESCAPES: &[(&str, QuoteEscape)] = &[
+    ("doubled", QuoteEscape::Doubled),
+    ("backslash", QuoteEscape::Backslash),
+];
+
+const INPUT_MODES: &[(&str, InputMode)] = &[
+    ("plain", InputMode::Plain),
+    ("csv", InputMode::Csv),
+    ("tsv", InputMode::Tsv),
+    ("json", InputMode::Json),
+    ("json-lines", InputMode::JsonLines),
+];
+
+const EMPTY_ITEMS: &[(&str, EmptyItems)] = &[
+    ("terminate", EmptyItems::Terminate),
+    ("skip", EmptyItems::Skip),
+    ("yield", EmptyItems::Yield),
+];
+
+const PARTIAL_CHUNKS: &[(&str, PartialChunk)] = &[
+    ("drop", PartialChunk::Drop),
+    ("yield", PartialChunk::Yield),
+    ("error", PartialChunk::Error),
+];
+
+const INVALID_UTF8: &[(&str, InvalidUtf8)] = &[
+    ("error", InvalidUtf8::Error),
+    ("replace", InvalidUtf8::Replace),
+];
+
+const BYTE_ROUNDINGS: &[(&str, ByteRounding)] = &[
+    ("error", ByteRounding::Error),
+    ("forward", ByteRounding::Forward),
+    ("backward", ByteRounding::Backward),
+];
+
+const OVERFLOW_POLICIES: &[(&str, OverflowPolicy)] = &[
+    ("overflow", OverflowPolicy::Overflow),
+    ("truncate", OverflowPolicy::Truncate),
+    ("error", OverflowPolicy::Error),
+    ("wrap", OverflowPolicy::Wrap),
+];
+
+const SIDES: &[(&str, Side)] = &[("left", Side::Left), ("right", Side::Right)];
+
+const OUTPUT_MODES: &[(&str, OutputMode)] = &[
+    ("plain", OutputMode::Plain),
+    ("csv", OutputMode::Csv),
+    ("json", OutputMode::Json),
+    ("json-lines", OutputMode::JsonLines),
+];
+
+const CASES: &[(&str, Case)] = &[
+    ("preserve", Case::Preserve),
+    ("upper", Case::Upper),
+    ("lower", Case::Lower),
+    ("title", Case::Title),
+];
+
+const FILL_ORDERS: &[(&str, FillOrder)] = &[
+    ("row", FillOrder::RowMajor),
+    ("column", FillOrder::ColumnMajor),
+];
+
+const QUOTE_POLICIES: &[(&str, QuotePolicy)] = &[
+    ("never", QuotePolicy::Never),
+    ("when-needed", QuotePolicy::WhenNeeded),
+    ("always", QuotePolicy::Always),
+];
+
+/// Name of `value` among `values`
+fn spec_name<T: PartialEq>(values: &[(&'static str, T)], value: T) -> &'static str {
+    values
+        .iter()
+        .find(|(_, named)| *named == value)
+        .map_or("", |(name, _)| name)
+}
+
+/// `value` single quoted, with backslash escapes for quotes, backslashes and control characters
+fn spec_quote(value: &str) -> String {
+    let mut quoted = String::from("'");
+    for c in value.chars() {
+        match c {
+            '\'' | '\\' => quoted.extend(['\\', c]),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\0' => quoted.push_str("\\0"),
+            c if c.is_control() => quoted.extend(c.escape_unicode()),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// Entries of the sections of format `spec` prefixed with `section`, or with no prefix
+fn spec_entries(spec: &str, section: &str) -> Result<Vec<SpecEntry>, LineupError> {
+    let invalid = |error: &str| LineupError::Spec(error.to_string());
+    let mut entries = Vec::new();
+    let mut chars = spec.chars().peekable();
+    while chars.peek().is_some() {
+        let mut prefix = None;
+        let mut section_entries = Vec::new();
+        loop {
+            let mut key = String::new();
+            while let Some(c) = chars.next_if(|c| !"=,;:".contains(*c)) {
+                key.push(c);
+            }
+            let key = key.trim().to_string();
+            let value = match chars.peek() {
+                Some(':') if prefix.is_none() && section_entries.is_empty() => {
+                    chars.next();
+                    prefix = Some(key);
+                    continue;
+                }
+                Some('=') => {
+                    chars.next();
+                    if chars.next_if_eq(&'\'').is_some() {
+                        let mut value = String::new();
+                        loop {
+                            match chars.next().ok_or_else(|| invalid("unterminated quote"))? {
+                                '\'' => break,
+                                '\\' => value.push(
+                                    match chars
+                                        .next()
+                                        .ok_or_else(|| invalid("unterminated quote"))?
+                                    {
+                                        'n' => '\n',
+                                        'r' => '\r',
+                                        't' => '\t',
+                                        '0' => '\0',
+                                        'u' if chars.next_if_eq(&'{').is_some() => {
+                                            let mut code = String::new();
+                                            while let Some(c) = chars.next_if(|c| *c != '}') {
+                                                code.push(c);
+                                            }
+                                            chars.next();
+                                            u32::from_str_radix(&code, 16)
+                                                .ok()
+                                                .and_then(char::from_u32)
+                                                .ok_or_else(|| invalid("invalid unicode escape"))?
+                                        }
+                                        c => c,
+                                    },
+                                ),
+                                c => value.push(c),
+                            }
+                        }
+                        SpecValue::Quoted(value)
+                    } else {
+                        let mut value = String::new();
+                        while let Some(c) = chars.next_if(|c| !",;".contains(*c)) {
+                            value.push(c);
+                        }
+                        SpecValue::Bare(value.trim().to_string())
+                    }
+                }
+                _ => SpecValue::Flag,
+            };
+            if !key.is_empty() {
+                section_entries.push(SpecEntry { key, value });
+            } else if value != SpecValue::Flag {
+                return Err(invalid("missing key"));
+            }
+            match chars.next() {
+                Some(',') => {}
+                Some(';') | None => break,
+                Some(c) => return Err(LineupError::Spec(format!("unexpected '{c}'"))),
+            }
+        }
+        match prefix.as_deref() {
+            None => entries.extend(section_entries),
+            Some(prefix) if prefix == section => entries.extend(section_entries),
+            Some("in" | "out") => {}
+            Some(prefix) => return Err(LineupError::Spec(format!("unknown section '{prefix}'"))),
+        }
+    }
+    Ok(entries)
+}
+
+/// Line separator of a format spec, out of its `cols` or `record` (input only) or `width`
+/// (output only) and `line` entries
+fn spec_line_separator(
+    items_per_line: Option<usize>,
+    record_length: Option<RecordLength>,
+    max_width: Option<usize>,
+    line_separator: Option<String>,
+) -> Result<Option<LineSeparator>, LineupError> {
+    let line = || line_separator.clone().unwrap_or_else(|| "\n".to_string());
+    match (items_per_line, record_length, max_width) {
+        (Some(items_per_line), None, None) => Ok(Some(LineSeparator::new(items_per_line, line()))),
+        (None, Some(record_length), None) => {
+            Ok(Some(LineSeparator::by_length(record_length, line())))
+        }
+        (None, None, Some(max_width)) => Ok(Some(LineSeparator::by_width(max_width, line()))),
+        (None, None, None) if line_separator.is_none() => Ok(None),
+        (None, None, None) => Err(LineupError::Spec("'line' requires 'cols'".to_string())),
+        _ => Err(LineupError::Spec("conflicting line lengths".to_string())),
+    }
+}
+
+impl std::str::FromStr for InFormat {
+    type Err = LineupError;
+
+    /// Input format out of a format spec: `;` separated sections of `,` separated `key=value`
+    /// entries, where values are bare words or single quoted strings with backslash escapes
+    /// (`\n`, `\r`, `\t`, `\0`, `\u{..}`), and keys with no value are set flags; sections
+    /// prefixed with `out:` are ignored, so that a single spec can describe both input and
+    /// output formats (see [OutFormat::from_str]), e.g. `in:sep=',';out:span=8,anchor=right`
+    ///
+    /// keys, with values as per [InFormat] fields, are:
+    /// - `sep`: item separator, a quoted string, `N` bytes, `Nc` chars, `Ng` grapheme clusters
+    ///   or `whitespace`; `regex` and `widths` (e.g. `8/4/12`) set the other separators
+    /// - `cols` items per line, or `record` length of lines (`N` bytes or `Nc` chars), and
+    ///   `line` separator, defaulting to a new line
+    /// - `mode` (`plain`, `csv`, `tsv`, `json`, `json-lines`), `quote`, `quote_escape`
+    ///   (`doubled`, `backslash`), `escape`, `trim`, `empty` (`terminate`, `skip`, `yield`),
+    ///   `partial` (`drop`, `yield`, `error`), `invalid_utf8` (`error`, `replace`), `rounding`
+    ///   (`error`, `forward`, `backward`), `skip_lines`, `header`, `comment`, `skip`, `max`,
+    ///   `strip_bom`, `max_length`, `expect`, `expect_line`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let fmt: lineup::InFormat = "in:sep=',',cols=2,line='\\n',trim;out:sep='|'".parse().unwrap();
+    /// let items: Vec<_> = lineup::read("a, b\nc, d", fmt).collect();
+    /// assert_eq!(items, ["a", "b", "c", "d"]);
+    /// ```
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut builder = InFormatBuilder::default();
+        let (mut cols, mut record, mut line) = (None, None, None);
+        for entry in spec_entries(spec, "in")? {
+            match entry.key.as_str() {
+                "sep" => {
+                    builder.item_separator(entry.item_separator()?);
+                }
+                #[cfg(feature = "regex")]
+                "regex" => {
+                    builder.item_separator(ItemSeparator::Regex(entry.string()?));
+                }
+                "widths" => {
+                    let widths = entry.string()?;
+                    let widths = widths.split('/').map(str::parse).collect::<Result<_, _>>();
+                    let widths = widths.map_err(|_| entry.invalid())?;
+                    builder.item_separator(ItemSeparator::FieldWidths(widths));
+                }
+                "cols" => cols = Some(entry.number()?),
+                "record" => {
+                    let length = entry.string()?;
+                    record = Some(match length.strip_suffix('c') {
+                        Some(chars) => {
+                            RecordLength::Chars(chars.parse().map_err(|_| entry.invalid())?)
+                        }
+                        None => RecordLength::Bytes(length.parse().map_err(|_| entry.invalid())?),
+                    });
+                }
+                "line" => line = Some(entry.string()?),
+                "mode" => {
+                    builder.mode(entry.named(INPUT_MODES)?);
+                }
+                "quote" => {
+                    builder.quote(Some(entry.char()?));
+                }
+                "quote_escape" => {
+                    builder.quote_escape(entry.named(QUOTE_ESCAPES)?);
+                }
+                "escape" => {
+                    builder.escape(Some(entry.char()?));
+                }
+                "trim" => {
+                    builder.trim(entry.flag()?);
+                }
+                "empty" => {
+                    builder.empty_items(entry.named(EMPTY_ITEMS)?);
+                }
+                "partial" => {
+                    builder.partial_chunk(entry.named(PARTIAL_CHUNKS)?);
+                }
+                "invalid_utf8" => {
+                    builder.invalid_utf8(entry.named(INVALID_UTF8)?);
+                }
+                "rounding" => {
+                    builder.byte_rounding(entry.named(BYTE_ROUNDINGS)?);
+                }
+                "skip_lines" => {
+                    builder.skip_lines(entry.number()?);
+                }
+                "header" => {
+                    builder.header(entry.flag()?);
+                }
+                "comment" => {
+                    builder.comment(Some(entry.string()?));
+                }
+                "skip" => {
+                    builder.skip_items(entry.number()?);
+                }
+                "max" => {
+                    builder.max_items(Some(entry.number()?));
+                }
+                "strip_bom" => {
+                    builder.strip_bom(entry.flag()?);
+                }
+                "max_length" => {
+                    builder.max_item_length(Some(entry.number()?));
+                }
+                "expect" => {
+                    builder.expected_items(Some(entry.number()?));
+                }
+                "expect_line" => {
+                    builder.expected_items_per_line(Some(entry.number()?));
+                }
+                key => return Err(LineupError::Spec(format!("unknown key '{key}'"))),
+            }
+        }
+        builder
+            .line_separator(spec_line_separator(cols, record, None, line)?)
+            .build()
+    }
+}
+
+impl Display for InFormat {
+    /// Format spec of input format (see [InFormat::from_str])
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let fmt = lineup::InFormatBuilder::default()
+    ///     .item_separator(lineup::ItemSeparator::CharCount(2))
+    ///     .line_separator(Some(lineup::LineSeparator::new(3, ";".to_string())))
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(fmt.to_string(), "in:sep=2c,cols=3,line=';'");
+    /// ```
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut entries = vec![match &self.item_separator {
+            ItemSeparator::Explicit(separator) => format!("sep={}", spec_quote(separator)),
+            ItemSeparator::ByteCount(count) => format!("sep={count}"),
+            ItemSeparator::CharCount(count) => format!("sep={count}c"),
+            #[cfg(feature = "unicode-segmentation")]
+            ItemSeparator::GraphemeCount(count) => format!("sep={count}g"),
+            ItemSeparator::Whitespace => "sep=whitespace".to_string(),
+            #[cfg(feature = "regex")]
+            ItemSeparator::Regex(regex) => format!("regex={}", spec_quote(regex)),
+            ItemSeparator::FieldWidths(widths) => {
+                let widths: Vec<String> = widths.iter().map(usize::to_string).collect();
+                format!("widths={}", widths.join("/"))
+            }
+        }];
+        if let Some(line_separator) = &self.line_separator {
+            entries.push(match line_separator.record_length {
+                Some(RecordLength::Bytes(length)) => format!("record={length}"),
+                Some(RecordLength::Chars(length)) => format!("record={length}c"),
+                None => format!("cols={}", line_separator.items_per_line),
+            });
+            entries.push(format!(
+                "line={}",
+                spec_quote(&line_separator.line_separator)
+            ));
+        }
+        let mut named = |key, name, default| {
+            if name != default {
+                entries.push(format!("{key}={name}"));
+            }
+        };
+        named("mode", spec_name(INPUT_MODES, self.mode), "plain");
+        named(
+            "quote_escape",
+            spec_name(QUOTE_ESCAPES, self.quote_escape),
+            "doubled",
+        );
+        named(
+            "empty",
+            spec_name(EMPTY_ITEMS, self.empty_items),
+            "terminate",
+        );
+        named(
+            "partial",
+            spec_name(PARTIAL_CHUNKS, self.partial_chunk),
+            "drop",
+        );
+        named(
+            "invalid_utf8",
+            spec_name(INVALID_UTF8, self.invalid_utf8),
+            "error",
+        );
+        named(
+            "rounding",
+            spec_name(BYTE_ROUNDINGS, self.byte_rounding),
+            "error",
+        );
+        let chars = [("quote", self.quote), ("escape", self.escape)];
+        for (key, c) in chars {
+            if let Some(c) = c {
+                entries.push(format!("{key}={}", spec_quote(&c.to_string())));
+            }
+        }
+        if let Some(comment) = &self.comment {
+            entries.push(format!("comment={}", spec_quote(comment)));
+        }
+        let flags = [
+            ("trim", self.trim),
+            ("header", self.header),
+            ("strip_bom", self.strip_bom),
+        ];
+        entries.extend(
+            flags
+                .iter()
+                .filter(|(_, set)| *set)
+                .map(|(key, _)| key.to_string()),
+        );
+        let numbers = [
+            (
+                "skip_lines",
+                (self.skip_lines > 0).then_some(self.skip_lines),
+            ),
+            ("skip", (self.skip_items > 0).then_some(self.skip_items)),
+            ("max", self.max_items),
+            ("max_length", self.max_item_length),
+            ("expect", self.expected_items),
+            ("expect_line", self.expected_items_per_line),
+        ];
+        for (key, number) in numbers {
+            if let Some(number) = number {
+                entries.push(format!("{key}={number}"));
+            }
+        }
+        write!(f, "in:{}", entries.join(","))
+    }
+}
+
+impl std::str::FromStr for OutFormat {
+    type Err = LineupError;
+
+    /// Output format out of a format spec, as per [InFormat::from_str], with sections prefixed
+    /// with `in:` being ignored
+    ///
+    /// keys, with values as per [OutFormat] and [ItemSpan] fields, are:
+    /// - `span`, and along with it `pad`, `anchor` (`left`, `right`, `center`, `decimal`),
+    ///   `odd_pad` (`left`, `right`) for centered items, `point` and `fraction` for decimal
+    ///   anchor and `overflow` (`overflow`, `truncate`, `error`, `wrap`); they require `span`
+    /// - `ellipsis` of truncated items
+    /// - `sep`: item separator
+    /// - `cols` items per line, or `width` of lines, and `line` separator, defaulting to a new
+    ///   line
+    /// - `page` lines per page and `page_sep` separator, defaulting to a form feed
+    /// - `prefix` and `suffix` of lines, `item_prefix` and `item_suffix`, `template` (see
+    ///   [Template])
+    /// - `mode` (`plain`, `csv`, `json`, `json-lines`), `case` (`preserve`, `upper`, `lower`,
+    ///   `title`), `fill` (`row`, `column`), `quoting` (`never`, `when-needed`, `always`),
+    ///   `quote`, `quote_escape` (`doubled`, `backslash`), `tabs` (spaces width, or tabs if
+    ///   no value), `trailing`, `fill_last` and `bidi` flags
+    ///
+    /// column spans, anchors and pads, number formats, styles, width, header and footer are
+    /// not covered by format specs
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let spec = "in:sep=',';out:span=3,pad='_',anchor=right,sep='|',cols=2,line=';'";
+    /// let fmt: lineup::InFormat = spec.parse().unwrap();
+    /// let format: lineup::OutFormat = spec.parse().unwrap();
+    /// let mut output = Vec::new();
+    /// lineup::write(lineup::read("a,bb,ccc", fmt), &mut output, format).unwrap();
+    /// assert_eq!(String::from_utf8(output).unwrap(), "__a|_bb;ccc");
+    /// ```
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut builder = OutFormatBuilder::default();
+        let mut span_builder = ItemSpanBuilder::default();
+        let (mut span, mut anchor, mut odd_pad, mut point, mut fraction) =
+            (None, None, Side::Right, '.', 0);
+        let (mut cols, mut width, mut line) = (None, None, None);
+        let (mut page, mut page_separator) = (None, None);
+        let mut quoting = Quoting::default();
+        let mut span_settings = false;
+        for entry in spec_entries(spec, "out")? {
+            span_settings |= matches!(
+                entry.key.as_str(),
+                "pad" | "anchor" | "odd_pad" | "point" | "fraction" | "overflow"
+            );
+            match entry.key.as_str() {
+                "span" => span = Some(entry.number()?),
+                "pad" => {
+                    span_builder.pad(entry.char()?);
+                }
+                "anchor" => anchor = Some(entry.string()?),
+                "odd_pad" => odd_pad = entry.named(SIDES)?,
+                "point" => point = entry.char()?,
+                "fraction" => fraction = entry.number()?,
+                "overflow" => {
+                    span_builder.overflow(entry.named(OVERFLOW_POLICIES)?);
+                }
+                "ellipsis" => {
+                    builder.ellipsis(entry.string()?);
+                }
+                "sep" => {
+                    builder.item_separator(entry.string()?);
+                }
+                "cols" => cols = Some(entry.number()?),
+                "width" => width = Some(entry.number()?),
+                "line" => line = Some(entry.string()?),
+                "page" => page = Some(entry.number()?),
+                "page_sep" => page_separator = Some(entry.string()?),
+                "prefix" => {
+                    builder.line_prefix(entry.string()?);
+                }
+                "suffix" => {
+                    builder.line_suffix(entry.string()?);
+                }
+                "item_prefix" => {
+                    builder.item_prefix(entry.string()?);
+                }
+                "item_suffix" => {
+                    builder.item_suffix(entry.string()?);
+                }
+                "template" => {
+                    builder.template(Some(Template::new(&entry.string()?)?));
+                }
+                "mode" => {
+                    builder.mode(entry.named(OUTPUT_MODES)?);
+                }
+                "case" => {
+                    builder.case(entry.named(CASES)?);
+                }
+                "fill" => {
+                    builder.fill_order(entry.named(FILL_ORDERS)?);
+                }
+                "quoting" => quoting.policy = entry.named(QUOTE_POLICIES)?,
+                "quote" => quoting.quote = entry.char()?,
+                "quote_escape" => quoting.escape = entry.named(QUOTE_ESCAPES)?,
+                "tabs" => {
+                    builder.tab_stops(Some(match entry.value {
+                        SpecValue::Flag => TabStops::Tabs,
+                        _ => TabStops::Spaces(entry.number()?),
+                    }));
+                }
+                "trailing" => {
+                    builder.trailing_separator(entry.flag()?);
+                }
+                "fill_last" => {
+                    builder.fill_last_line(entry.flag()?);
+                }
+                "bidi" => {
+                    builder.bidi_isolate(entry.flag()?);
+                }
+                key => return Err(LineupError::Spec(format!("unknown key '{key}'"))),
+            }
+        }
+        if let Some(span) = span {
+            span_builder.span(span).anchor(match anchor.as_deref() {
+                None | Some("left") => Anchor::Left,
+                Some("right") => Anchor::Right,
+                Some("center") => Anchor::Center(odd_pad),
+                Some("decimal") => Anchor::Decimal { point, fraction },
+                Some(_) => return Err(LineupError::Spec("invalid value for 'anchor'".to_string())),
+            });
+            builder.span(Some(span_builder.build()?));
+        } else if span_settings {
+            return Err(LineupError::Spec(
+                "span settings require 'span'".to_string(),
+            ));
+        }
+        let page_separator = page_separator.unwrap_or_else(|| "\u{c}".to_string());
+        builder
+            .line_separator(spec_line_separator(cols, None, width, line)?)
+            .page_separator(page.map(|page| PageSeparator::new(page, page_separator)))
+            .quoting(quoting)
+            .build()
+    }
+}
+
+impl Display for OutFormat {
+    /// Format spec of output format (see [OutFormat::from_str]); settings not covered by
+    /// format specs are left out
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let format = lineup::OutFormatBuilder::default()
+    ///     .span(Some(lineup::ItemSpan::new(4, '_', lineup::Anchor::Right)))
+    ///     .item_separator("|".to_string())
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(format.to_string(), "out:sep='|',span=4,pad='_',anchor=right");
+    /// ```
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut entries = vec![format!("sep={}", spec_quote(&self.item_separator))];
+        if let Some(span) = &self.span {
+            entries.push(format!("span={}", span.span));
+            if span.pad != ' ' {
+                entries.push(format!("pad={}", spec_quote(&span.pad.to_string())));
+            }
+            match span.anchor {
+                Anchor::Left => {}
+                Anchor::Right => entries.push("anchor=right".to_string()),
+                Anchor::Center(side) => {
+                    entries.push("anchor=center".to_string());
+                    if side == Side::Left {
+                        entries.push("odd_pad=left".to_string());
+                    }
+                }
+                Anchor::Decimal { point, fraction } => {
+                    entries.push("anchor=decimal".to_string());
+                    if point != '.' {
+                        entries.push(format!("point={}", spec_quote(&point.to_string())));
+                    }
+                    if fraction > 0 {
+                        entries.push(format!("fraction={fraction}"));
+                    }
+                }
+            }
+            if span.overflow != OverflowPolicy::Overflow {
+                let overflow = spec_name(OVERFLOW_POLICIES, span.overflow);
+                entries.push(format!("overflow={overflow}"));
+            }
+        }
+        if !self.ellipsis.is_empty() {
+            entries.push(format!("ellipsis={}", spec_quote(&self.ellipsis)));
+        }
+        if let Some(line_separator) = &self.line_separator {
+            entries.push(match line_separator.max_width {
+                Some(max_width) => format!("width={max_width}"),
+                None => format!("cols={}", line_separator.items_per_line),
+            });
+            entries.push(format!(
+                "line={}",
+                spec_quote(&line_separator.line_separator)
+            ));
+        }
+        if let Some(page_separator) = &self.page_separator {
+            entries.push(format!("page={}", page_separator.lines_per_page));
+            let separator = spec_quote(&page_separator.page_separator);
+            entries.push(format!("page_sep={separator}"));
+        }
+        let strings = [
+            ("prefix", &self.line_prefix),
+            ("suffix", &self.line_suffix),
+            ("item_prefix", &self.item_prefix),
+            ("item_suffix", &self.item_suffix),
+        ];
+        for (key, string) in strings {
+            if !string.is_empty() {
+                entries.push(format!("{key}={}", spec_quote(string)));
+            }
+        }
+        if let Some(template) = &self.template {
+            entries.push(format!("template={}", spec_quote(&template.source)));
+        }
+        let mut named = |key, name, default| {
+            if name != default {
+                entries.push(format!("{key}={name}"));
+            }
+        };
+        named("mode", spec_name(OUTPUT_MODES, self.mode), "plain");
+        named("case", spec_name(CASES, self.case), "preserve");
+        named("fill", spec_name(FILL_ORDERS, self.fill_order), "row");
+        named(
+            "quoting",
+            spec_name(QUOTE_POLICIES, self.quoting.policy),
+            "never",
+        );
+        named(
+            "quote_escape",
+            spec_name(QUOTE_ESCAPES, self.quoting.escape),
+            "doubled",
+        );
+        if self.quoting.quote != '"' {
+            entries.push(format!(
+                "quote={}",
+                spec_quote(&self.quoting.quote.to_string())
+            ));
+        }
+        match self.tab_stops {
+            Some(TabStops::Spaces(width)) => entries.push(format!("tabs={width}")),
+            Some(TabStops::Tabs) => entries.push("tabs".to_string()),
+            None => {}
+        }
+        let flags = [
+            ("trailing", self.trailing_separator),
+            ("fill_last", self.fill_last_line),
+            ("bidi", self.bidi_isolate),
+        ];
+        entries.extend(
+            flags
+                .iter()
+                .filter(|(_, set)| *set)
+                .map(|(key, _)| key.to_string()),
+        );
+        write!(f, "out:{}", entries.join(","))
+    }
+}
+
+/// Write all input items as per provided format
+///
+/// # Examples
+///
+/// ```
+/// let input = ["😊😊", "👶", "💼💼💼"];
+/// let format = lineup::OutFormatBuilder::default()
+///     .span(Some(lineup::ItemSpan::new(4, '👉', lineup::Anchor::Right)))
+///     .item_separator("🖖".to_string())
+///     .line_separator(Some(lineup::LineSeparator::new(2, "🔩\n".to_string())))
+///     .build()
+///     .unwrap();
+/// let expected = "👉👉😊😊🖖👉👉👉👶🔩\n👉💼💼💼";
+/// let mut output = vec![0u8; 100 ];
+/// let written = lineup::write(input.into_iter(), output.as_mut_slice(), format).unwrap();
+/// assert_eq!(written.items, 3);
+/// let output = &output[..written.bytes];
+/// assert_eq!(String::from_utf8(output.to_vec()).unwrap(), expected);
+/// ```
+///
+/// items can be anything referencing a string, e.g. owned ones
+/// ```
+/// let input = vec!["a".to_string(), "b".to_string()];
+/// let format = lineup::OutFormatBuilder::default().build().unwrap();
+/// let mut output = Vec::new();
+/// lineup::write(input.into_iter(), &mut output, format).unwrap();
+/// assert_eq!(String::from_utf8(output).unwrap(), "a b");
+/// ```
+///
+/// returns the amount of output written (see [Written])
+///
+/// [Written]: crate::Written
+pub fn write<In, Out>(
+    istream: In,
+    mut ostream: Out,
+    format: OutFormat,
+) -> Result<Written, LineupError>
+where
+    In: IntoIterator,
+    In::Item: AsRef<str>,
+    Out: std::io::Write,
+{
+    let mut writer = ItemWriter::new(format);
+    let mut written = Written::default();
+    for item in istream {
+        written += writer.write(item.as_ref(), &mut ostream)?;
+    }
+    Ok(written + writer.finish(&mut ostream)?)
+}
+
+/// Write all input items as per provided format, with span computed from the widest item (see
+/// [align])
+///
+/// all items are buffered before writing since the span is not known up front; pad, anchor and
+/// overflow policy are taken from format span if any, otherwise [ItemSpanBuilder] defaults are
+/// used; format span width is ignored
+///
+/// when format has column spans, each of them gets the width of the widest item it applies to;
+/// header and footer labels are accounted for as items
+///
+/// # Examples
+///
+/// ```
+/// let input = ["a", "bbb", "cc"];
+/// let format = lineup::OutFormatBuilder::default()
+///     .span(Some(lineup::ItemSpan::new(1, '_', lineup::Anchor::Right)))
+///     .item_separator("|".to_string())
+///     .build()
+///     .unwrap();
+/// let mut output = Vec::new();
+/// lineup::write_aligned(input.into_iter(), &mut output, format).unwrap();
+/// assert_eq!(String::from_utf8(output).unwrap(), "__a|bbb|_cc");
+/// ```
+///
+/// [align]: crate::OutFormat::align
+/// [ItemSpanBuilder]: crate::ItemSpanBuilder
+pub fn write_aligned<In, Out>(
+    istream: In,
+    ostream: Out,
+    mut format: OutFormat,
+) -> Result<Written, LineupError>
+where
+    In: IntoIterator,
+    In::Item: AsRef<str>,
+    Out: std::io::Write,
+{
+    let items: Vec<In::Item> = istream.into_iter().collect();
+    let items: Vec<&str> = items.iter().map(AsRef::as_ref).collect();
+    format.align(&items);
+    write(items, ostream, format)
+}
+
+/// Write rows of items as per provided format, each row starting a new line; rows longer than
+/// the items per line of format, if any, go on over more lines
+///
+/// rows are not to be laid out column-major (see [FillOrder]), where items go on regardless of
+/// rows; in JSON output mode, rows are nested arrays only if format has a line separator
+///
+/// # Examples
+///
+/// ```
+/// let rows = [vec!["a", "b", "c"], vec![], vec!["d"]];
+/// let format = lineup::OutFormatBuilder::default()
+///     .span(Some(lineup::ItemSpan::new(2, '_', lineup::Anchor::Right)))
+///     .item_separator("|".to_string())
+///     .build()
+///     .unwrap();
+/// let mut output = Vec::new();
+/// lineup::write_rows(rows, &mut output, format).unwrap();
+/// assert_eq!(String::from_utf8(output).unwrap(), "_a|_b|_c\n_d");
+/// ```
+///
+/// [FillOrder]: crate::FillOrder
+pub fn write_rows<Rows, Out>(
+    rows: Rows,
+    mut ostream: Out,
+    format: OutFormat,
+) -> Result<Written, LineupError>
+where
+    Rows: IntoIterator,
+    Rows::Item: IntoIterator,
+    <Rows::Item as IntoIterator>::Item: AsRef<str>,
+    Out: std::io::Write,
+{
+    let mut writer = ItemWriter::new(format);
+    let mut written = Written::default();
+    for row in rows {
+        for item in row {
+            written += writer.write(item.as_ref(), &mut ostream)?;
+        }
+        written += writer.end_line(&mut ostream)?;
+    }
+    Ok(written + writer.finish(&mut ostream)?)
+}
+
+/// Turn rows of items into rows of the items at the same column position, i.e. columns, e.g. N
+/// rows of M items into M rows of N items; rows shorter than the longest one have their missing
+/// items taken as default ones, e.g. empty strings
+///
+/// # Examples
+///
+/// ```
+/// let rows = [vec!["a", "b", "c"], vec!["d", "e"]];
+/// let columns = lineup::transpose(rows);
+/// assert_eq!(columns, [vec!["a", "d"], vec!["b", "e"], vec!["c", ""]]);
+///
+/// let format = lineup::OutFormatBuilder::default().build().unwrap();
+/// let mut output = Vec::new();
+/// lineup::write_rows(columns, &mut output, format).unwrap();
+/// assert_eq!(String::from_utf8(output).unwrap(), "a d\nb e\nc ");
+/// ```
+pub fn transpose<Rows, Item>(rows: Rows) -> Vec<Vec<Item>>
+where
+    Rows: IntoIterator,
+    Rows::Item: IntoIterator<Item = Item>,
+    Item: Default,
+{
+    let mut columns: Vec<Vec<Item>> = Vec::new();
+    for (row, items) in rows.into_iter().enumerate() {
+        for (column, item) in items.into_iter().enumerate() {
+            if columns.len() <= column {
+                columns.resize_with(column + 1, || {
+                    std::iter::repeat_with(Item::default).take(row).collect()
+                });
+            }
+            columns[column].push(item);
+        }
+        for column in &mut columns {
+            column.resize_with(row + 1, Item::default);
+        }
+    }
+    columns
+}
+
+/// Write all input items as per provided format to an asynchronous sink (see [write])
+///
+/// # Examples
+///
+/// ```
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let format = lineup::OutFormatBuilder::default()
+///     .item_separator("|".to_string())
+///     .build()
+///     .unwrap();
+/// let mut output = Vec::new();
+/// lineup::write_async(["a", "b"].into_iter(), &mut output, format).await.unwrap();
+/// assert_eq!(String::from_utf8(output).unwrap(), "a|b");
+/// # })
+/// ```
+///
+/// [write]: crate::write
+#[cfg(feature = "async")]
+pub async fn write_async<In, Out>(
+    istream: In,
+    mut ostream: Out,
+    format: OutFormat,
+) -> Result<Written, LineupError>
+where
+    In: IntoIterator,
+    In::Item: AsRef<str>,
+    Out: tokio::io::AsyncWrite + Unpin,
+{
+    let mut writer = AsyncItemWriter::new(format);
+    let mut written = Written::default();
+    for item in istream {
+        written += writer.write(item.as_ref(), &mut ostream).await?;
+    }
+    Ok(written + writer.finish(&mut ostream).await?)
+}
+
+/// Write all input items as per provided format to a [std::fmt::Write] sink, e.g. a String (see
+/// [write])
+///
+/// # Examples
+///
+/// ```
+/// let format = lineup::OutFormatBuilder::default()
+///     .span(Some(lineup::ItemSpan::new(2, '_', lineup::Anchor::Right)))
+///     .item_separator("|".to_string())
+///     .build()
+///     .unwrap();
+/// let mut output = String::from(">");
+/// let written = lineup::write_fmt(["a", "b"], &mut output, format).unwrap();
+/// assert_eq!(output, ">_a|_b");
+/// assert_eq!(written.bytes, 5);
+/// ```
+///
+/// [write]: crate::write
+pub fn write_fmt<In, Out>(
+    istream: In,
+    ostream: &mut Out,
+    format: OutFormat,
+) -> Result<Written, LineupError>
+where
+    In: IntoIterator,
+    In::Item: AsRef<str>,
+    Out: std::fmt::Write,
+{
+    let mut ostream = FmtWriter::new(ostream);
+    let written = write(istream, &mut ostream, format)?;
+    ostream.finish()?;
+    Ok(written)
+}
+
+/// Write all input items as per provided format to a new String (see [write])
+///
+/// # Examples
+///
+/// ```
+/// let format = lineup::OutFormatBuilder::default()
+///     .item_separator("|".to_string())
+///     .line_separator(Some(lineup::LineSeparator::new(2, "\n".to_string())))
+///     .build()
+///     .unwrap();
+/// let output = lineup::write_string(["😊", "b", "c"], format).unwrap();
+/// assert_eq!(output, "😊|b\nc");
+/// ```
+///
+/// [write]: crate::write
+pub fn write_string<In>(istream: In, format: OutFormat) -> Result<String, LineupError>
+where
+    In: IntoIterator,
+    In::Item: AsRef<str>,
+{
+    let mut output = String::new();
+    write_fmt(istream, &mut output, format)?;
+    Ok(output)
+}
+
+/// Output stream forwarding bytes to a [std::fmt::Write] sink, holding back incomplete UTF-8
+/// sequences until they are completed
+struct FmtWriter<'w, W: std::fmt::Write> {
+    writer: &'w mut W,
+    /// Leading bytes of an incomplete UTF-8 sequence
+    pending: Vec<u8>,
+}
+
+impl<'w, W: std::fmt::Write> FmtWriter<'w, W> {
+    fn new(writer: &'w mut W) -> Self {
+        Self {
+            writer,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Check that no incomplete UTF-8 sequence is left over
+    fn finish(self) -> Result<(), std::io::Error> {
+        if self.pending.is_empty() {
+            Ok(())
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "output ends with an incomplete UTF-8 sequence",
+            ))
+        }
+    }
+}
+
+impl<W: std::fmt::Write> std::io::Write for FmtWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+        let bytes = if self.pending.is_empty() {
+            Cow::Borrowed(buf)
+        } else {
+            let mut bytes = std::mem::take(&mut self.pending);
+            bytes.extend_from_slice(buf);
+            Cow::Owned(bytes)
+        };
+        let valid = match std::str::from_utf8(&bytes) {
+            Ok(valid) => valid,
+            Err(error) if error.error_len().is_none() => {
+                self.pending = bytes[error.valid_up_to()..].to_vec();
+                std::str::from_utf8(&bytes[..error.valid_up_to()]).expect("valid UTF-8")
+            }
+            Err(error) => {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, error));
+            }
+        };
+        self.writer
+            .write_str(valid)
+            .map_err(std::io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+}
+
+/// Read items from `input` as per input format, then write them as per output format into a new
+/// String
+///
+/// items are unquoted as per input format (see [InFormat::unquote]); input header, if any, is
+/// written as output header unless output format has one
+///
+/// # Examples
+///
+/// ```
+/// let in_format = lineup::InFormatBuilder::default()
+///     .quote(Some('"'))
+///     .build()
+///     .unwrap();
+/// let out_format = lineup::OutFormatBuilder::default()
+///     .span(Some(lineup::ItemSpan::new(3, '_', lineup::Anchor::Right)))
+///     .item_separator("|".to_string())
+///     .line_separator(Some(lineup::LineSeparator::new(2, "\n".to_string())))
+///     .build()
+///     .unwrap();
+/// let output = lineup::reformat("a,\"b,c\",d,ee", in_format, out_format).unwrap();
+/// assert_eq!(output, "__a|b,c\n__d|_ee");
+/// ```
+///
+/// [InFormat::unquote]: crate::InFormat::unquote
+pub fn reformat(
+    input: &str,
+    in_format: InFormat,
+    out_format: OutFormat,
+) -> Result<String, LineupError> {
+    let mut output = String::new();
+    let mut ostream = FmtWriter::new(&mut output);
+    reformat_items(input, in_format, out_format, &mut ostream)?;
+    ostream.finish()?;
+    Ok(output)
+}
+
+/// Read items from `istream` as they are complete, in constant memory but for items themselves
+/// (see [StreamItemReader]), and write them to `ostream`, as [reformat] does
+///
+/// invalid UTF-8 input is an error or has it replaced as per input format (see [InvalidUtf8]);
+/// output is written as items are read, so part of it may have been written on error
+///
+/// # Examples
+///
+/// ```
+/// let in_format = lineup::InFormatBuilder::default().build().unwrap();
+/// let out_format = lineup::OutFormatBuilder::default()
+///     .item_separator("|".to_string())
+///     .build()
+///     .unwrap();
+/// let mut output = Vec::new();
+/// let written = lineup::reformat_io(&b"a,b,c"[..], &mut output, in_format, out_format).unwrap();
+/// assert_eq!(written.items, 3);
+/// assert_eq!(String::from_utf8(output).unwrap(), "a|b|c");
+/// ```
+///
+/// [reformat]: crate::reformat
+/// [InvalidUtf8]: crate::InvalidUtf8
+pub fn reformat_io<In, Out>(
+    istream: In,
+    mut ostream: Out,
+    in_format: InFormat,
+    mut out_format: OutFormat,
+) -> Result<Written, LineupError>
+where
+    In: std::io::Read,
+    Out: std::io::Write,
+{
+    let mut reader = StreamItemReader::new(istream, in_format.clone());
+    let first = reader.next_item()?;
+    if out_format.header.is_empty() {
+        out_format.header = reader.header().to_vec();
+    }
+    let mut writer = ItemWriter::new(out_format);
+    let mut written = Written::default();
+    for item in first.map(Ok).into_iter().chain(reader) {
+        written += writer.write(&in_format.unquote(&item?), &mut ostream)?;
+    }
+    Ok(written + writer.finish(&mut ostream)?)
+}
+
+/// Write items read from `input` to `ostream` (see [reformat])
+fn reformat_items<Out: std::io::Write>(
+    input: &str,
+    in_format: InFormat,
+    mut out_format: OutFormat,
+    ostream: &mut Out,
+) -> Result<Written, LineupError> {
+    let mut reader = ItemReader::new(input, in_format.clone());
+    if out_format.header.is_empty() {
+        out_format.header = reader.header()?.to_vec();
+    }
+    let mut writer = ItemWriter::new(out_format);
+    let mut written = Written::default();
+    for item in reader.fallible() {
+        written += writer.write(&in_format.unquote(item?), ostream)?;
+    }
+    Ok(written + writer.finish(ostream)?)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[builder(derive(Debug), build_fn(error = "LineupError"))]
+/// Format of a column of a [Table]; unset settings fall back to the ones of the table format
+///
+/// [Table]: crate::Table
+pub struct TableColumn {
+    #[builder(default = "None")]
+    /// Span width; if not set, the width of the widest item of the column, header label
+    /// included
+    pub span: Option<usize>,
+
+    #[builder(default = "None")]
+    /// Pad character
+    pub pad: Option<char>,
+
+    #[builder(default = "None")]
+    /// Anchor
+    pub anchor: Option<Anchor>,
+
+    #[builder(default = "None")]
+    /// Separator emitted after items of the column, within a line
+    pub separator: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+/// Items laid out in rows and columns, each column with its own format (see [TableColumn]);
+/// columns are as wide as their widest item unless given a span
+///
+/// the table format provides everything else, e.g. item separator, decorations and output mode;
+/// its span provides pad, anchor and overflow policy of columns not setting them, its line
+/// separator string separates rows, a new line if none
+///
+/// # Examples
+///
+/// ```
+/// let format = lineup::OutFormatBuilder::default()
+///     .item_separator(" | ".to_string())
+///     .build()
+///     .unwrap();
+/// let qty = lineup::TableColumnBuilder::default()
+///     .anchor(Some(lineup::Anchor::Right))
+///     .build()
+///     .unwrap();
+/// let mut table = lineup::Table::new(format);
+/// table
+///     .header(["name", "qty"])
+///     .column(1, qty)
+///     .row(["apple", "3"])
+///     .row(["fig", "12"]);
+/// assert_eq!(table.column_widths(), [5, 3]);
+/// assert_eq!(table.to_string(), "name  | qty\napple |   3\nfig   |  12");
+/// ```
+pub struct Table {
+    format: OutFormat,
+    header: Vec<String>,
+    columns: Vec<TableColumn>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(format: OutFormat) -> Self {
+        Self {
+            format,
+            header: Vec::new(),
+            columns: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Set header labels, one per column, instead of the header of the table format
+    pub fn header<Labels>(&mut self, labels: Labels) -> &mut Self
+    where
+        Labels: IntoIterator,
+        Labels::Item: Into<String>,
+    {
+        self.header = labels.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the format of the column at given position
+    pub fn column(&mut self, column: usize, format: TableColumn) -> &mut Self {
+        if self.columns.len() <= column {
+            self.columns.resize_with(column + 1, TableColumn::default);
+        }
+        self.columns[column] = format;
+        self
+    }
+
+    /// Append a row of items, one per column; rows shorter than others leave their last columns
+    /// empty
+    pub fn row<Row>(&mut self, row: Row) -> &mut Self
+    where
+        Row: IntoIterator,
+        Row::Item: Into<String>,
+    {
+        self.rows.push(row.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Rows of items appended so far
+    pub fn rows(&self) -> &[Vec<String>] {
+        &self.rows
+    }
+
+    /// Number of columns: the length of the longest row, header included
+    pub fn column_count(&self) -> usize {
+        self.rows
+            .iter()
+            .map(Vec::len)
+            .chain([self.labels().len()])
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Width of the widest item of each column, header label included, as rendered by the
+    /// table format
+    pub fn column_widths(&self) -> Vec<usize> {
+        let mut widths = vec![0; self.column_count()];
+        let labels = self.labels().iter().enumerate().map(|(column, label)| {
+            let label = self.format.render(label, Position::new(0, column), None);
+            (column, self.format.width.of(&label))
+        });
+        let items = self.rows.iter().enumerate().flat_map(|(line, row)| {
+            row.iter()
+                .enumerate()
+                .map(move |(column, item)| (Position::new(line, column), item))
+        });
+        let items = items.enumerate().map(|(index, (position, item))| {
+            let item = self.format.render(item, position, Some(index));
+            (position.column, self.format.width.of(&item))
+        });
+        for (column, width) in labels.chain(items) {
+            widths[column] = widths[column].max(width);
+        }
+        widths
+    }
+
+    /// Output format the table is written with, spans, anchors, separators and header resolved
+    pub fn format(&self) -> OutFormat {
+        let mut format = self.format.clone();
+        let items = self.rows.iter().flat_map(|row| {
+            row.iter()
+                .enumerate()
+                .map(|(column, item)| (column, item.as_str()))
+        });
+        let detected = if self.format.detect_anchors {
+            detect_anchors(items)
+        } else {
+            Vec::new()
+        };
+        let default = TableColumn::default();
+        let span = self
+            .format
+            .span
+            .unwrap_or_else(|| ItemSpanBuilder::default().span(0).build().unwrap());
+        let columns: Vec<_> = self
+            .column_widths()
+            .into_iter()
+            .enumerate()
+            .map(|(column, width)| (self.columns.get(column).unwrap_or(&default), width))
+            .collect();
+        format.column_spans = columns
+            .iter()
+            .enumerate()
+            .map(|(index, (column, width))| ItemSpan {
+                span: column.span.unwrap_or(*width),
+                pad: column
+                    .pad
+                    .or_else(|| self.format.column_pads.get(index).copied())
+                    .unwrap_or(span.pad),
+                anchor: column
+                    .anchor
+                    .or_else(|| self.format.column_anchors.get(index).copied())
+                    .or_else(|| detected.get(index).copied())
+                    .unwrap_or(span.anchor),
+                ..span
+            })
+            .collect();
+        format.column_pads = Vec::new();
+        format.column_anchors = Vec::new();
+        format.column_separators = columns
+            .iter()
+            .map(|(column, _)| {
+                column
+                    .separator
+                    .clone()
+                    .unwrap_or_else(|| self.format.item_separator.clone())
+            })
+            .collect();
+        let line_separator = self
+            .format
+            .line_separator
+            .as_ref()
+            .map_or("\n", |line_separator| {
+                line_separator.line_separator.as_str()
+            });
+        format.line_separator = Some(LineSeparator::new(
+            columns.len().max(1),
+            line_separator.to_string(),
+        ));
+        format.header = self.labels().to_vec();
+        format
+    }
+
+    /// Write the table, one line per row (see [write_rows])
+    ///
+    /// [write_rows]: crate::write_rows
+    pub fn write<Out: std::io::Write>(&self, ostream: Out) -> Result<Written, LineupError> {
+        write_rows(&self.rows, ostream, self.format())
+    }
+
+    /// Header labels, the table ones if any, otherwise the table format ones
+    fn labels(&self) -> &[String] {
+        if self.header.is_empty() {
+            &self.format.header
+        } else {
+            &self.header
+        }
+    }
+}
+
+impl Display for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut ostream = FmtWriter::new(f);
+        self.write(&mut ostream).map_err(|_| std::fmt::Error)?;
+        ostream.finish().map_err(|_| std::fmt::Error)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// How items are compared when sorted (see [Sort])
+///
+/// [Sort]: crate::Sort
+pub enum SortOrder {
+    /// By their characters, i.e. Unicode scalar values
+    #[default]
+    Lexical,
+    /// By their value, for items looking like numbers, leading and trailing whitespace ignored;
+    /// other items follow, lexically
+    Numeric,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Builder)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[builder(derive(Debug), build_fn(error = "LineupError"))]
+/// Sort of items, or of rows of items by the item at a column position; sorting is stable
+///
+/// it can be parsed from a comma separated list of options: 'lexical' or 'numeric' (see
+/// [SortOrder]), 'reverse', 'column=K' and 'row=N'
+///
+/// # Examples
+///
+/// ```
+/// let sort: lineup::Sort = "numeric,reverse".parse().unwrap();
+/// let mut items = vec!["2", "10", "x", "1"];
+/// sort.apply(&mut items);
+/// assert_eq!(items, ["x", "10", "2", "1"]);
+///
+/// let sort: lineup::Sort = "column=1,row=2".parse().unwrap();
+/// let mut items = vec!["a", "z", "b", "y"];
+/// sort.apply(&mut items);
+/// assert_eq!(items, ["b", "y", "a", "z"]);
+/// ```
+///
+/// [SortOrder]: crate::SortOrder
+pub struct Sort {
+    #[builder(default = "SortOrder::default()")]
+    /// How items are compared
+    pub order: SortOrder,
+
+    #[builder(default = "false")]
+    /// Sort in descending order
+    pub reverse: bool,
+
+    #[builder(default = "None")]
+    /// Column position of the item rows are sorted by, rather than sorting items
+    pub column: Option<usize>,
+
+    #[builder(default = "0")]
+    /// Number of items per row when sorting rows, 0 for a single row, left as it is; a last row
+    /// shorter than others sorts as having empty items
+    pub row_length: usize,
+}
+
+impl Sort {
+    /// Sort `items` in place
+    pub fn apply<Item: AsRef<str>>(&self, items: &mut Vec<Item>) {
+        let (column, row_length) = match self.column {
+            Some(column) if self.row_length > 0 => (column, self.row_length),
+            Some(_) => return,
+            None => {
+                items.sort_by(|a, b| self.compare(a.as_ref(), b.as_ref()));
+                return;
+            }
+        };
+        let mut rows: Vec<Vec<Item>> = Vec::new();
+        for (index, item) in std::mem::take(items).into_iter().enumerate() {
+            match rows.last_mut() {
+                Some(row) if index % row_length > 0 => row.push(item),
+                _ => rows.push(vec![item]),
+            }
+        }
+        rows.sort_by(|a, b| {
+            let (a, b) = (a.get(column), b.get(column));
+            self.compare(a.map_or("", AsRef::as_ref), b.map_or("", AsRef::as_ref))
+        });
+        items.extend(rows.into_iter().flatten());
+    }
+
+    fn compare(&self, a: &str, b: &str) -> std::cmp::Ordering {
+        let ordering = match self.order {
+            SortOrder::Lexical => a.cmp(b),
+            SortOrder::Numeric => {
+                let number = |item: &str| {
+                    let item = item.trim();
+                    is_number(item).then(|| item.parse::<f64>().ok()).flatten()
+                };
+                match (number(a), number(b)) {
+                    (Some(x), Some(y)) => x.total_cmp(&y),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => a.cmp(b),
+                }
+            }
+        };
+        if self.reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
+impl std::str::FromStr for Sort {
+    type Err = LineupError;
+
+    fn from_str(options: &str) -> Result<Self, Self::Err> {
+        let mut sort = Self::default();
+        let number = |value: &str| {
+            value
+                .parse()
+                .map_err(|_| LineupError::Stage(format!("invalid sort option value '{value}'")))
+        };
+        for option in options.split(',').filter(|option| !option.is_empty()) {
+            match option.split_once('=') {
+                None if option == "lexical" => sort.order = SortOrder::Lexical,
+                None if option == "numeric" => sort.order = SortOrder::Numeric,
+                None if option == "reverse" => sort.reverse = true,
+                Some(("column", value)) => sort.column = Some(number(value)?),
+                Some(("row", value)) => sort.row_length = number(value)?,
+                _ => {
+                    return Err(LineupError::Stage(format!(
+                        "unknown sort option '{option}'"
+                    )));
+                }
+            }
+        }
+        Ok(sort)
+    }
+}
+
+impl Display for Sort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut options = vec![match self.order {
+            SortOrder::Lexical => "lexical".to_string(),
+            SortOrder::Numeric => "numeric".to_string(),
+        }];
+        if self.reverse {
+            options.push("reverse".to_string());
+        }
+        if let Some(column) = self.column {
+            options.push(format!("column={column}"));
+        }
+        if self.row_length > 0 {
+            options.push(format!("row={}", self.row_length));
+        }
+        f.write_str(&options.join(","))
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Stage of a [Pipeline] that can be described as data, e.g. parsed from a command line argument:
+/// 'trim', 'keep=REGEX', 'discard=REGEX', 'skip=N', 'take=N' or 'sort[=OPTIONS]' (see [Sort])
+///
+/// # Examples
+///
+/// ```
+/// let stage: lineup::Stage = "take=3".parse().unwrap();
+/// assert!(matches!(stage, lineup::Stage::Take(3)));
+/// assert_eq!(stage.to_string(), "take=3");
+/// assert!("take=x".parse::<lineup::Stage>().is_err());
+/// ```
+///
+/// [Pipeline]: crate::Pipeline
+/// [Sort]: crate::Sort
+pub enum Stage {
+    /// Trim leading and trailing whitespace from items
+    Trim,
+    /// Keep items matching given regular expression, discarding others
+    #[cfg(feature = "regex")]
+    #[cfg_attr(feature = "serde", serde(with = "serde_regex"))]
+    Keep(regex::Regex),
+    /// Discard items matching given regular expression
+    #[cfg(feature = "regex")]
+    #[cfg_attr(feature = "serde", serde(with = "serde_regex"))]
+    Discard(regex::Regex),
+    /// Discard the first given number of items reaching this stage
+    Skip(usize),
+    /// Keep the first given number of items reaching this stage, ending the pipeline then
+    Take(usize),
+    /// Sort items reaching this stage, buffering all of them (see [Sort])
+    Sort(Sort),
+}
+
+impl std::str::FromStr for Stage {
+    type Err = LineupError;
+
+    fn from_str(stage: &str) -> Result<Self, Self::Err> {
+        let (name, value) = match stage.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (stage, None),
+        };
+        let count = || {
+            value
+                .ok_or_else(|| LineupError::Stage(format!("'{name}' needs a number of items")))?
+                .parse()
+                .map_err(|_| LineupError::Stage(format!("'{name}' needs a number of items")))
+        };
+        #[cfg(feature = "regex")]
+        let regex = || {
+            let pattern = value.ok_or_else(|| {
+                LineupError::Stage(format!("'{name}' needs a regular expression"))
+            })?;
+            regex::Regex::new(pattern).map_err(|error| LineupError::Stage(error.to_string()))
+        };
+        match name {
+            "trim" if value.is_none() => Ok(Self::Trim),
+            #[cfg(feature = "regex")]
+            "keep" => Ok(Self::Keep(regex()?)),
+            #[cfg(feature = "regex")]
+            "discard" => Ok(Self::Discard(regex()?)),
+            "skip" => Ok(Self::Skip(count()?)),
+            "take" => Ok(Self::Take(count()?)),
+            "sort" => Ok(Self::Sort(value.unwrap_or("").parse()?)),
+            _ => Err(LineupError::Stage(format!("unknown stage '{stage}'"))),
+        }
+    }
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Trim => f.write_str("trim"),
+            #[cfg(feature = "regex")]
+            Self::Keep(regex) => write!(f, "keep={regex}"),
+            #[cfg(feature = "regex")]
+            Self::Discard(regex) => write!(f, "discard={regex}"),
+            Self::Skip(count) => write!(f, "skip={count}"),
+            Self::Take(count) => write!(f, "take={count}"),
+            Self::Sort(sort) if *sort == Sort::default() => f.write_str("sort"),
+            Self::Sort(sort) => write!(f, "sort={sort}"),
+        }
+    }
+}
+
+/// User predicate items are kept by (see [Pipeline::filter])
+type Filter = Box<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Stage of a [Pipeline], user callbacks included
+enum PipelineStage {
+    Trim,
+    Map(Transform),
+    Filter(Filter),
+    Skip(usize),
+    Take(usize),
+    Sort(Sort),
+}
+
+#[derive(Default)]
+/// Chain of transforms and filters items go through between reading and writing, e.g. between
+/// an [ItemReader] and an [ItemWriter], in the order stages are added
+///
+/// # Examples
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// let pipeline = lineup::Pipeline::default()
+///     .trim()
+///     .filter(|item| !item.is_empty())
+///     .map(|item| Cow::Owned(item.to_uppercase()))
+///     .take(2);
+/// let fmt = lineup::InFormatBuilder::default().build().unwrap();
+/// let items = lineup::read(" a , , b,c", fmt);
+/// let format = lineup::OutFormatBuilder::default()
+///     .item_separator("|".to_string())
+///     .build()
+///     .unwrap();
+/// let output = lineup::write_string(pipeline.apply(items), format).unwrap();
+/// assert_eq!(output, "A|B");
+/// ```
+///
+/// stages can be configured from data as well (see [Stage])
+/// ```
+/// let pipeline: lineup::Pipeline = ["skip=1", "take=2"]
+///     .iter()
+///     .map(|stage| stage.parse::<lineup::Stage>().unwrap())
+///     .collect();
+/// let items: Vec<_> = pipeline.apply(["a", "b", "c", "d"]).collect();
+/// assert_eq!(items, ["b", "c"]);
+/// ```
+///
+/// [ItemReader]: crate::ItemReader
+/// [ItemWriter]: crate::ItemWriter
+pub struct Pipeline {
+    stages: Vec<PipelineStage>,
+}
+
+impl Pipeline {
+    /// Trim leading and trailing whitespace from items
+    pub fn trim(self) -> Self {
+        self.push(PipelineStage::Trim)
+    }
+
+    /// Replace items by what `transform` makes of them
+    pub fn map<F>(self, transform: F) -> Self
+    where
+        F: for<'a> Fn(&'a str) -> Cow<'a, str> + Send + Sync + 'static,
+    {
+        self.push(PipelineStage::Map(Box::new(transform)))
+    }
+
+    /// Keep items `filter` holds true for, discarding others
+    pub fn filter<F>(self, filter: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.push(PipelineStage::Filter(Box::new(filter)))
+    }
+
+    /// Discard the first `count` items reaching this stage
+    pub fn skip(self, count: usize) -> Self {
+        self.push(PipelineStage::Skip(count))
+    }
+
+    /// Keep the first `count` items reaching this stage; no more input is read after them
+    pub fn take(self, count: usize) -> Self {
+        self.push(PipelineStage::Take(count))
+    }
+
+    /// Sort items, or rows of items, reaching this stage (see [Sort]); all of them are buffered
+    /// until no more are coming
+    pub fn sort(self, sort: Sort) -> Self {
+        self.push(PipelineStage::Sort(sort))
+    }
+
+    /// Add a stage described as data (see [Stage])
+    pub fn stage(self, stage: Stage) -> Self {
+        match stage {
+            Stage::Trim => self.trim(),
+            #[cfg(feature = "regex")]
+            Stage::Keep(regex) => self.filter(move |item| regex.is_match(item)),
+            #[cfg(feature = "regex")]
+            Stage::Discard(regex) => self.filter(move |item| !regex.is_match(item)),
+            Stage::Skip(count) => self.skip(count),
+            Stage::Take(count) => self.take(count),
+            Stage::Sort(sort) => self.sort(sort),
+        }
+    }
+
+    /// Get an iterator over `items` gone through the pipeline, borrowed ones staying borrowed
+    /// unless transformed
+    pub fn apply<'p, 'i, In>(&'p self, items: In) -> PipelineItems<'p, 'i, In::IntoIter>
+    where
+        In: IntoIterator,
+        In::Item: Into<Cow<'i, str>>,
+    {
+        PipelineItems {
+            pipeline: self,
+            items: items.into_iter(),
+            counts: vec![0; self.stages.len()],
+            exhausted: false,
+            pending: None,
+            buffered: None,
+            item: std::marker::PhantomData,
+        }
+    }
+
+    fn push(mut self, stage: PipelineStage) -> Self {
+        self.stages.push(stage);
+        self
+    }
+}
+
+impl FromIterator<Stage> for Pipeline {
+    fn from_iter<T: IntoIterator<Item = Stage>>(stages: T) -> Self {
+        stages.into_iter().fold(Self::default(), Self::stage)
+    }
+}
+
+/// Iterator over items gone through a [Pipeline], as returned from [Pipeline::apply]
+pub struct PipelineItems<'p, 'i, In> {
+    pipeline: &'p Pipeline,
+    items: In,
+    /// Items counted so far by each stage, for those counting them
+    counts: Vec<usize>,
+    /// Whether the current source of items is over, as a take stage has had all of its items
+    exhausted: bool,
+    /// Items buffered by a sort stage, with its index, until its source is over
+    pending: Option<(usize, Vec<Cow<'i, str>>)>,
+    /// Sorted items, if any, with the index of the stage they go on from
+    buffered: Option<(usize, std::vec::IntoIter<Cow<'i, str>>)>,
+    item: std::marker::PhantomData<Cow<'i, str>>,
+}
+
+impl<'i, In> PipelineItems<'_, 'i, In> {
+    /// Take an item through the pipeline, starting from given stage; `None` if it is discarded
+    /// or buffered by a sort stage
+    fn process(&mut self, mut item: Cow<'i, str>, from: usize) -> Option<Cow<'i, str>> {
+        let stages = self.pipeline.stages.iter().zip(&mut self.counts);
+        for (index, (stage, count)) in stages.enumerate().skip(from) {
+            match stage {
+                PipelineStage::Trim => {
+                    item = match item {
+                        Cow::Borrowed(item) => Cow::Borrowed(item.trim()),
+                        Cow::Owned(item) if item.trim().len() == item.len() => Cow::Owned(item),
+                        Cow::Owned(item) => Cow::Owned(item.trim().to_string()),
+                    }
+                }
+                PipelineStage::Map(transform) => {
+                    item = match item {
+                        Cow::Borrowed(item) => transform(item),
+                        Cow::Owned(item) => Cow::Owned(transform(&item).into_owned()),
+                    }
+                }
+                PipelineStage::Filter(filter) => {
+                    if !filter(&item) {
+                        return None;
+                    }
+                }
+                PipelineStage::Skip(skip) => {
+                    if *count < *skip {
+                        *count += 1;
+                        return None;
+                    }
+                }
+                PipelineStage::Take(take) => {
+                    if *count >= *take {
+                        self.exhausted = true;
+                        return None;
+                    }
+                    *count += 1;
+                    if *count == *take {
+                        self.exhausted = true;
+                    }
+                }
+                PipelineStage::Sort(_) => {
+                    let (_, items) = self.pending.get_or_insert_with(|| (index, Vec::new()));
+                    items.push(item);
+                    return None;
+                }
+            }
+        }
+        Some(item)
+    }
+}
+
+impl<'i, In> Iterator for PipelineItems<'_, 'i, In>
+where
+    In: Iterator,
+    In::Item: Into<Cow<'i, str>>,
+{
+    type Item = Cow<'i, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (from, item) = match &mut self.buffered {
+                _ if self.exhausted => (0, None),
+                Some((from, items)) => (*from, items.next()),
+                None => (0, self.items.next().map(Into::into)),
+            };
+            let Some(item) = item else {
+                // source is over: carry on with items buffered by a sort stage, if any
+                let (stage, mut items) = self.pending.take()?;
+                if let PipelineStage::Sort(sort) = &self.pipeline.stages[stage] {
+                    sort.apply(&mut items);
+                }
+                self.buffered = Some((stage + 1, items.into_iter()));
+                self.exhausted = false;
+                continue;
+            };
+            if let Some(item) = self.process(item, from) {
+                return Some(item);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Default)]
+/// Metrics of items, e.g. to pick a span before writing them (see [stats]); its display is a
+/// report of them, with a histogram of item widths
+///
+/// [stats]: crate::stats
+pub struct Stats {
+    /// Number of items
+    pub items: usize,
+    /// Number of input lines with items
+    pub lines: usize,
+    /// Width of the narrowest item, 0 if none
+    pub min_width: usize,
+    /// Width of the widest item, 0 if none
+    pub max_width: usize,
+    /// Mean item width, 0 if none
+    pub mean_width: f64,
+    /// Number of items of each width, by width
+    pub histogram: std::collections::BTreeMap<usize, usize>,
+}
+
+impl Stats {
+    /// Metrics of `items`, measured as per `width`, lines left uncounted
+    pub fn new<In>(items: In, width: Width) -> Self
+    where
+        In: IntoIterator,
+        In::Item: AsRef<str>,
+    {
+        let mut histogram = std::collections::BTreeMap::new();
+        for item in items {
+            *histogram.entry(width.of(item.as_ref())).or_default() += 1;
+        }
+        Self::from_histogram(histogram)
+    }
+
+    /// Metrics of items given the number of them of each width, lines left uncounted
+    fn from_histogram(histogram: std::collections::BTreeMap<usize, usize>) -> Self {
+        let items = histogram.values().sum();
+        let total: usize = histogram.iter().map(|(width, count)| width * count).sum();
+        Self {
+            items,
+            lines: 0,
+            min_width: histogram.keys().next().copied().unwrap_or(0),
+            max_width: histogram.keys().next_back().copied().unwrap_or(0),
+            mean_width: if items > 0 {
+                total as f64 / items as f64
+            } else {
+                0.0
+            },
+            histogram,
+        }
+    }
+}
+
+impl std::ops::Add for Stats {
+    type Output = Self;
+
+    /// Metrics of the items of both, e.g. read from separate inputs
+    fn add(self, other: Self) -> Self {
+        let mut histogram = self.histogram;
+        for (width, count) in other.histogram {
+            *histogram.entry(width).or_default() += count;
+        }
+        Self {
+            lines: self.lines + other.lines,
+            ..Self::from_histogram(histogram)
+        }
+    }
+}
+
+impl std::ops::AddAssign for Stats {
+    fn add_assign(&mut self, other: Self) {
+        *self = std::mem::take(self) + other;
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const BAR_WIDTH: usize = 40;
+        writeln!(f, "items: {}", self.items)?;
+        writeln!(f, "lines: {}", self.lines)?;
+        writeln!(
+            f,
+            "width: min {}, max {}, mean {:.2}",
+            self.min_width, self.max_width, self.mean_width
+        )?;
+        let most = self.histogram.values().copied().max().unwrap_or(0);
+        let width_digits = self.max_width.to_string().len();
+        let count_digits = most.to_string().len();
+        for (width, count) in &self.histogram {
+            let bar = (count * BAR_WIDTH).div_ceil(most);
+            writeln!(
+                f,
+                "{width:>width_digits$} {count:>count_digits$} {}",
+                "#".repeat(bar)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Metrics of the items of `input`, as per input format, unquoted, measured as per `width`
+///
+/// # Examples
+///
+/// ```
+/// let fmt = lineup::InFormatBuilder::default()
+///     .item_separator(lineup::ItemSeparator::Whitespace)
+///     .build()
+///     .unwrap();
+/// let stats = lineup::stats("a bbb\ncc d", fmt, lineup::Width::Chars).unwrap();
+/// assert_eq!((stats.items, stats.lines), (4, 2));
+/// assert_eq!((stats.min_width, stats.max_width, stats.mean_width), (1, 3, 1.75));
+/// assert_eq!(
+///     stats.to_string(),
+///     "items: 4\nlines: 2\nwidth: min 1, max 3, mean 1.75\n\
+///      1 2 ########################################\n\
+///      2 1 ####################\n\
+///      3 1 ####################\n"
+/// );
+/// ```
+pub fn stats(input: &str, format: InFormat, width: Width) -> Result<Stats, LineupError> {
+    let mut reader = ItemReader::new(input, format.clone());
+    let items = std::iter::from_fn(|| reader.try_next().transpose())
+        .map(|item| item.map(|item| format.unquote(item)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut stats = Stats::new(items, width);
+    stats.lines = reader.lines();
+    Ok(stats)
+}
+
+/// Check all of `input` conforms to input format, e.g. to its expected items per line, its
+/// maximum item length or to fixed byte size items not splitting UTF-8 code points, returning
+/// the number of items read or the first violation found
+///
+/// # Examples
+///
+/// ```
+/// let fmt = lineup::InFormatBuilder::default()
+///     .item_separator(lineup::ItemSeparator::Whitespace)
+///     .expected_items_per_line(Some(2))
+///     .build()
+///     .unwrap();
+/// assert_eq!(lineup::check("a b\nc d\n", fmt.clone()), Ok(4));
+/// let violation = lineup::check("a b\nc d\ne\n", fmt).unwrap_err();
+/// assert_eq!((violation.line, violation.column), (3, 1));
+/// assert_eq!(
+///     violation.to_string(),
+///     "line 3, column 1: line at byte 8 has an unexpected number of items: 1"
+/// );
+/// ```
+pub fn check(input: &str, format: InFormat) -> Result<usize, Violation> {
+    let mut reader = ItemReader::new(input, format);
+    let mut items = 0;
+    loop {
+        match reader.try_next() {
+            Ok(Some(_)) => items += 1,
+            Ok(None) => return Ok(items),
+            Err(error) => return Err(Violation::new(input, error)),
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+/// Read all items of `input` as per input format, unquoted, as [ItemReader] would, splitting
+/// input on item separators into chunks read in parallel by `jobs` threads, as many as CPUs if
+/// 0; input is read as a whole unless items are split by an explicit separator, with no quoting,
+/// escaping, lines, header, comments, skipped items or expected counts
+///
+/// # Examples
+///
+/// ```
+/// let fmt = lineup::InFormatBuilder::default().build().unwrap();
+/// let items = lineup::read_parallel("a,b,c,d,e,,f", &fmt, 3).unwrap();
+/// assert_eq!(items, ["a", "b", "c", "d", "e"]);
+/// ```
+///
+/// [ItemReader]: crate::ItemReader
+pub fn read_parallel<'i>(
+    input: &'i str,
+    format: &InFormat,
+    jobs: usize,
+) -> Result<Vec<Cow<'i, str>>, LineupError> {
+    use rayon::prelude::*;
+
+    let Some(separator) = format.chunk_separator() else {
+        let mut reader = ItemReader::new(input, format.clone());
+        return std::iter::from_fn(|| reader.try_next().transpose())
+            .map(|item| Ok(format.unquote(item?)))
+            .collect();
+    };
+    let pool = thread_pool(jobs)?;
+    // chunks end with the first separator past evenly spaced offsets
+    let chunk_length = input.len().div_ceil(pool.current_num_threads()).max(1);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    loop {
+        let mut target = start + chunk_length;
+        while !input.is_char_boundary(target.min(input.len())) {
+            target += 1;
+        }
+        let rest = input.get(target..).unwrap_or_default();
+        match memchr::memmem::find(rest.as_bytes(), separator.as_bytes()) {
+            Some(found) => {
+                let end = target + found + separator.len();
+                chunks.push((start, &input[start..end]));
+                start = end;
+            }
+            None => {
+                chunks.push((start, &input[start..]));
+                break;
+            }
+        }
+    }
+    let chunks: Vec<Result<_, ReadError>> = pool.install(|| {
+        chunks
+            .into_par_iter()
+            .enumerate()
+            .map(|(chunk, (offset, input))| {
+                let chunk_format = InFormat {
+                    strip_bom: format.strip_bom && chunk == 0,
+                    ..format.clone()
+                };
+                let mut reader = ItemReader::new("", chunk_format).with_input(input, offset);
+                let mut items = Vec::new();
+                loop {
+                    let pending = !reader.remaining().is_empty();
+                    match reader.try_next()? {
+                        Some(item) => items.push(item),
+                        // an empty item ends items, unless skipped, before the end of the chunk
+                        None => {
+                            let ended = pending && format.empty_items == EmptyItems::Terminate;
+                            return Ok((items, ended));
+                        }
+                    }
+                }
+            })
+            .collect()
+    });
+    let mut items = Vec::new();
+    for chunk in chunks {
+        let (chunk_items, ended) = chunk?;
+        items.extend(chunk_items.into_iter().map(|item| format.unquote(item)));
+        if ended {
+            break;
+        }
+    }
+    Ok(items)
+}
+
+#[cfg(feature = "rayon")]
+/// Write all `items` to `ostream` as per output format, as [write] would, formatting chunks of
+/// whole lines in parallel by `jobs` threads, as many as CPUs if 0, then writing them in order;
+/// items are formatted as a whole unless laid out row-major on lines of a fixed number of items
+///
+/// # Examples
+///
+/// ```
+/// let format = lineup::OutFormatBuilder::default()
+///     .span(Some(lineup::ItemSpan::new(2, ' ', lineup::Anchor::Right)))
+///     .item_separator("|".to_string())
+///     .line_separator(Some(lineup::LineSeparator::new(2, "\n".to_string())))
+///     .build()
+///     .unwrap();
+/// let items = ["1", "2", "3", "4", "5"];
+/// let mut output = Vec::new();
+/// let written = lineup::write_parallel(&items, &mut output, format, 2).unwrap();
+/// assert_eq!(written.items, 5);
+/// assert_eq!(String::from_utf8(output).unwrap(), " 1| 2\n 3| 4\n 5");
+/// ```
+///
+/// [write]: crate::write
+pub fn write_parallel<Item, Out>(
+    items: &[Item],
+    mut ostream: Out,
+    format: OutFormat,
+    jobs: usize,
+) -> Result<Written, LineupError>
+where
+    Item: AsRef<str> + Sync,
+    Out: std::io::Write,
+{
+    use rayon::prelude::*;
+
+    let items_per_line = match &format.line_separator {
+        Some(line_separator) if !format.column_major() && format.max_line_width().is_none() => {
+            line_separator.items_per_line
+        }
+        _ => 0,
+    };
+    if items_per_line == 0 || items.is_empty() {
+        return write(items, ostream, format);
+    }
+    let pool = thread_pool(jobs)?;
+    let lines = items.len().div_ceil(items_per_line);
+    let chunk_items = lines.div_ceil(pool.current_num_threads()) * items_per_line;
+    let last = (items.len() - 1) / chunk_items;
+    let chunks: Vec<(Vec<u8>, Written)> = pool.install(|| {
+        items
+            .par_chunks(chunk_items)
+            .enumerate()
+            .map(|(chunk, items)| {
+                let mut writer = ItemWriter::new(format.clone());
+                if chunk > 0 {
+                    writer.resume(chunk * chunk_items);
+                }
+                let mut output = Vec::new();
+                let mut written = Written::default();
+                for item in items {
+                    written += writer.write(item.as_ref(), &mut output)?;
+                }
+                if chunk == last {
+                    written += writer.finish(&mut output)?;
+                }
+                Ok((output, written))
+            })
+            .collect::<Result<_, LineupError>>()
+    })?;
+    let mut written = Written::default();
+    for (output, chunk_written) in chunks {
+        ostream.write_all(&output)?;
+        written += chunk_written;
+    }
+    ostream.flush()?;
+    Ok(written)
+}
+
+#[cfg(feature = "rayon")]
+/// Pool of `jobs` threads, as many as CPUs if 0
+fn thread_pool(jobs: usize) -> Result<rayon::ThreadPool, LineupError> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .map_err(|error| LineupError::Io(std::io::Error::other(error)))
+}
+
+/// Opaque type definition around [ItemReader], as returned from [read]
+///
+/// [ItemReader]: crate::ItemReader
+/// [read]: crate::read
+pub type ItemIterator<'i> = impl Iterator<Item = &'i str> + std::fmt::Debug;
+
+/// Get an iterator over &str items, ending early if input cannot be split as per format (see
+/// [try_read] to get the error)
+///
+/// # Examples
+///
+/// separate by byte count for each item
+/// ```
+/// let input = "aabbccdd";
+/// let fmt = lineup::InFormatBuilder::default()
+///     .item_separator(lineup::ItemSeparator::ByteCount(2))
+///     .build()
+///     .unwrap();
+/// let mut it = lineup::read(input, fmt);
+/// assert_eq!(Some("aa"), it.next());
+/// assert_eq!(Some("bb"), it.next());
+/// assert_eq!(Some("cc"), it.next());
+/// assert_eq!(Some("dd"), it.next());
+/// assert_eq!(None, it.next());
+/// ```
+///
+/// separate by explicit string
+/// ```
+/// let input = "👉👉👉SEP😊😊SEP🖖SEP💼💼💼";
+/// let fmt = lineup::InFormatBuilder::default()
+///     .item_separator(lineup::ItemSeparator::Explicit("SEP".to_string()))
+///     .build()
+///     .unwrap();
+/// let mut it = lineup::read(input, fmt);
+/// assert_eq!(Some("👉👉👉"), it.next());
+/// assert_eq!(Some("😊😊"), it.next());
+/// assert_eq!(Some("🖖"), it.next());
+/// assert_eq!(Some("💼💼💼"), it.next());
+/// assert_eq!(None, it.next());
+/// ```
+///
+#[define_opaque(ItemIterator)]
+pub fn read(input: &str, format: InFormat) -> ItemIterator<'_> {
+    ItemReader::new(input, format)
+}
+
+/// Opaque type definition around [ItemReader] yielding owned items, as returned from
+/// [read_owned]
+///
+/// [ItemReader]: crate::ItemReader
+/// [read_owned]: crate::read_owned
+pub type OwnedItemIterator<'i> = impl Iterator<Item = String> + std::fmt::Debug;
+
+/// Get an iterator over String items, which can outlive input, e.g. to be sent to other threads
+///
+/// # Examples
+///
+/// ```
+/// let items: Vec<String> = {
+///     let input = String::from("a,b,c");
+///     let fmt = lineup::InFormatBuilder::default().build().unwrap();
+///     lineup::read_owned(&input, fmt).collect()
+/// };
+/// let handle = std::thread::spawn(move || items.concat());
+/// assert_eq!("abc", handle.join().unwrap());
+/// ```
+#[define_opaque(OwnedItemIterator)]
+pub fn read_owned(input: &str, format: InFormat) -> OwnedItemIterator<'_> {
+    ItemReader::new(input, format).map(str::to_string)
+}
+
+/// Get an iterator over items of raw bytes `input`, borrowed if it is valid UTF-8, or the error
+/// input cannot be split as per format with, if any, as the last item; items holding invalid
+/// UTF-8 are an error or have it replaced, as per format (see [InvalidUtf8]), and byte offsets
+/// of errors refer to input with it replaced
+///
+/// # Examples
+///
+/// ```
+/// let mut fmt = lineup::InFormatBuilder::default().build().unwrap();
+/// let input = b"a,b\xff,c";
+/// let items: Vec<_> = lineup::read_bytes(input, fmt.clone()).collect();
+/// assert_eq!(items, [Ok("a".into()), Err(lineup::ReadError::InvalidUtf8 { offset: 3 })]);
+/// fmt.invalid_utf8 = lineup::InvalidUtf8::Replace;
+/// let items: Vec<_> = lineup::read_bytes(input, fmt).collect();
+/// assert_eq!(items, [Ok("a".into()), Ok("b\u{fffd}".into()), Ok("c".into())]);
+/// ```
+pub fn read_bytes(input: &[u8], format: InFormat) -> BytesItemReader<'_> {
+    let input = match std::str::from_utf8(input) {
+        Ok(input) => BytesInput::Valid(ItemReader::new(input, format)),
+        Err(_) => {
+            let mut decoded = String::with_capacity(input.len());
+            let mut replacements = Vec::new();
+            let mut offset = 0;
+            for chunk in input.utf8_chunks() {
+                decoded.push_str(chunk.valid());
+                offset += chunk.valid().len();
+                if !chunk.invalid().is_empty() {
+                    replacements.push((decoded.len(), offset));
+                    decoded.push(char::REPLACEMENT_CHARACTER);
+                    offset += chunk.invalid().len();
+                }
+            }
+            BytesInput::Decoded {
+                reader: Some(ItemReader::new("", format)),
+                decoded,
+                offset: 0,
+                replacements,
+            }
+        }
+    };
+    BytesItemReader {
+        input,
+        failed: false,
+    }
+}
+
+#[derive(Debug)]
+/// Reader of items from raw bytes (see [read_bytes])
+pub struct BytesItemReader<'i> {
+    input: BytesInput<'i>,
+    failed: bool,
+}
+
+#[derive(Debug)]
+enum BytesInput<'i> {
+    /// Input being valid UTF-8
+    Valid(ItemReader<'i>),
+    /// Input with invalid UTF-8 replaced, read from given byte offset on, and the byte offsets
+    /// of replacement characters within it and of what they replace within input
+    Decoded {
+        reader: Option<ItemReader<'static>>,
+        decoded: String,
+        offset: usize,
+        replacements: Vec<(usize, usize)>,
+    },
+}
+
+impl<'i> Iterator for BytesItemReader<'i> {
+    type Item = Result<Cow<'i, str>, ReadError>;
+    /// Next item as per input format, or the read error, after which there are no more items
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let item = match &mut self.input {
+            BytesInput::Valid(reader) => reader.try_next().map(|item| item.map(Cow::Borrowed)),
+            BytesInput::Decoded {
+                reader,
+                decoded,
+                offset,
+                replacements,
+            } => {
+                let state = reader.take().expect("reader state");
+                let mut state = state.with_input(&decoded[*offset..], *offset);
+                let item = state.try_next().and_then(|item| {
+                    let Some(item) = item else {
+                        return Ok(None);
+                    };
+                    let start = item.as_ptr() as usize - decoded.as_ptr() as usize;
+                    let replaced = replacements
+                        .iter()
+                        .find(|(index, _)| (start..start + item.len()).contains(index));
+                    match (replaced, state.fmt.invalid_utf8) {
+                        (Some(&(_, offset)), InvalidUtf8::Error) => {
+                            Err(ReadError::InvalidUtf8 { offset })
+                        }
+                        _ => Ok(Some(Cow::Owned(item.to_string()))),
+                    }
+                });
+                *offset = decoded.len() - state.input.len();
+                *reader = Some(state.with_input("", 0));
+                item
+            }
+        };
+        let item = item.transpose();
+        self.failed = matches!(item, Some(Err(_)));
+        item
+    }
+}
+
+/// Get an iterator over &str items, or the error input cannot be split as per format with, if
+/// any, as the last item
+///
+/// # Examples
+///
+/// ```
+/// let fmt = lineup::InFormatBuilder::default()
+///     .item_separator(lineup::ItemSeparator::ByteCount(2))
+///     .build()
+///     .unwrap();
+/// let mut it = lineup::try_read("aaaé", fmt);
+/// assert_eq!(Some(Ok("aa")), it.next());
+/// assert_eq!(
+///     Some(Err(lineup::ReadError::InvalidBoundary { offset: 4 })),
+///     it.next()
+/// );
+/// assert_eq!(None, it.next());
+/// ```
+pub fn try_read(input: &str, format: InFormat) -> FallibleItemReader<'_> {
+    ItemReader::new(input, format).fallible()
+}
+
+/// Input format guessed from a `sample` of input, e.g. its beginning: the first of tab (TSV),
+/// comma (CSV), semicolon and pipe splitting its first lines into the same number of items, the
+/// most items first, or whitespace; `None` if no separator is found
+///
+/// # Examples
+///
+/// ```
+/// let fmt = lineup::detect_format("a;b;\"c;d\"\n1;2;3\n").unwrap();
+/// assert_eq!(fmt.item_separator, lineup::ItemSeparator::Explicit(";".to_string()));
+/// let items: Vec<_> = lineup::read("a;b;\"c;d\"\n1;2;3\n", fmt.clone())
+///     .map(|item| fmt.unquote(item))
+///     .collect();
+/// assert_eq!(items, ["a", "b", "c;d", "1", "2", "3"]);
+/// ```
+pub fn detect_format(sample: &str) -> Option<InFormat> {
+    const SAMPLE_LINES: usize = 20;
+    let mut lines: Vec<&str> = sample
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(SAMPLE_LINES + 1)
+        .collect();
+    // the last line of a sample may be cut short
+    if lines.len() > SAMPLE_LINES || lines.len() > 1 && !sample.ends_with('\n') {
+        lines.pop();
+    }
+    let count = |line: &str, separator: char| {
+        let mut quoted = false;
+        line.chars()
+            .filter(|&c| {
+                quoted ^= c == '"';
+                c == separator && !quoted
+            })
+            .count()
+    };
+    // candidates in reverse order of preference, as the last of equally good ones is taken
+    let separator = ['|', ';', ',', '\t']
+        .into_iter()
+        .filter_map(|separator| {
+            let first = count(lines.first()?, separator);
+            let consistent = lines.iter().all(|line| count(line, separator) == first);
+            (first > 0 && consistent).then_some((separator, first))
+        })
+        .max_by_key(|&(_, count)| count);
+    let mut fmt = InFormatBuilder::default();
+    match separator {
+        Some(('\t', _)) => fmt.mode(InputMode::Tsv),
+        Some((',', _)) => fmt.mode(InputMode::Csv),
+        Some((separator, count)) => {
+            let line_break = if sample.contains("\r\n") {
+                "\r\n"
+            } else {
+                "\n"
+            };
+            let line_separator = LineSeparator::new(count + 1, line_break.to_string());
+            fmt.item_separator(ItemSeparator::Explicit(separator.to_string()))
+                .line_separator(Some(line_separator))
+                .quote(sample.contains('"').then_some('"'))
+        }
+        None if lines.len() > 1 || sample.split_whitespace().nth(1).is_some() => {
+            fmt.item_separator(ItemSeparator::Whitespace)
+        }
+        None => return None,
+    };
+    Some(fmt.build().expect("valid format"))
+}
+
+enum EmittingSeparator {
+    None,
+    Item,
+    Line,
+}
+
+/// User transform applied to items (see [with_transform])
+///
+/// [with_transform]: ItemWriter::with_transform
+type Transform = Box<dyn for<'a> Fn(&'a str) -> Cow<'a, str> + Send + Sync>;
+
+/// User callback overriding the format of items (see [with_formatter])
+///
+/// [with_formatter]: ItemWriter::with_formatter
+type Formatter = Box<dyn Fn(&str, Position) -> ItemOverride + Send + Sync>;
+
+#[derive(New, Clone, Copy, Debug, PartialEq, Eq)]
+/// Position of an item in output, header excluded
+pub struct Position {
+    /// Line index, starting from 0
+    pub line: usize,
+    /// Column position within the line, starting from 0
+    pub column: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+/// Format settings overriding the ones from [OutFormat] for a single item; unset ones are left
+/// as they are
+///
+/// [OutFormat]: crate::OutFormat
+pub struct ItemOverride {
+    /// Span width; items without a span get one with [ItemSpanBuilder] defaults
+    ///
+    /// [ItemSpanBuilder]: crate::ItemSpanBuilder
+    pub span: Option<usize>,
+    /// Pad character
+    pub pad: Option<char>,
+    /// Anchor
+    pub anchor: Option<Anchor>,
+    /// Style, not applied in JSON output modes
+    pub style: Option<Style>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+/// Amount of output written (see [write])
+///
+/// [write]: crate::write
+pub struct Written {
+    /// Bytes written
+    pub bytes: usize,
+    /// Items written, labels and empty items filling lines excluded
+    pub items: usize,
+}
+
+impl std::ops::Add for Written {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            bytes: self.bytes + other.bytes,
+            items: self.items + other.items,
+        }
+    }
+}
+
+impl std::ops::AddAssign for Written {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+/// Output sink counting the bytes written to it
+struct ByteCounter<'w, W> {
+    writer: &'w mut W,
+    bytes: usize,
+}
+
+impl<'w, W: std::io::Write> ByteCounter<'w, W> {
+    fn new(writer: &'w mut W) -> Self {
+        Self { writer, bytes: 0 }
+    }
+}
+
+impl<W: std::io::Write> std::io::Write for ByteCounter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+        let written = self.writer.write(buf)?;
+        self.bytes += written;
+        Ok(written)
+    }
+
+    fn write_vectored(&mut self, bufs: &[std::io::IoSlice<'_>]) -> Result<usize, std::io::Error> {
+        let written = self.writer.write_vectored(bufs)?;
+        self.bytes += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        self.writer.flush()
+    }
+}
+
+/// Output sink holding back output, when batching, until flushed, then writing it at once
+struct LineBatch<'w, W> {
+    writer: &'w mut W,
+    /// Output held back
+    line: Vec<u8>,
+    batching: bool,
+}
+
+impl<W: std::io::Write> std::io::Write for LineBatch<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+        if !self.batching {
+            return self.writer.write(buf);
+        }
+        self.line.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn write_vectored(&mut self, bufs: &[std::io::IoSlice<'_>]) -> Result<usize, std::io::Error> {
+        if !self.batching {
+            return self.writer.write_vectored(bufs);
+        }
+        for buf in bufs {
+            self.line.extend_from_slice(buf);
+        }
+        Ok(bufs.iter().map(|buf| buf.len()).sum())
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        if !self.line.is_empty() {
+            self.writer.write_all(&self.line)?;
+            self.line.clear();
+        }
+        self.writer.flush()
+    }
+}
+
+/// Pieces of output gathered to be written with as few vectored writes as possible
+struct Gather<'a> {
+    slices: [std::io::IoSlice<'a>; Gather::CAPACITY],
+    len: usize,
+}
+
+impl<'a> Gather<'a> {
+    const CAPACITY: usize = 16;
+
+    fn new() -> Self {
+        Self {
+            slices: [std::io::IoSlice::new(&[]); Gather::CAPACITY],
+            len: 0,
+        }
+    }
+
+    /// Add a piece of output, writing gathered ones first if there is no room left for it
+    fn push<Out: std::io::Write>(
+        &mut self,
+        piece: &'a [u8],
+        writer: &mut Out,
+    ) -> Result<(), std::io::Error> {
+        if piece.is_empty() {
+            return Ok(());
+        }
+        if self.len == Self::CAPACITY {
+            self.flush(writer)?;
+        }
+        self.slices[self.len] = std::io::IoSlice::new(piece);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Write all gathered pieces, as many writes as needed
+    fn flush<Out: std::io::Write>(&mut self, writer: &mut Out) -> Result<(), std::io::Error> {
+        let mut slices = &mut self.slices[..self.len];
+        self.len = 0;
+        while !slices.is_empty() {
+            match writer.write_vectored(slices) {
+                Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+                Ok(written) => std::io::IoSlice::advance_slices(&mut slices, written),
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Block of pad characters on the stack, to gather pads from rather than from a string
+struct Pads {
+    block: [u8; Pads::BLOCK * 4],
+    /// Length of the encoded pad character
+    pad_len: usize,
+}
+
+impl Pads {
+    /// Number of pad characters in a block
+    const BLOCK: usize = 32;
+
+    /// Block of up to `count` `pad` characters
+    fn new(pad: char, count: usize) -> Self {
+        let mut encoded = [0; 4];
+        let pad = pad.encode_utf8(&mut encoded).as_bytes();
+        let mut block = [0; Pads::BLOCK * 4];
+        for chunk in block[..Pads::BLOCK.min(count) * pad.len()].chunks_exact_mut(pad.len()) {
+            chunk.copy_from_slice(pad);
+        }
+        Self {
+            block,
+            pad_len: pad.len(),
+        }
+    }
+
+    /// Gather `count` pad characters
+    fn gather<'a, Out: std::io::Write>(
+        &'a self,
+        count: usize,
+        gather: &mut Gather<'a>,
+        writer: &mut Out,
+    ) -> Result<(), std::io::Error> {
+        let mut count = count;
+        while count > 0 {
+            let pads = count.min(Pads::BLOCK);
+            gather.push(&self.block[..pads * self.pad_len], writer)?;
+            count -= pads;
+        }
+        Ok(())
+    }
+}
+
+/// Pending separator before an item, as the pieces of output it is made of
+struct Separator<'f> {
+    pieces: [Cow<'f, str>; 4],
+    /// Line width following the separator
+    width: usize,
+}
+
+impl<'f> Separator<'f> {
+    /// No separator, within a line of given width
+    fn none(width: usize) -> Self {
+        Self::new([""; 4].map(Cow::Borrowed), width)
+    }
+
+    /// Separator within a line, followed by given line width
+    fn item(item_break: Cow<'f, str>, width: usize) -> Self {
+        Self::new([item_break, "".into(), "".into(), "".into()], width)
+    }
+
+    fn new(pieces: [Cow<'f, str>; 4], width: usize) -> Self {
+        Self { pieces, width }
+    }
+
+    /// Gather all pieces
+    fn gather<'a, Out: std::io::Write>(
+        &'a self,
+        gather: &mut Gather<'a>,
+        writer: &mut Out,
+    ) -> Result<(), std::io::Error> {
+        for piece in &self.pieces {
+            gather.push(piece.as_bytes(), writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Format of an item at its position, resolved from [OutFormat] and [ItemOverride]
+#[derive(Clone, Copy)]
+struct Cell<'f> {
+    span: Option<CellSpan<'f>>,
+    style: Option<Style>,
+}
+
+/// Span of an item at its position, with anchor and pad resolved
+#[derive(Clone, Copy)]
+struct CellSpan<'f> {
+    span: usize,
+    anchor: Anchor,
+    pad: char,
+    overflow: OverflowPolicy,
+    ellipsis: &'f str,
+}
+
+/// Write input items as per provided format (see [write])
+///
+/// [write]: ItemWriter::write
+#[derive(New)]
+pub struct ItemWriter {
+    #[new(value = "EmittingSeparator::None")]
+    separator: EmittingSeparator,
+    fmt: OutFormat,
+    #[new(value = "0")]
+    items_in_line: usize,
+    #[new(value = "0")]
+    line_width: usize,
+    #[new(value = "false")]
+    started: bool,
+    #[new(default)]
+    buffer: Vec<String>,
+    #[new(value = "0")]
+    line: usize,
+    /// Index of the next item to be emitted
+    #[new(value = "0")]
+    index: usize,
+    #[new(default)]
+    row: Vec<(String, ItemOverride)>,
+    #[new(default)]
+    transform: Option<Transform>,
+    #[new(default)]
+    formatter: Option<Formatter>,
+    /// Write output in batches of complete lines (see [with_line_batches])
+    ///
+    /// [with_line_batches]: ItemWriter::with_line_batches
+    #[new(value = "false")]
+    line_batches: bool,
+    /// Output of the current line, when writing output in batches of lines
+    #[new(default)]
+    batch: Vec<u8>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Error reading items from input
+pub enum ReadError {
+    /// An item boundary, at given byte offset of input, is not a UTF-8 code point boundary
+    InvalidBoundary { offset: usize },
+    /// A trailing chunk, at given byte offset of input, is shorter than fixed size items
+    PartialChunk { offset: usize },
+    /// Input, at given byte offset, is not a JSON value to be read as an item
+    InvalidJson { offset: usize },
+    /// The item at given byte offset of input is longer, `length` chars, than the maximum one
+    ItemTooLong { offset: usize, length: usize },
+    /// Input has not the expected number of items: it has `found` ones, if fewer, or at least
+    /// `found` ones, if more
+    ItemCount { expected: usize, found: usize },
+    /// The line at given byte offset of input has `found` items instead of the expected ones
+    LineItemCount { offset: usize, found: usize },
+    /// An item holds invalid UTF-8 at given byte offset of input (see [read_bytes])
+    InvalidUtf8 { offset: usize },
+}
+
+impl Display for ReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadError::InvalidBoundary { offset } => {
+                write!(
+                    f,
+                    "item boundary at byte {offset} is within a UTF-8 code point"
+                )
+            }
+            ReadError::PartialChunk { offset } => {
+                write!(f, "trailing chunk at byte {offset} is shorter than an item")
+            }
+            ReadError::InvalidJson { offset } => {
+                write!(f, "invalid JSON value at byte {offset}")
+            }
+            ReadError::ItemTooLong { offset, length } => {
+                write!(f, "item at byte {offset} is too long: {length} characters")
+            }
+            ReadError::ItemCount { expected, found } if found > expected => {
+                write!(f, "expected {expected} items, found more")
+            }
+            ReadError::ItemCount { expected, found } => {
+                write!(f, "expected {expected} items, found {found}")
+            }
+            ReadError::LineItemCount { offset, found } => {
+                write!(
+                    f,
+                    "line at byte {offset} has an unexpected number of items: {found}"
+                )
+            }
+            ReadError::InvalidUtf8 { offset } => write!(f, "invalid UTF-8 at byte {offset}"),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {}
+
+impl ReadError {
+    /// Byte offset of input the error is at, if any: a missing or extra item is at none
+    pub fn offset(&self) -> Option<usize> {
+        match *self {
+            ReadError::InvalidBoundary { offset }
+            | ReadError::PartialChunk { offset }
+            | ReadError::InvalidJson { offset }
+            | ReadError::ItemTooLong { offset, .. }
+            | ReadError::LineItemCount { offset, .. }
+            | ReadError::InvalidUtf8 { offset } => Some(offset),
+            ReadError::ItemCount { .. } => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Input not conforming to its input format, as found by [check]
+///
+/// [check]: crate::check
+pub struct Violation {
+    /// How input does not conform to its format
+    pub error: ReadError,
+    /// Line of input the violation is at, from 1; the last one if the error is at no offset
+    pub line: usize,
+    /// Column, in chars, of the line the violation is at, from 1
+    pub column: usize,
+}
+
+impl Violation {
+    /// Violation of `input` due to `error`, located at its offset or else at the end of input
+    pub fn new(input: &str, error: ReadError) -> Self {
+        let before = &input.as_bytes()[..error.offset().unwrap_or(input.len()).min(input.len())];
+        let line_start = before
+            .iter()
+            .rposition(|&byte| byte == b'\n')
+            .map_or(0, |newline| newline + 1);
+        Self {
+            error,
+            line: before.iter().filter(|&&byte| byte == b'\n').count() + 1,
+            // chars are counted by their leading bytes, the offset possibly being within one
+            column: before[line_start..]
+                .iter()
+                .filter(|&&byte| (byte as i8) >= -0x40)
+                .count()
+                + 1,
+        }
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.error
+        )
+    }
+}
+
+impl std::error::Error for Violation {}
+
+#[derive(Debug, thiserror::Error)]
+/// Error reading, writing or building formats
+///
+/// # Examples
+///
+/// ```
+/// let error = lineup::ItemSpanBuilder::default().build().unwrap_err();
+/// assert!(matches!(error, lineup::LineupError::Build(_)));
+///
+/// let span = lineup::ItemSpanBuilder::default()
+///     .span(2)
+///     .overflow(lineup::OverflowPolicy::Error)
+///     .build()
+///     .unwrap();
+/// let format = lineup::OutFormatBuilder::default()
+///     .span(Some(span))
+///     .build()
+///     .unwrap();
+/// let error = lineup::write(["abc"].into_iter(), Vec::new(), format).unwrap_err();
+/// assert_eq!(error.to_string(), "item 'abc' is 3 wide, exceeding span of 2");
+///
+/// let error = lineup::InFormatBuilder::default()
+///     .item_separator(lineup::ItemSeparator::Explicit(String::new()))
+///     .build()
+///     .unwrap_err();
+/// assert!(matches!(error, lineup::LineupError::Validation(_)));
+/// ```
+pub enum LineupError {
+    /// Reading from or writing to a stream failed
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// Input cannot be split into items as per input format
+    #[error(transparent)]
+    Read(#[from] ReadError),
+    /// An item is wider than its span, with [OverflowPolicy::Error]
+    ///
+    /// [OverflowPolicy::Error]: crate::OverflowPolicy::Error
+    #[error("item '{item}' is {width} wide, exceeding span of {span}")]
+    Overflow {
+        item: String,
+        width: usize,
+        span: usize,
+    },
+    /// A format cannot be built from its builder
+    #[error("cannot build format: {0}")]
+    Build(String),
+    /// A format built from its builder would be meaningless, e.g. items of 0 size
+    #[error("invalid format: {0}")]
+    Validation(String),
+    /// A format spec cannot be parsed (see [InFormat::from_str])
+    ///
+    /// [InFormat::from_str]: crate::InFormat#method.from_str
+    #[error("invalid format spec: {0}")]
+    Spec(String),
+    /// A template cannot be parsed (see [Template])
+    ///
+    /// [Template]: crate::Template
+    #[error("invalid template: {0}")]
+    Template(String),
+    /// A pipeline stage cannot be parsed (see [Stage])
+    ///
+    /// [Stage]: crate::Stage
+    #[error("invalid pipeline stage: {0}")]
+    Stage(String),
+}
+
+impl From<derive_builder::UninitializedFieldError> for LineupError {
+    fn from(error: derive_builder::UninitializedFieldError) -> Self {
+        LineupError::Build(error.to_string())
+    }
+}
+
+/// I/O error as is, other errors wrapped as [std::io::ErrorKind::Other]
+impl From<LineupError> for std::io::Error {
+    fn from(error: LineupError) -> Self {
+        match error {
+            LineupError::Io(error) => error,
+            error => std::io::Error::other(error),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ItemReader<'i> {
+    input: &'i str,
+    /// Format, shared so as to borrow its separators while reading
+    fmt: Arc<InFormat>,
+    items_in_current_line: usize,
+    /// Whether skipped lines and header have been read already
+    started: bool,
+    header: Vec<String>,
+    /// Whether input starts at the beginning of a line
+    line_start: bool,
+    /// Whether input consumed with the last item read goes on past it, i.e. a separator was
+    /// matched after it rather than the end of input
+    separated: bool,
+    /// Number of items read, discarded ones included
+    items: usize,
+    /// Bytes of input left in the current fixed length record, if any
+    record_rest: usize,
+    /// Number of items read, skipped empty ones excluded
+    read_items: usize,
+    /// Number of items read on the current line, and byte offset it starts at
+    line_items: usize,
+    line_offset: usize,
+    /// Number of lines with items ended so far
+    lines: usize,
+    input_len: usize,
+    #[cfg(feature = "regex")]
+    regex: Option<regex::Regex>,
+    /// Searchers of the explicit item and line separators last split on
+    finders: Vec<memchr::memmem::Finder<'static>>,
+    /// Error iteration ended with, if any, returned by any later read
+    error: Option<ReadError>,
+}
+
+impl<'i> ItemReader<'i> {
+    pub fn new(input: &'i str, fmt: InFormat) -> Self {
+        Self {
+            input,
+            fmt: Arc::new(fmt),
+            items_in_current_line: 0,
+            started: false,
+            header: Vec::new(),
+            line_start: true,
+            separated: false,
+            items: 0,
+            record_rest: 0,
+            read_items: 0,
+            line_items: 0,
+            line_offset: 0,
+            lines: 0,
+            input_len: input.len(),
+            #[cfg(feature = "regex")]
+            regex: None,
+            finders: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Next item as per `separator`, or an error if input cannot be split as per it
+    pub fn try_next_item(
+        &mut self,
+        separator: &ItemSeparator,
+    ) -> Result<Option<&'i str>, ReadError> {
+        if let ItemSeparator::ByteCount(count) = *separator {
+            if self.fmt.byte_rounding == ByteRounding::Error
+                && self.input.len() >= count
+                && !self.input.is_char_boundary(count)
+            {
+                let offset = self.input_len - self.input.len() + count;
+                return Err(ReadError::InvalidBoundary { offset });
+            }
+        }
+        if self.fmt.partial_chunk == PartialChunk::Error && !self.input.is_empty() {
+            let short = |units: usize, count: usize| count > 0 && units < count;
+            let partial = match *separator {
+                ItemSeparator::ByteCount(count) => short(self.input.len(), count),
+                ItemSeparator::CharCount(count) => {
+                    short(self.input.chars().take(count).count(), count)
+                }
+                #[cfg(feature = "unicode-segmentation")]
+                ItemSeparator::GraphemeCount(count) => {
+                    let graphemes =
+                        unicode_segmentation::UnicodeSegmentation::graphemes(self.input, true);
+                    short(graphemes.take(count).count(), count)
+                }
+                _ => false,
+            };
+            if partial {
+                let offset = self.input_len - self.input.len();
+                return Err(ReadError::PartialChunk { offset });
+            }
+        }
+        Ok(self.next_item(separator))
+    }
+
+    /// Next item as per input format, `None` at the end of input, or an error if input cannot
+    /// be split as per format
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let fmt = lineup::InFormatBuilder::default()
+    ///     .item_separator(lineup::ItemSeparator::ByteCount(2))
+    ///     .build()
+    ///     .unwrap();
+    /// let mut reader = lineup::ItemReader::new("aaaé", fmt);
+    /// assert_eq!(Ok(Some("aa")), reader.try_next());
+    /// assert_eq!(
+    ///     Err(lineup::ReadError::InvalidBoundary { offset: 4 }),
+    ///     reader.try_next()
+    /// );
+    /// ```
+    pub fn try_next(&mut self) -> Result<Option<&'i str>, ReadError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        self.start()?;
+        while self.items < self.fmt.skip_items {
+            if self.read()?.is_none() {
+                return Ok(None);
+            }
+            self.items += 1;
+        }
+        if self.exhausted() {
+            return Ok(None);
+        }
+        let item = self.read()?;
+        if item.is_some() {
+            self.items += 1;
+        }
+        Ok(item)
+    }
+
+    /// Whether as many items as to be read, as per format, have been read
+    fn exhausted(&self) -> bool {
+        self.fmt
+            .max_items
+            .is_some_and(|max| self.items >= self.fmt.skip_items.saturating_add(max))
+    }
+
+    /// Next item as per input format, whether to be discarded or not
+    fn read(&mut self) -> Result<Option<&'i str>, ReadError> {
+        loop {
+            self.skip_comments();
+            if self.line_items == 0 {
+                self.line_offset = self.input_len - self.input.len();
+            }
+            let before = self.input;
+            let fmt = Arc::clone(&self.fmt);
+            let item = match fmt.mode {
+                InputMode::Plain => {
+                    let line_separator = fmt.line_separator.as_ref();
+                    match line_separator.and_then(|line_separator| line_separator.record_length) {
+                        Some(record_length) => self.next_in_record(record_length)?,
+                        None => {
+                            let line_break = self.line_break();
+                            let line_separator = line_separator
+                                .filter(|_| line_break)
+                                .map(|line_separator| line_separator.line_separator.as_str());
+                            self.try_next_separated(&fmt.item_separator, line_separator)?
+                        }
+                    }
+                }
+                InputMode::Csv => self.next_field(','),
+                InputMode::Tsv => self.next_field('\t'),
+                InputMode::Json => self.next_json_value(false)?,
+                InputMode::JsonLines => self.next_json_value(true)?,
+            };
+            let consumed = &before[..before.len() - self.input.len()];
+            self.line_start = consumed.ends_with('\n');
+            self.separated = item.is_some_and(|item| {
+                item.as_bytes().as_ptr_range().end < consumed.as_bytes().as_ptr_range().end
+            });
+            let item = if self.fmt.trim {
+                item.map(str::trim)
+            } else {
+                item
+            };
+            let skipped = item == Some("") && self.fmt.empty_items == EmptyItems::Skip;
+            if item.is_some() && !skipped {
+                self.count_item()?;
+            }
+            if self.line_start {
+                self.end_line()?;
+            }
+            match item {
+                Some("") if skipped => {}
+                Some(item) => {
+                    self.check_length(item, before)?;
+                    return Ok(Some(item));
+                }
+                None if self.fmt.empty_items != EmptyItems::Skip => return self.end_input(),
+                _ if self.input.is_empty() => return self.end_input(),
+                _ => {}
+            }
+        }
+    }
+
+    /// Count an item read, checking input has no more items than expected
+    fn count_item(&mut self) -> Result<(), ReadError> {
+        self.read_items += 1;
+        self.line_items += 1;
+        match self.fmt.expected_items {
+            Some(expected) if self.read_items > expected => Err(ReadError::ItemCount {
+                expected,
+                found: self.read_items,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// End the current line, checking it has the expected number of items, if it has any
+    fn end_line(&mut self) -> Result<(), ReadError> {
+        let found = std::mem::take(&mut self.line_items);
+        if found > 0 {
+            self.lines += 1;
+        }
+        match self.fmt.expected_items_per_line {
+            Some(expected) if found > 0 && found != expected => Err(ReadError::LineItemCount {
+                offset: self.line_offset,
+                found,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// End of input, checking the last line and input have the expected number of items
+    fn end_input(&mut self) -> Result<Option<&'i str>, ReadError> {
+        self.end_line()?;
+        match self.fmt.expected_items {
+            Some(expected) if self.read_items < expected => Err(ReadError::ItemCount {
+                expected,
+                found: self.read_items,
+            }),
+            _ => Ok(None),
+        }
+    }
+
+    /// Check `item`, read from `input`, is not longer than the maximum item length, if any
+    fn check_length(&self, item: &str, input: &str) -> Result<(), ReadError> {
+        let Some(max_length) = self.fmt.max_item_length else {
+            return Ok(());
+        };
+        let length = item.chars().count();
+        if length > max_length {
+            let offset =
+                self.input_len - input.len() + (item.as_ptr() as usize) - (input.as_ptr() as usize);
+            return Err(ReadError::ItemTooLong { offset, length });
+        }
+        Ok(())
+    }
+
+    /// Header labels, unquoted, read from the first input line after skipped ones if format has
+    /// a header, or none
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let fmt = lineup::InFormatBuilder::default()
+    ///     .skip_lines(1)
+    ///     .header(true)
+    ///     .build()
+    ///     .unwrap();
+    /// let mut reader = lineup::ItemReader::new("# report\nname,size\na,1", fmt);
+    /// assert_eq!(Ok(&["name".to_string(), "size".to_string()][..]), reader.header());
+    /// assert_eq!(vec!["a", "1"], reader.collect::<Vec<_>>());
+    /// ```
+    pub fn header(&mut self) -> Result<&[String], ReadError> {
+        self.start()?;
+        Ok(&self.header)
+    }
+
+    /// Number of input lines items have been read from so far, discarded items included; lines
+    /// end with a new line (not in JSON modes)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let fmt = lineup::InFormatBuilder::default()
+    ///     .item_separator(lineup::ItemSeparator::Whitespace)
+    ///     .build()
+    ///     .unwrap();
+    /// let mut reader = lineup::ItemReader::new("a b\n\nc\nd", fmt);
+    /// assert_eq!(reader.lines(), 0);
+    /// assert_eq!(reader.by_ref().count(), 4);
+    /// assert_eq!(reader.lines(), 3);
+    /// ```
+    pub fn lines(&self) -> usize {
+        self.lines + usize::from(self.line_items > 0)
+    }
+
+    /// Input not consumed yet, e.g. to be handed to another parser once done with items
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let fmt = lineup::InFormatBuilder::default()
+    ///     .item_separator(lineup::ItemSeparator::Explicit(" ".to_string()))
+    ///     .build()
+    ///     .unwrap();
+    /// let mut reader = lineup::ItemReader::new("2 a b {\"c\": 1}", fmt);
+    /// let count: usize = reader.next().unwrap().parse().unwrap();
+    /// assert_eq!(vec!["a", "b"], reader.by_ref().take(count).collect::<Vec<_>>());
+    /// assert_eq!("{\"c\": 1}", reader.remaining());
+    /// ```
+    pub fn remaining(&self) -> &'i str {
+        self.input
+    }
+
+    /// Next item, as [try_next] would read it, without consuming it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let fmt = lineup::InFormatBuilder::default().build().unwrap();
+    /// let mut reader = lineup::ItemReader::new("name,a,b", fmt);
+    /// if reader.peek() == Ok(Some("name")) {
+    ///     reader.next();
+    /// }
+    /// assert_eq!(vec!["a", "b"], reader.collect::<Vec<_>>());
+    /// ```
+    ///
+    /// [try_next]: ItemReader::try_next
+    pub fn peek(&self) -> Result<Option<&'i str>, ReadError> {
+        self.clone().try_next()
+    }
+
+    /// Next `n` items, or fewer at the end of input, without consuming them (see [peek])
+    ///
+    /// [peek]: ItemReader::peek
+    pub fn peek_n(&self, n: usize) -> Result<Vec<&'i str>, ReadError> {
+        self.clone().fallible().take(n).collect()
+    }
+
+    /// Skip lines and read header, as per format, if not done yet
+    fn start(&mut self) -> Result<(), ReadError> {
+        if self.started {
+            return Ok(());
+        }
+        self.started = true;
+        if self.fmt.strip_bom {
+            self.input = self.input.strip_prefix('\u{feff}').unwrap_or(self.input);
+        }
+        for _ in 0..self.fmt.skip_lines {
+            self.next_line();
+        }
+        self.skip_comments();
+        if self.fmt.header {
+            let offset = self.input_len - self.input.len();
+            let line = self.next_line();
+            let line = line.strip_suffix('\n').unwrap_or(line);
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            let fmt = InFormat {
+                skip_lines: 0,
+                header: false,
+                comment: None,
+                skip_items: 0,
+                max_items: None,
+                expected_items: None,
+                expected_items_per_line: None,
+                ..InFormat::clone(&self.fmt)
+            };
+            let mut reader = ItemReader::new(line, fmt);
+            reader.input_len = offset + line.len();
+            while let Some(label) = reader.try_next()? {
+                self.header.push(reader.fmt.unquote(label).into_owned());
+            }
+        }
+        Ok(())
+    }
+
+    /// Skip comment lines, as per format, if input starts at the beginning of a line
+    fn skip_comments(&mut self) {
+        while self.line_start
+            && self.fmt.comment.as_deref().is_some_and(|prefix| {
+                !prefix.is_empty() && !self.input.is_empty() && self.input.starts_with(prefix)
+            })
+        {
+            self.next_line();
+        }
+    }
+
+    /// Next input line, its new line included if any
+    fn next_line(&mut self) -> &'i str {
+        let end = find_newline(self.input).map_or(self.input.len(), |i| i + 1);
+        let (line, remainder) = self.input.split_at(end);
+        self.input = remainder;
+        line
+    }
+
+    /// Whether the line separator, rather than the item separator, follows the next item, as
+    /// per its position within its line
+    fn line_break(&mut self) -> bool {
+        if let ItemSeparator::FieldWidths(_) = self.fmt.item_separator {
+            false
+        } else if let Some(line_separator) = &self.fmt.line_separator {
+            if self.items_in_current_line == line_separator.items_per_line - 1 {
+                self.items_in_current_line = 0;
+                true
+            } else {
+                self.items_in_current_line += 1;
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    /// Next item as per `line_separator`, if the line ends with it, or `item_separator`
+    fn try_next_separated(
+        &mut self,
+        item_separator: &ItemSeparator,
+        line_separator: Option<&str>,
+    ) -> Result<Option<&'i str>, ReadError> {
+        match line_separator {
+            Some(line_separator) => Ok(self.next_explicit(line_separator)),
+            None => self.try_next_item(item_separator),
+        }
+    }
+
+    /// Next JSON string, number, boolean or null within (nested) arrays, or also objects, whose
+    /// keys are skipped, if `objects`
+    fn next_json_value(&mut self, objects: bool) -> Result<Option<&'i str>, ReadError> {
+        let delimiter = |c: char| {
+            c.is_whitespace() || matches!(c, '[' | ']' | ',') || objects && matches!(c, '{' | '}')
+        };
+        loop {
+            let input = self.input.trim_start_matches(delimiter);
+            self.input = input;
+            if input.is_empty() {
+                return Ok(None);
+            }
+            let offset = self.input_len - input.len();
+            let len = if input.starts_with('"') {
+                let mut escaped = false;
+                let mut chars = input.char_indices().skip(1);
+                let closing = chars.find(|&(_, c)| {
+                    let closing = c == '"' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    closing
+                });
+                closing.ok_or(ReadError::InvalidJson { offset })?.0 + 1
+            } else {
+                input
+                    .find(|c| delimiter(c) || c == ':')
+                    .unwrap_or(input.len())
+            };
+            let (item, remainder) = input.split_at(len);
+            if !item.starts_with('"')
+                && !is_number(item)
+                && !matches!(item, "true" | "false" | "null")
+            {
+                return Err(ReadError::InvalidJson { offset });
+            }
+            match remainder.trim_start().strip_prefix(':') {
+                Some(value) if objects && item.starts_with('"') => self.input = value,
+                Some(_) => return Err(ReadError::InvalidJson { offset }),
+                None => {
+                    self.input = remainder;
+                    return Ok(Some(item));
+                }
+            }
+        }
+    }
+
+    /// Next field of a record, followed by `separator` or a line break
+    fn next_field(&mut self, separator: char) -> Option<&'i str> {
+        if self.input.is_empty() {
+            return None;
+        }
+        let input = self.input;
+        let found = self.fmt.find_separator(input, |from| {
+            let start = from
+                + if separator.is_ascii() {
+                    memchr::memchr2(separator as u8, b'\n', &input.as_bytes()[from..])
+                } else {
+                    input[from..].find([separator, '\n'])
+                }?;
+            if input[start..].starts_with('\n') && input[..start].ends_with('\r') {
+                Some((start - 1, start + 1))
+            } else {
+                Some((start, start + 1))
+            }
+        });
+        self.split(found)
+    }
+
+    pub fn next_item(&mut self, separator: &ItemSeparator) -> Option<&'i str> {
+        if self.input.is_empty() {
+            None
+        } else {
+            match separator {
+                ItemSeparator::Explicit(separator) => self.next_explicit(separator),
+                #[cfg(feature = "regex")]
+                ItemSeparator::Regex(pattern) => {
+                    if self
+                        .regex
+                        .as_ref()
+                        .is_none_or(|regex| regex.as_str() != pattern)
+                    {
+                        // invalid patterns fail building formats, any set otherwise splits nothing
+                        self.regex = regex::Regex::new(pattern).ok();
+                    }
+                    let (input, regex) = (self.input, self.regex.as_ref());
+                    let found = self.fmt.find_separator(input, |from| {
+                        let found = regex?.find_at(input, from)?;
+                        Some((found.start(), found.end()))
+                    });
+                    self.split(found)
+                }
+                ItemSeparator::ByteCount(count) => {
+                    if self.input.len() >= *count {
+                        let boundary = |index: &usize| self.input.is_char_boundary(*index);
+                        let forward = || (*count..).find(boundary).unwrap_or(*count);
+                        let count = match self.fmt.byte_rounding {
+                            ByteRounding::Forward => forward(),
+                            ByteRounding::Backward => {
+                                (1..=*count).rev().find(boundary).unwrap_or_else(forward)
+                            }
+                            ByteRounding::Error => *count,
+                        };
+                        let split = self.input.split_at(count);
+                        self.input = split.1;
+                        Some(split.0)
+                    } else {
+                        self.partial_chunk()
+                    }
+                }
+                ItemSeparator::Whitespace => {
+                    self.input = self.input.trim_start();
+                    if self.input.is_empty() {
+                        return None;
+                    }
+                    let input = self.input;
+                    let found = self.fmt.find_separator(input, |from| {
+                        let start = from + input[from..].find(char::is_whitespace)?;
+                        Some((start, input.len() - input[start..].trim_start().len()))
+                    });
+                    self.split(found)
+                }
+                ItemSeparator::CharCount(count) => {
+                    let boundaries = self.input.char_indices().map(|(index, _)| index);
+                    self.split_count(boundaries, *count)
+                }
+                #[cfg(feature = "unicode-segmentation")]
+                ItemSeparator::GraphemeCount(count) => {
+                    let boundaries = unicode_segmentation::UnicodeSegmentation::grapheme_indices(
+                        self.input, true,
+                    )
+                    .map(|(index, _)| index);
+                    self.split_count(boundaries, *count)
+                }
+                ItemSeparator::FieldWidths(widths) => {
+                    let next_line = find_newline(self.input).map_or(self.input.len(), |i| i + 1);
+                    let line = self.input[..next_line].trim_end_matches(['\r', '\n']);
+                    let width = widths.get(self.items_in_current_line).copied();
+                    let end = line
+                        .char_indices()
+                        .nth(width.unwrap_or_default())
+                        .map_or(line.len(), |(index, _)| index);
+                    let field = &self.input[..end];
+                    self.items_in_current_line += 1;
+                    if self.items_in_current_line >= widths.len() {
+                        self.items_in_current_line = 0;
+                        self.input = &self.input[next_line..];
+                    } else {
+                        self.input = &self.input[end..];
+                    }
+                    Some(field)
+                }
+            }
+        }
+    }
+}
+
+impl<'i> ItemReader<'i> {
+    /// Item before the next occurrence of explicit `separator`, if any, or the last item
+    fn next_explicit(&mut self, separator: &str) -> Option<&'i str> {
+        if self.input.is_empty() {
+            return None;
+        }
+        let finder = self.finder(separator);
+        let (input, finder) = (self.input, &self.finders[finder]);
+        let found = self.fmt.find_separator(input, |from| {
+            let start = from + finder.find(&input.as_bytes()[from..])?;
+            Some((start, start + separator.len()))
+        });
+        self.split(found)
+    }
+
+    /// Item before a separator found at `separator` byte range of input, if any, or the last
+    /// item otherwise
+    fn split(&mut self, separator: Option<(usize, usize)>) -> Option<&'i str> {
+        match separator {
+            None => {
+                let last = self.input;
+                self.input = "";
+                Some(last)
+            }
+            Some((start, end)) => {
+                let item = &self.input[..start];
+                self.input = &self.input[end..];
+                if item.is_empty()
+                    && self.fmt.empty_items == EmptyItems::Terminate
+                    && self.fmt.mode == InputMode::Plain
+                {
+                    None
+                } else {
+                    Some(item)
+                }
+            }
+        }
+    }
+
+    /// Item made of the first `count` units of input, given the byte offsets units start at,
+    /// if input has that many
+    fn split_count(
+        &mut self,
+        boundaries: impl Iterator<Item = usize>,
+        count: usize,
+    ) -> Option<&'i str> {
+        let mut boundaries = boundaries.chain(std::iter::once(self.input.len()));
+        match boundaries.nth(count) {
+            Some(index) => {
+                let split = self.input.split_at(index);
+                self.input = split.1;
+                Some(split.0)
+            }
+            None => self.partial_chunk(),
+        }
+    }
+
+    /// Next item within the current fixed length record, starting a new one if the current one
+    /// is over
+    fn next_in_record(
+        &mut self,
+        record_length: RecordLength,
+    ) -> Result<Option<&'i str>, ReadError> {
+        if self.record_rest == 0 {
+            if self.input.is_empty() {
+                return Ok(None);
+            }
+            self.record_rest = match record_length {
+                RecordLength::Bytes(length) if self.input.len() <= length => self.input.len(),
+                RecordLength::Bytes(length) if !self.input.is_char_boundary(length) => {
+                    let offset = self.input_len - self.input.len() + length;
+                    return Err(ReadError::InvalidBoundary { offset });
+                }
+                RecordLength::Bytes(length) => length,
+                RecordLength::Chars(length) => self
+                    .input
+                    .char_indices()
+                    .nth(length)
+                    .map_or(self.input.len(), |(index, _)| index),
+            };
+        }
+        let input = self.input;
+        let (record, rest) = input.split_at(self.record_rest);
+        self.input = record;
+        self.input_len -= rest.len();
+        let fmt = Arc::clone(&self.fmt);
+        let item = self.try_next_item(&fmt.item_separator);
+        self.input_len += rest.len();
+        let consumed = record.len() - self.input.len();
+        self.input = &input[consumed..];
+        self.record_rest -= consumed;
+        if self.record_rest == 0 {
+            let line_separator = self.fmt.line_separator.as_ref().expect("line separator");
+            let line_separator = line_separator.line_separator.as_str();
+            self.input = self
+                .input
+                .strip_prefix(line_separator)
+                .unwrap_or(self.input);
+        }
+        item
+    }
+
+    /// Trailing chunk of input, shorter than fixed size items, if it is to be yielded
+    fn partial_chunk(&mut self) -> Option<&'i str> {
+        let chunk = std::mem::take(&mut self.input);
+        match self.fmt.partial_chunk {
+            PartialChunk::Yield => Some(chunk),
+            PartialChunk::Drop | PartialChunk::Error => None,
+        }
+    }
+
+    /// Reader of `input`, found at byte `offset` of the whole input, carrying on with this
+    /// reader's format and state
+    fn with_input(self, input: &str, offset: usize) -> ItemReader<'_> {
+        ItemReader {
+            input,
+            fmt: self.fmt,
+            items_in_current_line: self.items_in_current_line,
+            started: self.started,
+            header: self.header,
+            line_start: self.line_start,
+            separated: self.separated,
+            items: self.items,
+            record_rest: self.record_rest,
+            read_items: self.read_items,
+            line_items: self.line_items,
+            line_offset: self.line_offset,
+            lines: self.lines,
+            input_len: offset + input.len(),
+            #[cfg(feature = "regex")]
+            regex: self.regex,
+            finders: self.finders,
+            error: self.error,
+        }
+    }
+
+    /// Position within input, once started, as counted so far
+    fn position(&self) -> ReadPosition {
+        ReadPosition {
+            items_in_current_line: self.items_in_current_line,
+            line_start: self.line_start,
+            items: self.items,
+            record_rest: self.record_rest,
+            read_items: self.read_items,
+            line_items: self.line_items,
+            line_offset: self.line_offset,
+            lines: self.lines,
+        }
+    }
+
+    /// Go back to a `position` within input, as counted then
+    fn rewind(&mut self, position: ReadPosition) {
+        self.items_in_current_line = position.items_in_current_line;
+        self.line_start = position.line_start;
+        self.items = position.items;
+        self.record_rest = position.record_rest;
+        self.read_items = position.read_items;
+        self.line_items = position.line_items;
+        self.line_offset = position.line_offset;
+        self.lines = position.lines;
+    }
+
+    /// Index of the searcher of `separator` within searchers, built if missing
+    fn finder(&mut self, separator: &str) -> usize {
+        let found = self
+            .finders
+            .iter()
+            .position(|finder| finder.needle() == separator.as_bytes());
+        found.unwrap_or_else(|| {
+            // item and line separators are the only ones searched for in turn
+            if self.finders.len() == 2 {
+                self.finders.remove(0);
+            }
+            let finder = memchr::memmem::Finder::new(separator.as_bytes()).into_owned();
+            self.finders.push(finder);
+            self.finders.len() - 1
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+/// Counts of an [ItemReader] at a position within its input, once started, to rewind it to
+struct ReadPosition {
+    items_in_current_line: usize,
+    line_start: bool,
+    items: usize,
+    record_rest: usize,
+    read_items: usize,
+    line_items: usize,
+    line_offset: usize,
+    lines: usize,
+}
+
+#[derive(Debug)]
+/// Items of input read in pieces from a source, as they are complete, i.e. when a separator or
+/// other input follows them, or at the end of input (see [StreamItemReader], [AsyncItemReader])
+struct ItemStream {
+    /// Format and state of reading, with no input
+    reader: Option<ItemReader<'static>>,
+    /// Input read from source, consumed up to `consumed`
+    buffer: String,
+    consumed: usize,
+    /// Bytes read from source not making a whole UTF-8 code point yet
+    pending: Vec<u8>,
+    /// Byte offset of buffer within the whole input
+    offset: usize,
+    end: bool,
+}
+
+impl ItemStream {
+    fn new(fmt: InFormat) -> Self {
+        Self {
+            reader: Some(ItemReader::new("", fmt)),
+            buffer: String::new(),
+            consumed: 0,
+            pending: Vec::new(),
+            offset: 0,
+            end: false,
+        }
+    }
+
+    /// Next item, `None` at the end of input, if input read so far tells it is complete
+    fn next_item(&mut self) -> Option<Result<Option<String>, ReadError>> {
+        let input = &self.buffer[self.consumed..];
+        if input.is_empty() && !self.end {
+            return None;
+        }
+        let state = self.reader.take().expect("reader state");
+        let position = state.position();
+        // skipped lines and header, read on start, are read again as a whole if incomplete
+        let start = (!state.started).then(|| state.clone());
+        let mut reader = state.with_input(input, self.offset + self.consumed);
+        let item = reader.try_next();
+        // an item is complete once a whole separator is matched after it, unless the separator
+        // may go on in input not read yet; errors but invalid boundaries may be due to input
+        // not read yet
+        let complete = self.end
+            || match item {
+                Ok(item) => {
+                    !reader.input.is_empty()
+                        || reader.exhausted()
+                        || item.is_some() && reader.separated && !reader.fmt.extensible_separator()
+                }
+                Err(error) => matches!(error, ReadError::InvalidBoundary { .. }),
+            };
+        if !complete {
+            self.reader = Some(start.unwrap_or_else(|| {
+                let mut state = reader.with_input("", 0);
+                state.rewind(position);
+                state
+            }));
+            return None;
+        }
+        let item = item.map(|item| item.map(str::to_string));
+        self.consumed += input.len() - reader.input.len();
+        self.reader = Some(reader.with_input("", 0));
+        Some(item)
+    }
+
+    /// Append `bytes` read from source to input, no bytes being the end of input; invalid UTF-8
+    /// is an error or is replaced as per input format
+    fn push(&mut self, bytes: &[u8]) -> Result<(), ReadError> {
+        self.buffer.drain(..self.consumed);
+        self.offset += self.consumed;
+        self.consumed = 0;
+        let replace = self
+            .reader
+            .as_ref()
+            .is_some_and(|reader| reader.fmt.invalid_utf8 == InvalidUtf8::Replace);
+        if bytes.is_empty() {
+            self.end = true;
+            if !self.pending.is_empty() {
+                if replace {
+                    self.pending.clear();
+                    self.buffer.push(char::REPLACEMENT_CHARACTER);
+                    return Ok(());
+                }
+                let offset = self.offset + self.buffer.len();
+                return Err(ReadError::InvalidUtf8 { offset });
+            }
+            return Ok(());
+        }
+        self.pending.extend_from_slice(bytes);
+        let mut decoded = 0;
+        while decoded < self.pending.len() {
+            let (valid, invalid) = match std::str::from_utf8(&self.pending[decoded..]) {
+                Ok(valid) => (valid, None),
+                Err(error) => {
+                    let valid = &self.pending[decoded..decoded + error.valid_up_to()];
+                    let valid = std::str::from_utf8(valid).expect("valid UTF-8");
+                    (valid, Some(error.error_len()))
+                }
+            };
+            self.buffer.push_str(valid);
+            decoded += valid.len();
+            match invalid {
+                // an incomplete code point may be completed by bytes read next
+                None | Some(None) => break,
+                Some(Some(len)) if replace => {
+                    self.buffer.push(char::REPLACEMENT_CHARACTER);
+                    decoded += len;
+                }
+                Some(Some(_)) => {
+                    let offset = self.offset + self.buffer.len();
+                    return Err(ReadError::InvalidUtf8 { offset });
+                }
+            }
+        }
+        self.pending.drain(..decoded);
+        Ok(())
+    }
+
+    /// Header labels, as per [ItemReader::header], once the first item has been read
+    fn header(&self) -> &[String] {
+        self.reader.as_ref().map_or(&[], |reader| &reader.header)
+    }
+
+    fn lines(&self) -> usize {
+        self.reader.as_ref().map_or(0, ItemReader::lines)
+    }
+}
+
+#[derive(Debug)]
+/// Reader of items from a source, e.g. standard input, as per input format, in constant memory
+/// but for items themselves; items are yielded as soon as they are complete, i.e. when a
+/// separator or other input follows them, or at the end of input
+///
+/// # Examples
+///
+/// ```
+/// let fmt = lineup::InFormatBuilder::default().build().unwrap();
+/// let mut reader = lineup::StreamItemReader::new("a,bb,ccc".as_bytes(), fmt);
+/// assert_eq!(Some("a".to_string()), reader.next_item().unwrap());
+/// let items: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+/// assert_eq!(items, ["bb", "ccc"]);
+/// ```
+pub struct StreamItemReader<R> {
+    source: R,
+    stream: ItemStream,
+    failed: bool,
+}
+
+impl<R: std::io::Read> StreamItemReader<R> {
+    /// Reader of items from `source`, e.g. a `std::io::Read` or `BufRead`
+    pub fn new(source: R, fmt: InFormat) -> Self {
+        Self {
+            source,
+            stream: ItemStream::new(fmt),
+            failed: false,
+        }
+    }
+
+    /// Next item, `None` at the end of input; input not being UTF-8, unless replaced as per
+    /// format, or not splitting as per format is a [ReadError]
+    pub fn next_item(&mut self) -> Result<Option<String>, LineupError> {
+        loop {
+            if let Some(item) = self.stream.next_item() {
+                return Ok(item?);
+            }
+            self.fill()?;
+        }
+    }
+
+    /// Header labels, as per [ItemReader::header], once the first item has been read
+    pub fn header(&self) -> &[String] {
+        self.stream.header()
+    }
+
+    /// Number of lines items have been read from so far, as per [ItemReader::lines]
+    pub fn lines(&self) -> usize {
+        self.stream.lines()
+    }
+
+    /// Read more input from source
+    fn fill(&mut self) -> Result<(), LineupError> {
+        let mut chunk = [0; 8192];
+        let read = loop {
+            match self.source.read(&mut chunk) {
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+                read => break read?,
+            }
+        };
+        Ok(self.stream.push(&chunk[..read])?)
+    }
+}
+
+impl<R: std::io::Read> Iterator for StreamItemReader<R> {
+    type Item = Result<String, LineupError>;
+    /// Next item as per input format, or the read error, after which there are no more items
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let item = self.next_item().transpose();
+        self.failed = matches!(item, Some(Err(_)));
+        item
+    }
+}
+
+#[cfg(feature = "async")]
+#[derive(Debug)]
+/// Reader of items from an asynchronous source, as per input format; items are yielded as soon
+/// as they are complete, i.e. when a separator or other input follows them, or at the end of
+/// input
+///
+/// # Examples
+///
+/// ```
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let fmt = lineup::InFormatBuilder::default().build().unwrap();
+/// let mut reader = lineup::AsyncItemReader::new("a,bb,ccc".as_bytes(), fmt);
+/// assert_eq!(Some("a".to_string()), reader.next_item().await.unwrap());
+/// assert_eq!(Some("bb".to_string()), reader.next_item().await.unwrap());
+/// assert_eq!(Some("ccc".to_string()), reader.next_item().await.unwrap());
+/// assert_eq!(None, reader.next_item().await.unwrap());
+/// # })
+/// ```
+pub struct AsyncItemReader<R> {
+    source: R,
+    stream: ItemStream,
+}
+
+#[cfg(feature = "async")]
+impl<R: tokio::io::AsyncRead + Unpin> AsyncItemReader<R> {
+    /// Reader of items from `source`, e.g. a `tokio::io::AsyncRead` or `AsyncBufRead`
+    pub fn new(source: R, fmt: InFormat) -> Self {
+        Self {
+            source,
+            stream: ItemStream::new(fmt),
+        }
+    }
+
+    /// Next item, `None` at the end of input; input not being UTF-8, unless replaced as per
+    /// format, or not splitting as per format is a [ReadError]
+    pub async fn next_item(&mut self) -> Result<Option<String>, LineupError> {
+        loop {
+            if let Some(item) = self.stream.next_item() {
+                return Ok(item?);
+            }
+            self.fill().await?;
+        }
+    }
+
+    /// Header labels, as per [ItemReader::header], once the first item has been read
+    pub fn header(&self) -> &[String] {
+        self.stream.header()
+    }
+
+    /// Number of lines items have been read from so far, as per [ItemReader::lines]
+    pub fn lines(&self) -> usize {
+        self.stream.lines()
+    }
+
+    /// Read more input from source
+    async fn fill(&mut self) -> Result<(), LineupError> {
+        let mut chunk = [0; 8192];
+        let read = tokio::io::AsyncReadExt::read(&mut self.source, &mut chunk).await?;
+        Ok(self.stream.push(&chunk[..read])?)
+    }
+}
+
+#[derive(Debug)]
+/// Reader of items as per input format, yielding the error input cannot be split as per format
+/// with, if any, as its last item (see [ItemReader::fallible])
+pub struct FallibleItemReader<'i> {
+    reader: ItemReader<'i>,
+    failed: bool,
+}
+
+impl<'i> ItemReader<'i> {
+    /// Iterator over items as per input format, yielding read errors rather than ending
+    pub fn fallible(self) -> FallibleItemReader<'i> {
+        FallibleItemReader {
+            reader: self,
+            failed: false,
+        }
+    }
+}
+
+impl<'i> FallibleItemReader<'i> {
+    /// Underlying reader
+    pub fn into_inner(self) -> ItemReader<'i> {
+        self.reader
+    }
+}
+
+impl<'i> Iterator for FallibleItemReader<'i> {
+    type Item = Result<&'i str, ReadError>;
+    /// Next item as per input format, or the read error, after which there are no more items
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let item = self.reader.try_next().transpose();
+        self.failed = matches!(item, Some(Err(_)));
+        item
+    }
+}
+
+#[derive(Debug)]
+/// Reader of items whose number is known in advance, i.e. fixed byte size ones (see
+/// [ItemReader::exact_size])
+pub struct ExactSizeItemReader<'i>(ItemReader<'i>);
+
+impl<'i> ItemReader<'i> {
+    /// Reader of a known number of items, if it can be computed from input length, i.e. items
+    /// are of fixed byte size (see [ItemSeparator::ByteCount]), with no line separator, byte
+    /// rounding, comments, header or skipped lines to be read yet, nor trimmed items to be
+    /// skipped; `None` otherwise
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let fmt = lineup::InFormatBuilder::default()
+    ///     .item_separator(lineup::ItemSeparator::ByteCount(2))
+    ///     .partial_chunk(lineup::PartialChunk::Yield)
+    ///     .build()
+    ///     .unwrap();
+    /// let mut reader = lineup::ItemReader::new("aabbc", fmt).exact_size().unwrap();
+    /// assert_eq!(3, reader.len());
+    /// reader.next();
+    /// assert_eq!(2, reader.len());
+    /// ```
+    pub fn exact_size(self) -> Option<ExactSizeItemReader<'i>> {
+        self.exact_len().map(|_| ExactSizeItemReader(self))
+    }
+
+    /// Number of items left, if it can be computed from input length (see [exact_size])
+    ///
+    /// [exact_size]: ItemReader::exact_size
+    fn exact_len(&self) -> Option<usize> {
+        let fmt = &self.fmt;
+        let ItemSeparator::ByteCount(count) = fmt.item_separator else {
+            return None;
+        };
+        if count == 0
+            || fmt.mode != InputMode::Plain
+            || fmt.line_separator.is_some()
+            || fmt.byte_rounding != ByteRounding::Error
+            || fmt.comment.is_some()
+            || fmt.trim && fmt.empty_items == EmptyItems::Skip
+            || !self.started && (fmt.skip_lines > 0 || fmt.header)
+        {
+            return None;
+        }
+        let input = match self.started || !fmt.strip_bom {
+            true => self.input,
+            false => self.input.strip_prefix('\u{feff}').unwrap_or(self.input),
+        };
+        let partial = fmt.partial_chunk == PartialChunk::Yield && input.len() % count > 0;
+        let items = input.len() / count + usize::from(partial);
+        let items = items.saturating_sub(fmt.skip_items.saturating_sub(self.items));
+        let taken = self.items.saturating_sub(fmt.skip_items);
+        Some(match fmt.max_items {
+            Some(max_items) => items.min(max_items.saturating_sub(taken)),
+            None => items,
+        })
+    }
+}
+
+impl<'i> Iterator for ExactSizeItemReader<'i> {
+    type Item = &'i str;
+    /// Next item as per input format (see [ItemReader::next])
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl ExactSizeIterator for ExactSizeItemReader<'_> {}
+
+impl<'i> Iterator for ItemReader<'i> {
+    type Item = &'i str;
+    /// Next item as per input format, `None` also if input cannot be split as per format, the
+    /// error being then returned by [try_next] (see also [fallible])
+    ///
+    /// [try_next]: ItemReader::try_next
+    /// [fallible]: ItemReader::fallible
+    fn next(&mut self) -> Option<Self::Item> {
+        self.try_next().unwrap_or_else(|error| {
+            self.error = Some(error);
+            None
+        })
+    }
+
+    /// Exact number of items left if it can be computed from input length (see
+    /// [exact_size]), no bounds otherwise
+    ///
+    /// [exact_size]: ItemReader::exact_size
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.exact_len() {
+            Some(items) => (items, Some(items)),
+            None => (0, None),
+        }
+    }
+}
+
+impl ItemWriter {
+    /// Apply `transform` to every item before anything else, e.g. case transform, decorations
+    /// and padding; header and footer labels are not transformed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    ///
+    /// let format = lineup::OutFormatBuilder::default()
+    ///     .span(Some(lineup::ItemSpan::new(3, '_', lineup::Anchor::Right)))
+    ///     .build()
+    ///     .unwrap();
+    /// let mut writer = lineup::ItemWriter::new(format).with_transform(|item| match item {
+    ///     "secret" => Cow::Borrowed("***"),
+    ///     _ => Cow::Borrowed(item.trim()),
+    /// });
+    /// let mut output = Vec::new();
+    /// writer.write(" a ", &mut output).unwrap();
+    /// writer.write("secret", &mut output).unwrap();
+    /// writer.finish(&mut output).unwrap();
+    /// assert_eq!(String::from_utf8(output).unwrap(), "__a ***");
+    /// ```
+    pub fn with_transform<F>(mut self, transform: F) -> Self
+    where
+        F: for<'a> Fn(&'a str) -> Cow<'a, str> + Send + Sync + 'static,
+    {
+        self.transform = Some(Box::new(transform));
+        self
+    }
+
+    /// Override format settings of every item by `formatter`, given the item, after any user
+    /// transform (see [with_transform]), and its position
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let format = lineup::OutFormatBuilder::default()
+    ///     .span(Some(lineup::ItemSpan::new(3, ' ', lineup::Anchor::Left)))
+    ///     .line_separator(Some(lineup::LineSeparator::new(2, "\n".to_string())))
+    ///     .build()
+    ///     .unwrap();
+    /// let mut writer = lineup::ItemWriter::new(format).with_formatter(|_, position| {
+    ///     match position.column {
+    ///         0 => lineup::ItemOverride {
+    ///             pad: Some('0'),
+    ///             anchor: Some(lineup::Anchor::Right),
+    ///             ..Default::default()
+    ///         },
+    ///         _ => lineup::ItemOverride::default(),
+    ///     }
+    /// });
+    /// let mut output = Vec::new();
+    /// for item in ["1", "a", "22", "b"] {
+    ///     writer.write(item, &mut output).unwrap();
+    /// }
+    /// writer.finish(&mut output).unwrap();
+    /// assert_eq!(String::from_utf8(output).unwrap(), "001 a  \n022 b  ");
+    /// ```
+    ///
+    /// [with_transform]: ItemWriter::with_transform
+    pub fn with_formatter<F>(mut self, formatter: F) -> Self
+    where
+        F: Fn(&str, Position) -> ItemOverride + Send + Sync + 'static,
+    {
+        self.formatter = Some(Box::new(formatter));
+        self
+    }
+
+    /// Write output one complete line at a time: output is held back until the line it belongs
+    /// to is complete, then written with a single write call, and output is flushed; lines from
+    /// separate writers appending to the same file are thus not interleaved
+    ///
+    /// a line is complete once the next item is written or on [finish]; amounts of output
+    /// written account for the output actually written, whichever items it belongs to
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let format = lineup::OutFormatBuilder::default()
+    ///     .line_separator(Some(lineup::LineSeparator::new(2, "\n".to_string())))
+    ///     .build()
+    ///     .unwrap();
+    /// let mut writer = lineup::ItemWriter::new(format).with_line_batches();
+    /// let mut output = Vec::new();
+    /// writer.write("a", &mut output).unwrap();
+    /// writer.write("b", &mut output).unwrap();
+    /// assert!(output.is_empty());
+    /// writer.write("c", &mut output).unwrap();
+    /// assert_eq!(String::from_utf8(output.clone()).unwrap(), "a b\n");
+    /// writer.finish(&mut output).unwrap();
+    /// assert_eq!(String::from_utf8(output).unwrap(), "a b\nc");
+    /// ```
+    ///
+    /// [finish]: ItemWriter::finish
+    pub fn with_line_batches(mut self) -> Self {
+        self.line_batches = true;
+        self
+    }
+
+    /// Write input item as per provided format
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let input = ["😊😊", "👶", "💼💼💼"];
+    /// let format = lineup::OutFormatBuilder::default()
+    ///     .span(Some(lineup::ItemSpan::new(4, '👉', lineup::Anchor::Right)))
+    ///     .item_separator("🖖".to_string())
+    ///     .line_separator(Some(lineup::LineSeparator::new(2, "🔩\n".to_string())))
+    ///     .build()
+    ///     .unwrap();
+    /// let expected = "👉👉😊😊🖖👉👉👉👶🔩\n👉💼💼💼";
+    /// let mut output = vec![0u8; 100 ];
+    /// let mut writer = lineup::ItemWriter::new(format);
+    /// let istream = input.into_iter();
+    /// let mut ostream = output.as_mut_slice();
+    /// let mut written = lineup::Written::default();
+    /// for item in istream {
+    ///     written += writer.write(item, &mut ostream).unwrap();
+    /// }
+    /// assert_eq!(written.items, 3);
+    /// let output = &output[..written.bytes];
+    /// assert_eq!(String::from_utf8(output.to_vec()).unwrap(), expected);
+    /// ```
+    ///
+    /// returns the amount of output written for the item, separators and labels preceding it
+    /// included; when items are laid out column-major (see [FillOrder]), they are buffered and
+    /// only written on [finish]
+    ///
+    /// [FillOrder]: crate::FillOrder
+    /// [finish]: ItemWriter::finish
+    pub fn write<Out: std::io::Write>(
+        &mut self,
+        item: &str,
+        writer: &mut Out,
+    ) -> Result<Written, LineupError> {
+        if self.fmt.column_major() {
+            self.buffer.push(item.to_string());
+            return Ok(Written::default());
+        }
+        let mut counter = ByteCounter::new(writer);
+        self.batched(&mut counter, |writer, batch| writer.emit(item, batch))?;
+        Ok(Written {
+            bytes: counter.bytes,
+            items: 1,
+        })
+    }
+
+    /// Write input item right away, as the next one in row-major order
+    fn emit<Out: std::io::Write>(
+        &mut self,
+        item: &str,
+        writer: &mut Out,
+    ) -> Result<(), LineupError> {
+        // emit output start and header before first item
+        if !self.started {
+            self.start(writer, true)?;
+        }
+
+        // end the previous line and write it out, if writing batches of lines
+        if self.line_batches && matches!(self.separator, EmittingSeparator::Line) {
+            self.emit_separator(writer, 0, false)?;
+            writer.flush()?;
+            writer.write_all(self.fmt.line_start().as_bytes())?;
+            self.separator = EmittingSeparator::None;
+            self.line_width = 0;
+        }
+
+        // apply user transform, decorations and output mode
+        let input = item;
+        let item = match &self.transform {
+            Some(transform) => transform(input),
+            None => Cow::Borrowed(input),
+        };
+        let column = self.items_in_line;
+        let position = Position::new(self.line, column);
+        let item_override = match &self.formatter {
+            Some(formatter) => formatter(&item, position),
+            None => ItemOverride::default(),
+        };
+        let item = self.fmt.render(&item, position, Some(self.index));
+
+        // start a new line instead, if input would exceed max line width
+        if let (EmittingSeparator::Item, Some(max_width)) =
+            (&self.separator, self.fmt.max_line_width())
+        {
+            let cell = self.fmt.cell(&item, column, &item_override);
+            let item_break = self.fmt.item_break(column, self.line_width);
+            let item_break_width = self.fmt.width.of(&item_break);
+            if self.line_width + item_break_width + self.padded_width(&item, &cell)? > max_width {
+                self.separator = EmittingSeparator::Line;
+                self.items_in_line = 0;
+                self.line += 1;
+                if !self.row.is_empty() {
+                    self.emit_row(writer)?;
+                }
+                return self.emit(input, writer);
+            }
+        }
+        self.index += 1;
+
+        if self.fmt.wraps() {
+            // emit separator from previous line, then buffer input until line is complete
+            if self.row.is_empty() {
+                self.line_width = self.emit_separator(writer, column, true)?;
+            } else {
+                let item_break = self.fmt.item_break(column, self.line_width);
+                self.line_width += self.fmt.width.of(&item_break);
+            }
+            let cell = self.fmt.cell(&item, column, &item_override);
+            self.line_width += self.padded_width(&item, &cell)?;
+            self.row.push((item.into_owned(), item_override));
+        } else {
+            // apply overflow policy
+            let cell = self.fmt.cell(&item, column, &item_override);
+            let (fitted, item_width) = self.fit(&item, &cell)?;
+
+            // write separator from previous input along with (padded) input
+            let separator = self.separator(column, true);
+            let line_width = separator.width;
+            let padded_width = self.write_padded(&separator, &fitted, item_width, &cell, writer)?;
+            self.line_width = line_width + padded_width;
+        }
+
+        // decide on separator for next input
+        (self.separator, self.items_in_line) =
+            if let Some(line_separator) = self.fmt.line_separator.as_ref() {
+                if self.items_in_line + 1 < line_separator.items_per_line {
+                    (EmittingSeparator::Item, self.items_in_line + 1)
+                } else {
+                    (EmittingSeparator::Line, 0)
+                }
+            } else {
+                (EmittingSeparator::Item, self.items_in_line + 1)
+            };
+        if matches!(self.separator, EmittingSeparator::Line) {
+            self.line += 1;
+            if !self.row.is_empty() {
+                self.emit_row(writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write buffered line, wrapping items as needed
+    fn emit_row<Out: std::io::Write>(&mut self, writer: &mut Out) -> Result<(), LineupError> {
+        let row = std::mem::take(&mut self.row);
+        self.line_width = self.write_row(&row, writer)?;
+        Ok(())
+    }
+
+    /// End the current line, if it has any item, so that the next item starts a new one; the
+    /// line is filled with empty items as per [fill_last_line]; lines are not ended when items
+    /// are laid out column-major (see [FillOrder])
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let format = lineup::OutFormatBuilder::default()
+    ///     .item_separator("|".to_string())
+    ///     .build()
+    ///     .unwrap();
+    /// let mut writer = lineup::ItemWriter::new(format);
+    /// let mut output = Vec::new();
+    /// writer.write("a", &mut output).unwrap();
+    /// writer.end_line(&mut output).unwrap();
+    /// writer.end_line(&mut output).unwrap();
+    /// writer.write("b", &mut output).unwrap();
+    /// writer.finish(&mut output).unwrap();
+    /// assert_eq!(String::from_utf8(output).unwrap(), "a\nb");
+    /// ```
+    ///
+    /// [fill_last_line]: crate::OutFormat::fill_last_line
+    /// [FillOrder]: crate::FillOrder
+    pub fn end_line<Out: std::io::Write>(
+        &mut self,
+        writer: &mut Out,
+    ) -> Result<Written, LineupError> {
+        if self.fmt.column_major() || !matches!(self.separator, EmittingSeparator::Item) {
+            return Ok(Written::default());
+        }
+        let mut counter = ByteCounter::new(writer);
+        self.batched(&mut counter, |writer, batch| {
+            writer.fill_line(batch)?;
+            writer.separator = EmittingSeparator::Line;
+            if writer.items_in_line > 0 {
+                writer.items_in_line = 0;
+                writer.line += 1;
+            }
+            if !writer.row.is_empty() {
+                writer.emit_row(batch)?;
+            }
+            Ok(())
+        })?;
+        Ok(Written {
+            bytes: counter.bytes,
+            items: 0,
+        })
+    }
+
+    /// Finalize output once all items have been written, then flush it; the writer is reset
+    /// so that it can be used for a new sequence of items
+    ///
+    /// [write] calls this automatically at the end of its input; returns the amount of output
+    /// written, buffered column-major items included
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let format = lineup::OutFormatBuilder::default()
+    ///     .item_separator("|".to_string())
+    ///     .build()
+    ///     .unwrap();
+    /// let mut writer = lineup::ItemWriter::new(format);
+    /// let mut output = Vec::new();
+    /// writer.write("a", &mut output).unwrap();
+    /// writer.write("b", &mut output).unwrap();
+    /// writer.finish(&mut output).unwrap();
+    /// writer.write("c", &mut output).unwrap();
+    /// writer.finish(&mut output).unwrap();
+    /// assert_eq!(String::from_utf8(output).unwrap(), "a|bc");
+    /// ```
+    ///
+    /// [write]: crate::write
+    pub fn finish<Out: std::io::Write>(
+        &mut self,
+        writer: &mut Out,
+    ) -> Result<Written, LineupError> {
+        let items = self.buffer.len();
+        let mut counter = ByteCounter::new(writer);
+        self.batched(&mut counter, |writer, batch| writer.finish_output(batch))?;
+        Ok(Written {
+            bytes: counter.bytes,
+            items,
+        })
+    }
+
+    /// Write output by `write`, in batches of lines if set to (see [with_line_batches])
+    ///
+    /// [with_line_batches]: ItemWriter::with_line_batches
+    fn batched<Out, F>(&mut self, writer: &mut Out, write: F) -> Result<(), LineupError>
+    where
+        Out: std::io::Write,
+        F: FnOnce(&mut Self, &mut LineBatch<'_, Out>) -> Result<(), LineupError>,
+    {
+        let mut batch = LineBatch {
+            writer,
+            line: std::mem::take(&mut self.batch),
+            batching: self.line_batches,
+        };
+        let result = write(self, &mut batch);
+        self.batch = batch.line;
+        result
+    }
+
+    /// Write buffered items, line fill, footer and epilogue, then reset writer
+    fn finish_output<Out: std::io::Write>(&mut self, writer: &mut Out) -> Result<(), LineupError> {
+        if !self.buffer.is_empty() {
+            self.emit_column_major(writer)?;
+        }
+        self.fill_line(writer)?;
+        if !self.row.is_empty() {
+            self.emit_row(writer)?;
+        }
+        if !self.started {
+            if self.fmt.header_labels().is_empty() && self.fmt.footer.is_empty() {
+                writer.write_all(self.fmt.mode.empty_output().as_bytes())?;
+                return self.reset(writer);
+            }
+            self.start(writer, false)?;
+        }
+        let trailing_separator = self.fmt.trailing_separator && !self.fmt.mode.is_json();
+        if !self.fmt.footer.is_empty() {
+            let written = !matches!(self.separator, EmittingSeparator::None);
+            if written || !self.fmt.header_labels().is_empty() {
+                writer.write_all(self.fmt.line_end().as_bytes())?;
+                writer.write_all(self.fmt.line_break().as_bytes())?;
+                writer.write_all(self.fmt.line_start().as_bytes())?;
+            }
+            self.write_labels(&self.fmt.footer, writer)?;
+            if trailing_separator {
+                writer.write_all(self.fmt.line_end().as_bytes())?;
+                writer.write_all(self.fmt.line_break().as_bytes())?;
+            }
+        } else if trailing_separator {
+            self.line_width = self.emit_separator(writer, self.items_in_line, false)?;
+        }
+        writer.write_all(self.fmt.epilogue().as_bytes())?;
+        self.reset(writer)
+    }
+
+    /// Write buffered items, laid out down the columns first
+    fn emit_column_major<Out: std::io::Write>(
+        &mut self,
+        writer: &mut Out,
+    ) -> Result<(), LineupError> {
+        let items = std::mem::take(&mut self.buffer);
+        let columns = self.fmt.line_separator.as_ref().unwrap().items_per_line;
+        let rows = items.len().div_ceil(columns);
+        for row in 0..rows {
+            for (index, item) in items.iter().enumerate().skip(row).step_by(rows) {
+                self.index = index;
+                self.emit(item, writer)?;
+            }
+            self.fill_line(writer)?;
+            if !self.row.is_empty() {
+                self.emit_row(writer)?;
+            }
+            // last column might be shorter, so lines are explicitly ended
+            if !matches!(self.separator, EmittingSeparator::Line) {
+                self.line += 1;
+            }
+            self.separator = EmittingSeparator::Line;
+            self.items_in_line = 0;
+        }
+        Ok(())
+    }
+
+    /// Complete current line with empty cells, if set to and the line is not complete yet
+    fn fill_line<Out: std::io::Write>(&mut self, writer: &mut Out) -> Result<(), LineupError> {
+        let items_per_line = match &self.fmt.line_separator {
+            Some(line_separator) if line_separator.max_width.is_none() => {
+                line_separator.items_per_line
+            }
+            _ => return Ok(()),
+        };
+        if !self.fmt.fill_last_line || !matches!(self.separator, EmittingSeparator::Item) {
+            return Ok(());
+        }
+        for column in self.items_in_line..items_per_line {
+            let empty = self.fmt.encode("", column);
+            if self.fmt.wraps() {
+                self.row.push((empty.into_owned(), ItemOverride::default()));
+            } else {
+                let cell = self.fmt.cell(&empty, column, &ItemOverride::default());
+                let separator = self.separator(column, true);
+                let line_width = separator.width;
+                let empty_width = self.fmt.width.of(&empty);
+                let padded_width =
+                    self.write_padded(&separator, &empty, empty_width, &cell, writer)?;
+                self.line_width = line_width + padded_width;
+            }
+        }
+        self.separator = EmittingSeparator::Line;
+        self.items_in_line = 0;
+        self.line += 1;
+        Ok(())
+    }
+
+    /// Carry on as if items up to `index`, the first of a line laid out row-major, had been
+    /// written already, header included
+    #[cfg(feature = "rayon")]
+    fn resume(&mut self, index: usize) {
+        let items_per_line = self
+            .fmt
+            .line_separator
+            .as_ref()
+            .map_or(0, |line_separator| line_separator.items_per_line);
+        self.started = true;
+        self.separator = EmittingSeparator::Line;
+        self.index = index;
+        self.line = index.checked_div(items_per_line).unwrap_or(0);
+    }
+
+    /// Reset writer state for a new sequence of items, flushing output
+    fn reset<Out: std::io::Write>(&mut self, writer: &mut Out) -> Result<(), LineupError> {
+        self.separator = EmittingSeparator::None;
+        self.items_in_line = 0;
+        self.line_width = 0;
+        self.line = 0;
+        self.index = 0;
+        self.started = false;
+        self.buffer.clear();
+        self.row.clear();
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Apply overflow policy to an item of given format, returning it along with its width
+    fn fit<'a>(&self, item: &'a str, cell: &Cell) -> Result<(Cow<'a, str>, usize), LineupError> {
+        let width = self.fmt.width;
+        let item_width = width.of(item);
+        match cell.span {
+            Some(span) if item_width > span.span => match span.overflow {
+                OverflowPolicy::Overflow | OverflowPolicy::Wrap => {
+                    Ok((Cow::Borrowed(item), item_width))
+                }
+                OverflowPolicy::Truncate => {
+                    let anchor = span.anchor;
+                    let ellipsis = span.ellipsis;
+                    let ellipsis_width = width.of(ellipsis);
+                    let truncated = if ellipsis_width >= span.span {
+                        Cow::Owned(
+                            width
+                                .truncate(ellipsis, span.span, Anchor::Left)
+                                .to_string(),
+                        )
+                    } else {
+                        let kept = width.truncate(item, span.span - ellipsis_width, anchor);
+                        match anchor {
+                            _ if ellipsis.is_empty() => Cow::Borrowed(kept),
+                            Anchor::Right => Cow::Owned(format!("{ellipsis}{kept}")),
+                            Anchor::Left | Anchor::Center(_) | Anchor::Decimal { .. } => {
+                                Cow::Owned(format!("{kept}{ellipsis}"))
+                            }
+                        }
+                    };
+                    let truncated_width = width.of(&truncated);
+                    Ok((truncated, truncated_width))
+                }
+                OverflowPolicy::Error => Err(LineupError::Overflow {
+                    item: item.to_string(),
+                    width: item_width,
+                    span: span.span,
+                }),
+            },
+            _ => Ok((Cow::Borrowed(item), item_width)),
+        }
+    }
+
+    /// Width of a rendered item of given format once written, wrapped or padded as per its span
+    fn padded_width(&self, item: &str, cell: &Cell) -> Result<usize, LineupError> {
+        match cell.span {
+            Some(span) if span.overflow == OverflowPolicy::Wrap && self.fmt.wraps() => {
+                Ok(span.span)
+            }
+            Some(span) => Ok(self.fit(item, cell)?.1.max(span.span)),
+            None => Ok(self.fmt.width.of(item)),
+        }
+    }
+
+    /// Write `separator` then an item of given width and format, padded as per its span, styled
+    /// and wrapped in directional isolates if required by output format, all at once; returns
+    /// the padded width
+    fn write_padded<Out: std::io::Write>(
+        &self,
+        separator: &Separator,
+        item: &str,
+        item_width: usize,
+        cell: &Cell,
+        writer: &mut Out,
+    ) -> Result<usize, LineupError> {
+        let (left_pad_count, right_pad_count, padded_width) = match cell.span {
+            Some(span) if item_width < span.span => {
+                let pad_count = span.span - item_width;
+                let (left_pad_count, right_pad_count) = match span.anchor {
+                    Anchor::Left => (0, pad_count),
+                    Anchor::Right => (pad_count, 0),
+                    Anchor::Center(Side::Left) => (pad_count - pad_count / 2, pad_count / 2),
+                    Anchor::Center(Side::Right) => (pad_count / 2, pad_count - pad_count / 2),
+                    Anchor::Decimal { point, fraction } => match item.find(point) {
+                        Some(position) => {
+                            let right_pad_count = (fraction + 1)
+                                .saturating_sub(self.fmt.width.of(&item[position..]))
+                                .min(pad_count);
+                            (pad_count - right_pad_count, right_pad_count)
+                        }
+                        None => (pad_count, 0),
+                    },
+                };
+                (left_pad_count, right_pad_count, span.span)
+            }
+            _ => (0, 0, item_width),
+        };
+        let pads = Pads::new(
+            cell.span.map_or(' ', |span| span.pad),
+            left_pad_count.max(right_pad_count),
+        );
+        let escape = match cell.style.as_ref().map(Style::escape) {
+            Some(escape) if !item.is_empty() => escape,
+            _ => String::new(),
+        };
+        let isolates = self.fmt.isolates(item);
+
+        let mut gather = Gather::new();
+        separator.gather(&mut gather, writer)?;
+        pads.gather(left_pad_count, &mut gather, writer)?;
+        if isolates {
+            gather.push("\u{2068}".as_bytes(), writer)?;
+        }
+        gather.push(escape.as_bytes(), writer)?;
+        gather.push(item.as_bytes(), writer)?;
+        if !escape.is_empty() {
+            gather.push(b"\x1b[0m", writer)?;
+        }
+        if isolates {
+            gather.push("\u{2069}".as_bytes(), writer)?;
+        }
+        pads.gather(right_pad_count, &mut gather, writer)?;
+        gather.flush(writer)?;
+        Ok(padded_width)
+    }
+
+    /// Start output, then write header labels, if any, as a line of their own; when
+    /// `line_break` is set, the line separator follows them
+    fn start<Out: std::io::Write>(
+        &mut self,
+        writer: &mut Out,
+        line_break: bool,
+    ) -> Result<(), LineupError> {
+        self.started = true;
+        writer.write_all(self.fmt.prologue().as_bytes())?;
+        writer.write_all(self.fmt.line_start().as_bytes())?;
+        if self.fmt.header_labels().is_empty() {
+            return Ok(());
+        }
+        self.write_labels(self.fmt.header_labels(), writer)?;
+        if line_break {
+            writer.write_all(self.fmt.line_end().as_bytes())?;
+            writer.write_all(self.fmt.line_break().as_bytes())?;
+            if self.line_batches {
+                writer.flush()?;
+            }
+            writer.write_all(self.fmt.line_start().as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Write labels as a line, each formatted as an item at the same column position
+    fn write_labels<Out: std::io::Write>(
+        &self,
+        labels: &[String],
+        writer: &mut Out,
+    ) -> Result<(), LineupError> {
+        let labels: Vec<_> = labels
+            .iter()
+            .enumerate()
+            .map(|(column, label)| {
+                let label = self.fmt.render(label, Position::new(0, column), None);
+                (label, ItemOverride::default())
+            })
+            .collect();
+        self.write_row(&labels, writer)?;
+        Ok(())
+    }
+
+    /// Write rendered items as a line, one per column position, returning the width of its
+    /// last row; items whose span wraps them are split over as many new line separated rows as
+    /// needed, other columns being padded on continuation rows
+    fn write_row<Out: std::io::Write, Item: AsRef<str>>(
+        &self,
+        items: &[(Item, ItemOverride)],
+        writer: &mut Out,
+    ) -> Result<usize, LineupError> {
+        let cells: Vec<_> = items
+            .iter()
+            .enumerate()
+            .map(|(column, (item, item_override))| {
+                self.fmt.cell(item.as_ref(), column, item_override)
+            })
+            .collect();
+        // fit all items first, not to write a partial line on error
+        let columns = items
+            .iter()
+            .zip(&cells)
+            .map(|((item, _), cell)| match cell.span {
+                Some(span) if span.overflow == OverflowPolicy::Wrap && !self.fmt.mode.is_json() => {
+                    Ok(self
+                        .fmt
+                        .width
+                        .wrap(item.as_ref(), span.span)
+                        .into_iter()
+                        .map(|chunk| (Cow::Borrowed(chunk), self.fmt.width.of(chunk)))
+                        .collect())
+                }
+                _ => self.fit(item.as_ref(), cell).map(|fitted| vec![fitted]),
+            })
+            .collect::<Result<Vec<Vec<_>>, _>>()?;
+        let rows = columns.iter().map(Vec::len).max().unwrap_or(0).max(1);
+        let mut line_width = 0;
+        for row in 0..rows {
+            for (column, chunks) in columns.iter().enumerate() {
+                let separator = match (row, column) {
+                    (0, 0) => Separator::none(line_width),
+                    (_, 0) => Separator::new(
+                        [self.fmt.line_end(), "\n", self.fmt.line_start(), ""].map(Cow::Borrowed),
+                        0,
+                    ),
+                    _ => {
+                        let item_break = self.fmt.item_break(column, line_width);
+                        let width = line_width + self.fmt.width.of(&item_break);
+                        Separator::item(item_break, width)
+                    }
+                };
+                let (chunk, chunk_width) = chunks
+                    .get(row)
+                    .map_or(("", 0), |(chunk, width)| (chunk.as_ref(), *width));
+                line_width = separator.width
+                    + self.write_padded(&separator, chunk, chunk_width, &cells[column], writer)?;
+            }
+        }
+        Ok(line_width)
+    }
+
+    /// Write pending separator before an item at given `column`, returning the line width
+    /// following it; when `next_line` is set, a line separator is followed by the start of the
+    /// next line
+    fn emit_separator<Out: std::io::Write>(
+        &self,
+        writer: &mut Out,
+        column: usize,
+        next_line: bool,
+    ) -> Result<usize, LineupError> {
+        let separator = self.separator(column, next_line);
+        let mut gather = Gather::new();
+        separator.gather(&mut gather, writer)?;
+        gather.flush(writer)?;
+        Ok(separator.width)
+    }
+
+    /// Pending separator before an item at given `column` (see [emit_separator])
+    ///
+    /// [emit_separator]: ItemWriter::emit_separator
+    fn separator(&self, column: usize, next_line: bool) -> Separator<'_> {
+        match self.separator {
+            EmittingSeparator::None => Separator::none(self.line_width),
+            EmittingSeparator::Item => {
+                let item_break = self.fmt.item_break(column, self.line_width);
+                let width = self.line_width + self.fmt.width.of(&item_break);
+                Separator::item(item_break, width)
+            }
+            EmittingSeparator::Line => {
+                let page_break = self.fmt.page_break(self.line).unwrap_or_default();
+                let line_start = if next_line { self.fmt.line_start() } else { "" };
+                let pieces = [
+                    self.fmt.line_end(),
+                    self.fmt.line_break(),
+                    page_break,
+                    line_start,
+                ];
+                Separator::new(pieces.map(Cow::Borrowed), 0)
+            }
+        }
+    }
+}
+
+/// Write input items as per provided format to an asynchronous sink, as [ItemWriter] would
+///
+/// [ItemWriter]: crate::ItemWriter
+#[cfg(feature = "async")]
+pub struct AsyncItemWriter {
+    writer: ItemWriter,
+    /// Output of the last write, not written to sink yet
+    buffer: Vec<u8>,
+}
+
+#[cfg(feature = "async")]
+impl AsyncItemWriter {
+    pub fn new(format: OutFormat) -> Self {
+        Self::from(ItemWriter::new(format))
+    }
+
+    /// Write an item (see [ItemWriter::write])
+    pub async fn write<Out: tokio::io::AsyncWrite + Unpin>(
+        &mut self,
+        item: &str,
+        writer: &mut Out,
+    ) -> Result<Written, LineupError> {
+        let written = self.writer.write(item, &mut self.buffer)?;
+        self.flush_buffer(writer).await?;
+        Ok(written)
+    }
+
+    /// Finish writing items (see [ItemWriter::finish])
+    pub async fn finish<Out: tokio::io::AsyncWrite + Unpin>(
+        &mut self,
+        writer: &mut Out,
+    ) -> Result<Written, LineupError> {
+        let written = self.writer.finish(&mut self.buffer)?;
+        self.flush_buffer(writer).await?;
+        Ok(written)
+    }
+
+    async fn flush_buffer<Out: tokio::io::AsyncWrite + Unpin>(
+        &mut self,
+        writer: &mut Out,
+    ) -> Result<(), LineupError> {
+        tokio::io::AsyncWriteExt::write_all(writer, &self.buffer).await?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+/// Asynchronous writer carrying on with `writer` format, transform and formatter
+impl From<ItemWriter> for AsyncItemWriter {
+    fn from(writer: ItemWriter) -> Self {
+        Self {
+            writer,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+/// Sink taking each line written to it as an item, written to an output stream as per provided
+/// format, so as to line up the output of anything writing to a [std::io::Write]
+///
+/// lines are terminated by '\n', optionally preceded by '\r'; a last line with no terminator is
+/// taken as an item on [finish], which happens on drop at the latest, errors then being ignored
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Write;
+///
+/// let format = lineup::OutFormatBuilder::default()
+///     .span(Some(lineup::ItemSpan::new(3, '_', lineup::Anchor::Right)))
+///     .item_separator("|".to_string())
+///     .line_separator(Some(lineup::LineSeparator::new(2, "\n".to_string())))
+///     .build()
+///     .unwrap();
+/// let mut output = Vec::new();
+/// let mut sink = lineup::ItemSink::new(&mut output, format);
+/// writeln!(sink, "a").unwrap();
+/// write!(sink, "b\nc").unwrap();
+/// write!(sink, "c").unwrap();
+/// let written = sink.finish().unwrap();
+/// drop(sink);
+/// assert_eq!(written.items, 3);
+/// assert_eq!(String::from_utf8(output).unwrap(), "__a|__b\n_cc");
+/// ```
+///
+/// [finish]: ItemSink::finish
+pub struct ItemSink<Out: std::io::Write> {
+    writer: ItemWriter,
+    ostream: Out,
+    /// Bytes written since the last line terminator
+    line: Vec<u8>,
+    /// Output written since the last finish
+    written: Written,
+    /// Whether anything was written since the last finish
+    pending: bool,
+}
+
+impl<Out: std::io::Write> ItemSink<Out> {
+    pub fn new(ostream: Out, format: OutFormat) -> Self {
+        Self {
+            writer: ItemWriter::new(format),
+            ostream,
+            line: Vec::new(),
+            written: Written::default(),
+            pending: false,
+        }
+    }
+
+    /// Take the last line, if not empty, as an item and finish writing items (see
+    /// [ItemWriter::finish]), then flush the output stream
+    ///
+    /// returns the amount of output written since the last finish
+    pub fn finish(&mut self) -> Result<Written, LineupError> {
+        if !self.line.is_empty() {
+            let line = std::mem::take(&mut self.line);
+            self.write_line(&line)?;
+        }
+        self.written += self.writer.finish(&mut self.ostream)?;
+        self.ostream.flush()?;
+        self.pending = false;
+        Ok(std::mem::take(&mut self.written))
+    }
+
+    /// Output stream
+    pub fn get_ref(&self) -> &Out {
+        &self.ostream
+    }
+
+    /// Write a line, terminator excluded, as an item
+    fn write_line(&mut self, line: &[u8]) -> Result<(), LineupError> {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let item = std::str::from_utf8(line)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+        self.written += self.writer.write(item, &mut self.ostream)?;
+        Ok(())
+    }
+}
+
+impl<Out: std::io::Write> std::io::Write for ItemSink<Out> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, std::io::Error> {
+        self.pending = true;
+        let mut lines = buf.split_inclusive(|byte| *byte == b'\n').peekable();
+        while let Some(line) = lines.next() {
+            match line.strip_suffix(b"\n") {
+                Some(line) if self.line.is_empty() => self.write_line(line)?,
+                Some(line) => {
+                    self.line.extend_from_slice(line);
+                    let line = std::mem::take(&mut self.line);
+                    self.write_line(&line)?;
+                }
+                None => {
+                    debug_assert!(lines.peek().is_none());
+                    self.line.extend_from_slice(line);
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+
+    /// Flush the output stream; items are not finished, see [ItemSink::finish]
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        self.ostream.flush()
+    }
+}
+
+impl<Out: std::io::Write> Drop for ItemSink<Out> {
+    fn drop(&mut self) {
+        if self.pending {
+            let _ = self.finish();
+        }
+    }
+}
+
+/// Whether `item` looks like a number: an optional sign, digits with an optional '.' decimal
+/// point, and an optional exponent
+fn is_number(item: &str) -> bool {
+    let digits = |s: &str| s.bytes().take_while(u8::is_ascii_digit).count();
+    let unsigned = item.strip_prefix(['+', '-']).unwrap_or(item);
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(position) => (&unsigned[..position], Some(&unsigned[position + 1..])),
+        None => (unsigned, None),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let mantissa_ok = digits(integer) == integer.len()
+        && digits(fraction) == fraction.len()
+        && integer.len() + fraction.len() > 0;
+    let exponent_ok = exponent.is_none_or(|exponent| {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        !exponent.is_empty() && digits(exponent) == exponent.len()
+    });
+    mantissa_ok && exponent_ok
+}
+
+/// Number looking like one as per [is_number], by its significant digits
+struct Decimal {
+    negative: bool,
+    /// ASCII digits, without leading nor trailing zeros: none for zero
+    digits: Vec<u8>,
+    /// Power of ten of the first digit
+    exponent: i64,
+}
+
+impl Decimal {
+    /// Digits of `number`, or `None` if its exponent is out of range
+    fn parse(number: &str) -> Option<Self> {
+        let unsigned = number.strip_prefix(['+', '-']).unwrap_or(number);
+        let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+            Some(position) => (
+                &unsigned[..position],
+                unsigned[position + 1..].parse().ok()?,
+            ),
+            None => (unsigned, 0i64),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let all_digits = || integer.bytes().chain(fraction.bytes());
+        let leading_zeros = all_digits().take_while(|&digit| digit == b'0').count();
+        let mut digits: Vec<u8> = all_digits().skip(leading_zeros).collect();
+        while digits.last() == Some(&b'0') {
+            digits.pop();
+        }
+        let exponent = if digits.is_empty() {
+            0
+        } else {
+            exponent.checked_add(integer.len() as i64 - leading_zeros as i64 - 1)?
+        };
+        Some(Decimal {
+            negative: number.starts_with('-'),
+            digits,
+            exponent,
+        })
+    }
+
+    /// Digit of the given power of ten
+    fn digit(&self, power: i64) -> char {
+        usize::try_from(self.exponent - power)
+            .ok()
+            .and_then(|index| self.digits.get(index))
+            .map_or('0', |&digit| char::from(digit))
+    }
+
+    /// Round half away from zero to digits of powers of ten down to `power`
+    fn round(&mut self, power: i64) {
+        let Ok(kept) = usize::try_from(self.exponent - power + 1) else {
+            self.digits.clear();
+            return;
+        };
+        if kept >= self.digits.len() {
+            return;
+        }
+        let round_up = self.digits[kept] >= b'5';
+        self.digits.truncate(kept);
+        if round_up {
+            while self.digits.last() == Some(&b'9') {
+                self.digits.pop();
+            }
+            match self.digits.last_mut() {
+                Some(digit) => *digit += 1,
+                None => {
+                    self.digits.push(b'1');
+                    self.exponent += 1;
+                }
+            }
+        }
+        while self.digits.last() == Some(&b'0') {
+            self.digits.pop();
+        }
+        if self.digits.is_empty() {
+            self.exponent = 0;
+        }
+    }
+
+    /// Fixed notation, with at least `fraction` fraction digits
+    fn fixed(&self, fraction: usize) -> String {
+        let fraction_len = self.digits.len() as i64 - self.exponent - 1;
+        let last = -fraction_len.max(fraction as i64);
+        let mut fixed = String::with_capacity(self.digits.len() + fraction + 2);
+        if self.negative {
+            fixed.push('-');
+        }
+        for power in (last..=self.exponent.max(0)).rev() {
+            if power == -1 {
+                fixed.push('.');
+            }
+            fixed.push(self.digit(power));
+        }
+        fixed
+    }
+
+    /// Scientific notation, with `precision` mantissa fraction digits if given, or as many as
+    /// needed otherwise
+    fn scientific(&self, precision: Option<usize>) -> String {
+        let fraction = precision.unwrap_or(self.digits.len().saturating_sub(1));
+        let mut scientific = String::with_capacity(fraction + 8);
+        if self.negative {
+            scientific.push('-');
+        }
+        scientific.push(self.digit(self.exponent));
+        if fraction > 0 {
+            scientific.push('.');
+            for power in 1..=fraction as i64 {
+                scientific.push(self.digit(self.exponent - power));
+            }
+        }
+        scientific.push('e');
+        scientific.push_str(&self.exponent.to_string());
+        scientific
+    }
+}
+
+/// Anchor of each column of `items`, given with their column position: right if all of its
+/// non-empty items look like numbers, left otherwise
+fn detect_anchors<'a>(items: impl Iterator<Item = (usize, &'a str)>) -> Vec<Anchor> {
+    let mut numeric = Vec::new();
+    for (column, item) in items {
+        if numeric.len() <= column {
+            numeric.resize(column + 1, None);
+        }
+        let item = item.trim();
+        if !item.is_empty() {
+            let column = &mut numeric[column];
+            *column = Some(column.unwrap_or(true) && is_number(item));
+        }
+    }
+    numeric
+        .into_iter()
+        .map(|numeric| match numeric {
+            Some(true) => Anchor::Right,
+            Some(false) | None => Anchor::Left,
+        })
+        .collect()
+}
+
+/// Separate groups of three integer digits, before `decimal_point`, of a number in fixed notation
+/// by `grouping`
+fn group_digits(number: &str, grouping: char, decimal_point: char) -> String {
+    let (sign, unsigned) = match number.strip_prefix(['+', '-']) {
+        Some(unsigned) => number.split_at(number.len() - unsigned.len()),
+        None => ("", number),
+    };
+    let integer_len = unsigned.find(decimal_point).unwrap_or(unsigned.len());
+    let mut grouped = String::with_capacity(number.len() + integer_len / 3);
+    grouped.push_str(sign);
+    for (i, digit) in unsigned[..integer_len].chars().enumerate() {
+        if i > 0 && (integer_len - i) % 3 == 0 {
+            grouped.push(grouping);
+        }
+        grouped.push(digit);
+    }
+    grouped.push_str(&unsigned[integer_len..]);
+    grouped
+}
+
+/// Quoted and escaped JSON string for `item`
+fn json_string(item: &str) -> String {
+    let mut json = String::with_capacity(item.len() + 2);
+    json.push('"');
+    for c in item.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            '\u{8}' => json.push_str("\\b"),
+            '\u{c}' => json.push_str("\\f"),
+            c if c < ' ' => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Byte offset of the first line feed in `input`, if any
+fn find_newline(input: &str) -> Option<usize> {
+    memchr::memchr(b'\n', input.as_bytes())
+}
+
+/// JSON `string`, without quotes, with escape sequences unescaped; invalid ones are kept as is
+fn json_unescape(string: &str) -> String {
+    let mut unescaped = String::with_capacity(string.len());
+    let mut chars = string.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        let hex = |chars: &mut std::str::Chars| {
+            let code = chars.as_str().get(..4)?;
+            let code = u32::from_str_radix(code, 16).ok()?;
+            chars.nth(3);
+            Some(code)
+        };
+        match chars.next() {
+            Some('b') => unescaped.push('\u{8}'),
+            Some('f') => unescaped.push('\u{c}'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('t') => unescaped.push('\t'),
+            Some('u') => {
+                let high = hex(&mut chars);
+                let code = match high {
+                    Some(high @ 0xd800..=0xdbff) if chars.as_str().starts_with("\\u") => {
+                        let mut low_chars = chars.clone();
+                        low_chars.nth(1);
+                        match hex(&mut low_chars) {
+                            Some(low @ 0xdc00..=0xdfff) => {
+                                chars = low_chars;
+                                Some(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+                            }
+                            _ => Some(high),
+                        }
+                    }
+                    code => code,
+                };
+                match code.and_then(char::from_u32) {
+                    Some(c) => unescaped.push(c),
+                    None => unescaped.push_str("\\u"),
+                }
+            }
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Keep at most `width` of `item`, made of `units` (byte position and width of each of them),
+/// dropping units on the side opposite to `anchor`
+fn truncate<Units>(item: &str, units: Units, width: usize, anchor: Anchor) -> &str
+where
+    Units: DoubleEndedIterator<Item = (usize, usize)>,
+{
+    let mut kept = 0;
+    match anchor {
+        Anchor::Right => {
+            let mut start = item.len();
+            for (position, unit_width) in units.rev() {
+                if kept + unit_width > width {
+                    break;
+                }
+                kept += unit_width;
+                start = position;
+            }
+            &item[start..]
+        }
+        Anchor::Left | Anchor::Center(_) | Anchor::Decimal { .. } => {
+            for (position, unit_width) in units {
+                if kept + unit_width > width {
+                    return &item[..position];
+                }
+                kept += unit_width;
+            }
+            item
+        }
+    }
+}
+
+#[cfg(test)]
+// items are mostly passed as iterators, as write functions took them before taking any
+// IntoIterator
+#[allow(clippy::useless_conversion)]
+mod write_test {
+    use super::*;
+
+    #[test]
+    fn long_pads() {
+        for (span, pad) in [(1, '-'), (33, '·'), (70, '👉')] {
+            let format = OutFormatBuilder::default()
+                .span(Some(ItemSpan::new(
+                    span + 1,
+                    pad,
+                    Anchor::Center(Side::Left),
+                )))
+                .build()
+                .unwrap();
+            let mut output = Vec::new();
+            write(["a"], &mut output, format).unwrap();
+            let left = pad.to_string().repeat(span - span / 2);
+            let right = pad.to_string().repeat(span / 2);
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                format!("{left}a{right}")
+            );
+        }
+    }
+
+    #[test]
+    fn vectored_writes() {
+        /// Sink recording every write call
+        #[derive(Default)]
+        struct Calls(Vec<String>);
+
+        impl std::io::Write for Calls {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.write_vectored(&[std::io::IoSlice::new(buf)])
+            }
+
+            fn write_vectored(&mut self, bufs: &[std::io::IoSlice<'_>]) -> std::io::Result<usize> {
+                let call: Vec<u8> = bufs.iter().flat_map(|buf| buf.iter().copied()).collect();
+                self.0.push(String::from_utf8(call).unwrap());
+                Ok(bufs.iter().map(|buf| buf.len()).sum())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(3, '_', Anchor::Center(Side::Left))))
+            .item_separator(",".to_string())
+            .line_separator(Some(LineSeparator::new(2, ";\n".to_string())))
+            .build()
+            .unwrap();
+        let mut calls = Calls::default();
+        write(["a", "b", "c"], &mut calls, format.clone()).unwrap();
+        // separator, pads and item of each item, in a single call
+        assert_eq!(calls.0, ["_a_", ",_b_", ";\n_c_"]);
+
+        /// Sink writing at most 2 bytes a call, of the first slice
+        struct Short(Vec<u8>);
+
+        impl std::io::Write for Short {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                let len = buf.len().min(2);
+                self.0.extend_from_slice(&buf[..len]);
+                Ok(len)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut short = Short(Vec::new());
+        write(["a", "b", "c"], &mut short, format).unwrap();
+        assert_eq!(String::from_utf8(short.0).unwrap(), "_a_,_b_;\n_c_");
+    }
+
+    #[test]
+    fn line_batches() {
+        /// Sink recording every batch of output flushed
+        #[derive(Default)]
+        struct Batches {
+            pending: Vec<u8>,
+            batches: Vec<String>,
+        }
+
+        impl std::io::Write for Batches {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.pending.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                let batch = std::mem::take(&mut self.pending);
+                if !batch.is_empty() {
+                    self.batches.push(String::from_utf8(batch).unwrap());
+                }
+                Ok(())
+            }
+        }
+
+        let items = ["a", "bb", "ccccc", "d", "e"];
+        let line_separator = Some(LineSeparator::new(2, ";\n".to_string()));
+        let formats = [
+            OutFormatBuilder::default()
+                .line_separator(line_separator.clone())
+                .header(vec!["h".to_string()])
+                .footer(vec!["f".to_string()])
+                .line_prefix("<".to_string())
+                .line_suffix(">".to_string())
+                .build()
+                .unwrap(),
+            OutFormatBuilder::default()
+                .line_separator(line_separator.clone())
+                .fill_order(FillOrder::ColumnMajor)
+                .trailing_separator(true)
+                .build()
+                .unwrap(),
+            OutFormatBuilder::default()
+                .span(Some(
+                    ItemSpanBuilder::default()
+                        .span(3)
+                        .overflow(OverflowPolicy::Wrap)
+                        .build()
+                        .unwrap(),
+                ))
+                .line_separator(line_separator)
+                .build()
+                .unwrap(),
+        ];
+        let expected_batches = [
+            vec!["<h>;\n", "<a bb>;\n", "<ccccc d>;\n", "<e>;\n<f"],
+            vec!["a d;\n", "bb e;\n", "ccccc;\n"],
+            vec!["a   bb ;\n", "ccc d  \ncc     ;\n", "e  "],
+        ];
+        for (format, expected_batches) in formats.into_iter().zip(expected_batches) {
+            let expected = write_string(items, format.clone()).unwrap();
+            let mut writer = ItemWriter::new(format).with_line_batches();
+            let mut output = Batches::default();
+            let mut written = Written::default();
+            for item in items {
+                written += writer.write(item, &mut output).unwrap();
+            }
+            written += writer.finish(&mut output).unwrap();
+            assert_eq!(output.batches, expected_batches);
+            assert_eq!(output.batches.concat(), expected);
+            assert_eq!(written.bytes, expected.len());
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn write_parallel() {
+        let items: Vec<String> = (0..23).map(|item| item.to_string()).collect();
+        let formats = [
+            OutFormatBuilder::default()
+                .line_separator(Some(LineSeparator::new(3, "\n".to_string())))
+                .page_separator(Some(PageSeparator::new(2, "--\n".to_string())))
+                .header(vec!["a".to_string(), "b".to_string()])
+                .footer(vec!["z".to_string()])
+                .fill_last_line(true)
+                .template(Some(Template::new("{index}:{item}").unwrap()))
+                .build()
+                .unwrap(),
+            OutFormatBuilder::default()
+                .span(Some(ItemSpan::new(3, ' ', Anchor::Right)))
+                .line_separator(Some(LineSeparator::new(4, "\n".to_string())))
+                .mode(OutputMode::Json)
+                .build()
+                .unwrap(),
+            OutFormatBuilder::default()
+                .line_separator(Some(LineSeparator::new(4, "\n".to_string())))
+                .fill_order(FillOrder::ColumnMajor)
+                .build()
+                .unwrap(),
+            OutFormatBuilder::default()
+                .line_separator(Some(LineSeparator::by_width(10, "\n".to_string())))
+                .build()
+                .unwrap(),
+        ];
+        for format in formats {
+            for count in [0, 1, 4, 23] {
+                let items = &items[..count];
+                let mut expected = Vec::new();
+                write(items, &mut expected, format.clone()).unwrap();
+                for jobs in 1..8 {
+                    let mut output = Vec::new();
+                    let written = super::write_parallel(items, &mut output, format.clone(), jobs);
+                    assert_eq!(written.unwrap().bytes, expected.len());
+                    assert_eq!(output, expected, "{count} items by {jobs} jobs");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_format() {
+        let error = OutFormatBuilder::default()
+            .line_separator(Some(LineSeparator::new(0, "\n".to_string())))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid format: line separator has 0 items per line"
+        );
+        let error = OutFormatBuilder::default()
+            .page_separator(Some(PageSeparator::new(0, "\x0c".to_string())))
+            .build()
+            .unwrap_err();
+        assert!(matches!(error, LineupError::Validation(_)));
+        let error = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(0, '_', Anchor::Left)))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid format: span 0 with pad '_': items are never padded"
+        );
+        assert!(OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(0, ' ', Anchor::Left)))
+            .column_spans(vec![ItemSpan::new(0, '_', Anchor::Left)])
+            .build()
+            .is_ok());
+        assert!(OutFormatBuilder::default()
+            .item_separator(String::new())
+            .line_separator(Some(LineSeparator::new(1, "\n".to_string())))
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn rows() {
+        let rows = vec![vec!["a", "b", "c"], vec!["d"], vec![]];
+        let format = |fill_last_line, overflow| {
+            OutFormatBuilder::default()
+                .span(Some(
+                    ItemSpanBuilder::default()
+                        .span(2)
+                        .pad('_')
+                        .overflow(overflow)
+                        .build()
+                        .unwrap(),
+                ))
+                .item_separator("|".to_string())
+                .line_separator(Some(LineSeparator::new(2, ";".to_string())))
+                .fill_last_line(fill_last_line)
+                .build()
+                .unwrap()
+        };
+        let mut output = Vec::new();
+        write_rows(&rows, &mut output, format(false, OverflowPolicy::Overflow)).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a_|b_;c_;d_");
+
+        let mut output = Vec::new();
+        write_rows(&rows, &mut output, format(true, OverflowPolicy::Overflow)).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a_|b_;c_|__;d_|__");
+
+        let rows = [["abc", "d"], ["e", "f"]];
+        let mut output = Vec::new();
+        write_rows(rows, &mut output, format(false, OverflowPolicy::Wrap)).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "ab|d_\nc_|__;e_|f_");
+    }
+
+    #[test]
+    fn write_collections() {
+        let format = OutFormatBuilder::default()
+            .item_separator("|".to_string())
+            .build()
+            .unwrap();
+        let strings = vec!["a".to_string(), "bb".to_string()];
+        let mut outputs = [Vec::new(), Vec::new(), Vec::new(), Vec::new()];
+        write(["a", "bb"], &mut outputs[0], format.clone()).unwrap();
+        write(&strings, &mut outputs[1], format.clone()).unwrap();
+        write(&strings[..], &mut outputs[2], format.clone()).unwrap();
+        write(strings.clone(), &mut outputs[3], format.clone()).unwrap();
+        for output in outputs {
+            assert_eq!(String::from_utf8(output).unwrap(), "a|bb");
+        }
+
+        let mut output = Vec::new();
+        write_aligned(strings, &mut output, format).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a |bb");
+    }
+
+    #[test]
+    fn template() {
+        let template = |source| Some(Template::new(source).unwrap());
+        let format = OutFormatBuilder::default()
+            .template(template("{line}.{column}={item:*>2}"))
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .fill_order(FillOrder::ColumnMajor)
+            .header(vec!["h".to_string()])
+            .item_prefix("(".to_string())
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        write(["a", "b", "c"].into_iter(), &mut output, format).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "(h\n(0.0=*a (0.1=*c\n(1.0=*b"
+        );
+
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(1, '_', Anchor::Right)))
+            .template(template("{{{index:<2}}}{item}"))
+            .item_separator("|".to_string())
+            .build()
+            .unwrap();
+        let input = (0..11)
+            .map(|index| "x".repeat(index % 2 + 1))
+            .collect::<Vec<_>>();
+        let mut output = Vec::new();
+        write_aligned(input.iter().map(String::as_str), &mut output, format).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("_{0 }x|{1 }xx|"));
+        assert!(output.ends_with("|{9 }xx|_{10}x"));
+
+        let error = |source| Template::new(source).unwrap_err().to_string();
+        assert_eq!(error("{item"), "invalid template: unterminated field");
+        assert_eq!(error("item}"), "invalid template: unmatched '}'");
+        assert_eq!(
+            error("{count}"),
+            "invalid template: unknown variable 'count'"
+        );
+        assert_eq!(
+            error("{item:>x}"),
+            "invalid template: invalid field spec '>x'"
+        );
+        let template = Template::new("{item:é^3}").unwrap();
+        assert_eq!(
+            template.render("€", 0, Position::new(0, 0), Width::Chars),
+            "é€é"
+        );
+        assert_eq!(template.to_string(), "{item:é^3}");
+
+        let format: OutFormat = "out:template='{index}: {item}'".parse().unwrap();
+        assert_eq!(format.to_string(), "out:sep=' ',template='{index}: {item}'");
+    }
+
+    #[test]
+    fn spec() {
+        let spec = "out:sep=' | ',span=5,pad='.',anchor=decimal,point=',',fraction=1,\
+                    overflow=truncate,ellipsis='…',width=20,line='\\n',page=2,page_sep='\\u{c}',\
+                    prefix='[',item_suffix='\\'',case=upper,fill=column,quoting=always,tabs,bidi";
+        let format: OutFormat = spec.parse().unwrap();
+        assert_eq!(format.item_separator, " | ");
+        assert_eq!(
+            format.span.as_ref().unwrap().anchor,
+            Anchor::Decimal {
+                point: ',',
+                fraction: 1
+            }
+        );
+        assert_eq!(format.item_suffix, "'");
+        assert_eq!(format.tab_stops, Some(TabStops::Tabs));
+        assert_eq!(format.to_string(), spec);
+        assert_eq!(
+            format.to_string().parse::<OutFormat>().unwrap().to_string(),
+            spec
+        );
+
+        let format: OutFormat = "in:sep=',';span=3,anchor=center".parse().unwrap();
+        assert_eq!(format.to_string(), "out:sep=' ',span=3,anchor=center");
+
+        let error = |spec: &str| spec.parse::<OutFormat>().unwrap_err().to_string();
+        assert_eq!(
+            error("pad='_'"),
+            "invalid format spec: span settings require 'span'"
+        );
+        for spec in [
+            "out:overflow=truncate",
+            "odd_pad=left",
+            "point=','",
+            "fraction=2",
+        ] {
+            assert_eq!(
+                error(spec),
+                "invalid format spec: span settings require 'span'"
+            );
+        }
+        let format: OutFormat = "ellipsis='~'".parse().unwrap();
+        assert_eq!(format.to_string(), "out:sep=' ',ellipsis='~'");
+        assert_eq!(
+            error("span=x"),
+            "invalid format spec: invalid value for 'span'"
+        );
+        assert_eq!(error("sep='|"), "invalid format spec: unterminated quote");
+        assert_eq!(
+            error("line='\\n'"),
+            "invalid format spec: 'line' requires 'cols'"
+        );
+        assert_eq!(
+            error("color=red"),
+            "invalid format spec: unknown key 'color'"
+        );
+        assert_eq!(
+            error("all:span=1"),
+            "invalid format spec: unknown section 'all'"
+        );
+        assert_eq!(
+            error("cols=0"),
+            "invalid format: line separator has 0 items per line"
+        );
+    }
+
+    #[cfg(all(feature = "serde", feature = "regex"))]
+    #[test]
+    fn serde() {
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(3, '_', Anchor::Right)))
+            .item_separator("|".to_string())
+            .line_separator(Some(LineSeparator::new(2, ";".to_string())))
+            .styles(vec![StyleRule::new(
+                Selector::Matches(regex::Regex::new("^b").unwrap()),
+                StyleBuilder::default().bold(true).build().unwrap(),
+            )])
+            .build()
+            .unwrap();
+        let json = serde_json::to_string(&format).unwrap();
+        let format: OutFormat = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&format).unwrap(), json);
+        let mut output = Vec::new();
+        write(["a", "b", "c"].into_iter(), &mut output, format).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "__a|__\x1b[1mb\x1b[0m;__c"
+        );
+
+        let json = r#"{"line_separator":{"items_per_line":0,"line_separator":";"}}"#;
+        assert!(serde_json::from_str::<OutFormat>(json).is_err());
+        let json = r#"{"page_separator":{"lines_per_page":0,"page_separator":""}}"#;
+        assert!(serde_json::from_str::<OutFormat>(json).is_err());
+
+        let span: ItemSpan =
+            serde_json::from_str(r#"{"span":2,"pad":"-","anchor":"Left","overflow":"Truncate"}"#)
+                .unwrap();
+        let expected = ItemSpanBuilder::default()
+            .span(2)
+            .pad('-')
+            .overflow(OverflowPolicy::Truncate)
+            .build()
+            .unwrap();
+        assert_eq!(span, expected);
+        let format: OutFormat = serde_json::from_str(r#"{"ellipsis":"~"}"#).unwrap();
+        assert_eq!(format.ellipsis, "~");
+    }
+
+    #[test]
+    fn test() {
+        let input = ["001", "01", "1"];
+        let expected = "_001|__01;___1*";
+        let mut output = [0u8; 15];
+        output[14] = b'*';
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(4, '_', Anchor::Right)))
+            .item_separator("|".to_string())
+            .line_separator(Some(LineSeparator::new(2, ";".to_string())))
+            .build()
+            .unwrap();
+        write(input.into_iter(), output.as_mut_slice(), format).unwrap();
+        assert_eq!(String::from_utf8(output.to_vec()).unwrap(), expected);
+    }
+
+    #[test]
+    fn example() {
+        let input = ["😊😊", "👶", "💼💼💼"];
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(4, '👉', Anchor::Right)))
+            .item_separator("🖖".to_string())
+            .line_separator(Some(LineSeparator::new(2, "🔩\n".to_string())))
+            .build()
+            .unwrap();
+        let expected = "👉👉😊😊🖖👉👉👉👶🔩\n👉💼💼💼";
+        let mut output = vec![0u8; 100];
+        write(input.into_iter(), output.as_mut_slice(), format).unwrap();
+        let eof = output
+            .iter()
+            .position(|x| *x == 0u8)
+            .unwrap_or(output.len());
+        let output = output.split_at(eof).0;
+        assert_eq!(String::from_utf8(output.to_vec()).unwrap(), expected);
+    }
+
+    #[test]
+    fn finish() {
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(2, '_', Anchor::Right)))
+            .column_spans(vec![
+                ItemSpan::new(2, '_', Anchor::Left),
+                ItemSpan::new(3, '_', Anchor::Right),
+            ])
+            .item_separator("|".to_string())
+            .line_separator(Some(LineSeparator::new(2, ";".to_string())))
+            .build()
+            .unwrap();
+        let mut writer = ItemWriter::new(format);
+        let mut output = Vec::new();
+        for item in ["a", "b", "c"] {
+            writer.write(item, &mut output).unwrap();
+        }
+        writer.finish(&mut output).unwrap();
+        for item in ["d", "e"] {
+            writer.write(item, &mut output).unwrap();
+        }
+        writer.finish(&mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a_|__b;c_d_|__e");
+    }
+
+    #[test]
+    fn trailing_separator() {
+        let format = |line_separator| {
+            OutFormatBuilder::default()
+                .item_separator(",".to_string())
+                .line_separator(line_separator)
+                .trailing_separator(true)
+                .build()
+                .unwrap()
+        };
+        let mut output = Vec::new();
+        write(["a", "b", "c"].into_iter(), &mut output, format(None)).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a,b,c,");
+
+        let mut output = Vec::new();
+        let line_separator = Some(LineSeparator::new(2, "\n".to_string()));
+        write(["a", "b", "c", "d"], &mut output, format(line_separator)).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a,b\nc,d\n");
+
+        let mut output = Vec::new();
+        write(std::iter::empty::<&str>(), &mut output, format(None)).unwrap();
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn header() {
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(3, '_', Anchor::Right)))
+            .column_anchors(vec![Anchor::Left])
+            .item_separator("|".to_string())
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .header(vec!["id".to_string(), "n".to_string()])
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        write(["a", "1", "b", "22"], &mut output, format.clone()).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "id_|__n\na__|__1\nb__|_22"
+        );
+
+        let mut output = Vec::new();
+        write(std::iter::empty::<&str>(), &mut output, format).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "id_|__n");
+    }
+
+    #[test]
+    fn footer() {
+        let format = |header: &[&str], trailing_separator| {
+            OutFormatBuilder::default()
+                .span(Some(ItemSpan::new(3, '_', Anchor::Right)))
+                .item_separator("|".to_string())
+                .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+                .header(header.iter().map(|label| label.to_string()).collect())
+                .footer(vec!["---".to_string(), "3".to_string()])
+                .trailing_separator(trailing_separator)
+                .build()
+                .unwrap()
+        };
+        let mut output = Vec::new();
+        write(["1", "2"].into_iter(), &mut output, format(&[], false)).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "__1|__2\n---|__3");
+
+        let mut output = Vec::new();
+        write(["1"].into_iter(), &mut output, format(&["a"], true)).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "__a\n__1\n---|__3\n");
+
+        let mut output = Vec::new();
+        write(std::iter::empty::<&str>(), &mut output, format(&[], false)).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "---|__3");
+    }
+
+    #[test]
+    fn csv() {
+        let input = ["a", "b,c", "say \"hi\"", "d\ne", "f;g"];
+        let format = OutFormatBuilder::default()
+            .item_separator(",".to_string())
+            .line_separator(Some(LineSeparator::new(3, ";".to_string())))
+            .mode(OutputMode::Csv)
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "a,\"b,c\",\"say \"\"hi\"\"\";\"d\ne\",\"f;g\""
+        );
+    }
+
+    #[test]
+    fn json() {
+        let input = ["a", "b\"c", "d\\e\n", "\u{1}"];
+        let format = |line_separator| {
+            OutFormatBuilder::default()
+                .item_separator("|".to_string())
+                .line_separator(line_separator)
+                .mode(OutputMode::Json)
+                .trailing_separator(true)
+                .build()
+                .unwrap()
+        };
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format(None)).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"["a","b\"c","d\\e\n","\u0001"]"#
+        );
+
+        let mut output = Vec::new();
+        let line_separator = Some(LineSeparator::new(3, ";".to_string()));
+        write(input.into_iter(), &mut output, format(line_separator)).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"[["a","b\"c","d\\e\n"],["\u0001"]]"#
+        );
+
+        let mut output = Vec::new();
+        write(std::iter::empty::<&str>(), &mut output, format(None)).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "[]");
+    }
+
+    #[test]
+    fn json_lines() {
+        let input = ["a", "b\"", "c"];
+        let format = |header: &[&str]| {
+            OutFormatBuilder::default()
+                .line_separator(Some(LineSeparator::new(2, ";".to_string())))
+                .mode(OutputMode::JsonLines)
+                .header(header.iter().map(|label| label.to_string()).collect())
+                .build()
+                .unwrap()
+        };
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format(&[])).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[\"a\",\"b\\\"\"]\n[\"c\"]\n"
+        );
+
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format(&["k"])).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"k\":\"a\",\"1\":\"b\\\"\"}\n{\"k\":\"c\"}\n"
+        );
+
+        let mut output = Vec::new();
+        write(std::iter::empty::<&str>(), &mut output, format(&["k"])).unwrap();
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn column_major() {
+        let format = OutFormatBuilder::default()
+            .column_spans(vec![
+                ItemSpan::new(2, '_', Anchor::Left),
+                ItemSpan::new(3, '_', Anchor::Left),
+            ])
+            .item_separator("|".to_string())
+            .line_separator(Some(LineSeparator::new(3, "\n".to_string())))
+            .fill_order(FillOrder::ColumnMajor)
+            .trailing_separator(true)
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        let input = ["a", "b", "c", "d", "e", "f", "g"];
+        write(input.into_iter(), &mut output, format.clone()).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "a_|d__|g_\nb_|e__\nc_|f__\n"
+        );
+
+        let mut output = Vec::new();
+        write(["a", "b"].into_iter(), &mut output, format).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a_|b__\n");
+    }
+
+    #[test]
+    fn sink() {
+        use std::io::Write;
+        let format = OutFormatBuilder::default()
+            .item_separator("|".to_string())
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        let mut sink = ItemSink::new(&mut output, format.clone());
+        sink.write_all(b"a\r\n\nb").unwrap();
+        sink.write_all(b"b\n").unwrap();
+        drop(sink);
+        assert_eq!(String::from_utf8(output).unwrap(), "a||bb");
+
+        let mut output = Vec::new();
+        let mut sink = ItemSink::new(&mut output, format);
+        let error = sink.write_all(b"\xff\n").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn fmt() {
+        use std::io::Write;
+        let format = OutFormatBuilder::default()
+            .item_separator("|".to_string())
+            .build()
+            .unwrap();
+        assert_eq!(write_string(["a", "🖖"], format.clone()).unwrap(), "a|🖖");
+        assert_eq!(
+            write_string(std::iter::empty::<&str>(), format).unwrap(),
+            ""
+        );
+
+        let mut output = String::new();
+        let mut writer = FmtWriter::new(&mut output);
+        let bytes = "👶".as_bytes();
+        writer.write_all(&bytes[..1]).unwrap();
+        writer.write_all(&bytes[1..]).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(output, "👶");
+
+        let mut output = String::new();
+        let mut writer = FmtWriter::new(&mut output);
+        writer.write_all(&bytes[..2]).unwrap();
+        assert!(writer.finish().is_err());
+        assert!(FmtWriter::new(&mut output).write_all(b"\xff").is_err());
+    }
+
+    #[test]
+    fn reformat_header() {
+        let in_format = InFormatBuilder::default()
+            .mode(InputMode::Csv)
+            .header(true)
+            .build()
+            .unwrap();
+        let out_format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(3, '_', Anchor::Right)))
+            .item_separator("|".to_string())
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .build()
+            .unwrap();
+        let input = "id,n\na,1\nb,\"2\"\n";
+        assert_eq!(
+            reformat(input, in_format.clone(), out_format.clone()).unwrap(),
+            "_id|__n\n__a|__1\n__b|__2"
+        );
+
+        let mut output = Vec::new();
+        let error = reformat_io(
+            &b"id,n\na,\xff"[..],
+            &mut output,
+            in_format.clone(),
+            out_format.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            LineupError::Read(ReadError::InvalidUtf8 { offset: 7 })
+        ));
+
+        let in_format = InFormat {
+            invalid_utf8: InvalidUtf8::Replace,
+            ..in_format
+        };
+        let mut output = Vec::new();
+        reformat_io(
+            &b"id,n\na,\xff"[..],
+            &mut output,
+            in_format.clone(),
+            out_format.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "_id|__n\n__a|__\u{fffd}"
+        );
+
+        // code points split across reads of the source are decoded as a whole
+        let source = std::io::Read::chain(&b"id,n\na,\xc3"[..], &b"\xa9\xff,\xc3"[..]);
+        let mut output = Vec::new();
+        reformat_io(source, &mut output, in_format, out_format).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "_id|__n\n__a|_\u{e9}\u{fffd}\n__\u{fffd}"
+        );
+    }
+
+    #[test]
+    fn column_separators() {
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(2, '_', Anchor::Left)))
+            .item_separator("|".to_string())
+            .column_separators(vec![": ".to_string()])
+            .line_separator(Some(LineSeparator::new(3, "\n".to_string())))
+            .build()
+            .unwrap();
+        assert_eq!(format.items_per_line(7), 2);
+        let mut output = Vec::new();
+        write(["a", "b", "c", "d"], &mut output, format).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a_: b_|c_\nd_");
+    }
+
+    #[test]
+    fn table() {
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(1, '.', Anchor::Right)))
+            .item_separator(" ".to_string())
+            .line_separator(Some(LineSeparator::new(5, ";".to_string())))
+            .fill_last_line(true)
+            .build()
+            .unwrap();
+        let mut table = Table::new(format);
+        table
+            .column(
+                0,
+                TableColumnBuilder::default()
+                    .span(Some(4))
+                    .anchor(Some(Anchor::Left))
+                    .separator(Some("|".to_string()))
+                    .build()
+                    .unwrap(),
+            )
+            .row(["a", "bb", "c"])
+            .row(["d"])
+            .row(["eeeee", "f"]);
+        assert_eq!(table.column_count(), 3);
+        assert_eq!(table.column_widths(), [5, 2, 1]);
+        assert_eq!(table.to_string(), "a...|bb c;d...|.. .;eeeee|.f .");
+
+        let mut output = Vec::new();
+        let written = table.write(&mut output).unwrap();
+        assert_eq!(written.items, 6);
+        assert_eq!(Table::new(OutFormat::null()).to_string(), "");
+    }
+
+    #[test]
+    fn transposed() {
+        let rows = [vec![], vec!["a"], vec!["b", "c", "d"]];
+        assert_eq!(
+            transpose(rows),
+            [vec!["", "a", "b"], vec!["", "", "c"], vec!["", "", "d"]]
+        );
+        assert!(transpose(Vec::<Vec<String>>::new()).is_empty());
+        assert!(transpose([Vec::<String>::new()]).is_empty());
+    }
+
+    #[test]
+    fn written() {
+        let format = OutFormatBuilder::default()
+            .item_separator("|".to_string())
+            .header(vec!["h".to_string()])
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .fill_last_line(true)
+            .build()
+            .unwrap();
+        let mut output = vec![0u8; 16];
+        let written = write(["a", "bb", "c"], output.as_mut_slice(), format.clone()).unwrap();
+        assert_eq!(written, Written { bytes: 9, items: 3 });
+        assert_eq!(&output[..written.bytes], b"h\na|bb\nc|");
+
+        let mut output = Vec::new();
+        let mut writer = ItemWriter::new(format.clone());
+        let written = writer.write("a", &mut output).unwrap();
+        assert_eq!(written, Written { bytes: 3, items: 1 });
+        let written = writer.finish(&mut output).unwrap();
+        assert_eq!(written, Written { bytes: 1, items: 0 });
+
+        let format = OutFormat {
+            fill_order: FillOrder::ColumnMajor,
+            ..format
+        };
+        let mut output = Vec::new();
+        let mut writer = ItemWriter::new(format);
+        assert_eq!(writer.write("a", &mut output).unwrap(), Written::default());
+        let written = writer.finish(&mut output).unwrap();
+        assert_eq!(written.items, 1);
+        assert_eq!(written.bytes, output.len());
+
+        let mut output = Vec::new();
+        let written = write_rows(
+            [vec!["a"], vec!["b"]],
+            &mut output,
+            OutFormatBuilder::default().build().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            written,
+            Written {
+                bytes: output.len(),
+                items: 2
+            }
+        );
+    }
+
+    #[test]
+    fn decorations() {
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(5, '_', Anchor::Right)))
+            .item_separator(",".to_string())
+            .item_prefix("[".to_string())
+            .item_suffix("]".to_string())
+            .header(vec!["h".to_string()])
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        write(["a", "bb", "ccc"].into_iter(), &mut output, format).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "__[h]\n__[a],_[bb]\n[ccc]"
+        );
+    }
+
+    #[test]
+    fn quoting() {
+        let input = ["a", "b c", "it's", "d\\"];
+        let format = |policy, escape| {
+            OutFormatBuilder::default()
+                .quoting(Quoting::new(policy, '\'', escape))
+                .build()
+                .unwrap()
+        };
+        let mut output = Vec::new();
+        let fmt = format(QuotePolicy::Never, QuoteEscape::Doubled);
+        write(input.into_iter(), &mut output, fmt).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a b c it's d\\");
+
+        let mut output = Vec::new();
+        let fmt = format(QuotePolicy::WhenNeeded, QuoteEscape::Doubled);
+        write(input.into_iter(), &mut output, fmt).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a 'b c' 'it''s' d\\");
+
+        let mut output = Vec::new();
+        let fmt = format(QuotePolicy::Always, QuoteEscape::Backslash);
+        write(input.into_iter(), &mut output, fmt).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "'a' 'b c' 'it\\'s' 'd\\\\'"
+        );
+    }
+
+    #[test]
+    fn tab_stops() {
+        let input = ["a", "bbbb", "ccccc", "d", "e"];
+        let mut output = Vec::new();
+        let fmt = OutFormatBuilder::default()
+            .tab_stops(Some(TabStops::Spaces(4)))
+            .line_separator(Some(LineSeparator::new(3, "\n".to_string())))
+            .header(vec!["x".to_string(), "y".to_string()])
+            .build()
+            .unwrap();
+        write(input.into_iter(), &mut output, fmt).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "x   y\na   bbbb    ccccc\nd   e"
+        );
+
+        let mut output = Vec::new();
+        let fmt = OutFormatBuilder::default()
+            .tab_stops(Some(TabStops::Tabs))
+            .build()
+            .unwrap();
+        write(input.into_iter().take(2), &mut output, fmt).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a\tbbbb");
+    }
+
+    #[test]
+    fn wrap() {
+        let input = ["abcdefg", "xy", "z"];
+        let mut output = Vec::new();
+        let fmt = OutFormatBuilder::default()
+            .span(Some(
+                ItemSpanBuilder::default()
+                    .span(3)
+                    .pad('_')
+                    .overflow(OverflowPolicy::Wrap)
+                    .build()
+                    .unwrap(),
+            ))
+            .item_separator("|".to_string())
+            .line_separator(Some(LineSeparator::new(2, ";\n".to_string())))
+            .header(vec!["head".to_string()])
+            .build()
+            .unwrap();
+        write(input.into_iter(), &mut output, fmt).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "hea\nd__;\nabc|xy_\ndef|___\ng__|___;\nz__"
+        );
+    }
+
+    #[test]
+    fn fit_line() {
+        let input = ["a", "bb", "c", "dd", "e"];
+        let mut output = Vec::new();
+        let mut fmt = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(2, '_', Anchor::Left)))
+            .column_spans(vec![
+                ItemSpan::new(1, '_', Anchor::Left),
+                ItemSpan::new(2, '_', Anchor::Left),
+            ])
+            .item_separator("|".to_string())
+            .build()
+            .unwrap();
+        assert_eq!(fmt.items_per_line(0), 1);
+        assert_eq!(fmt.items_per_line(6), 3);
+        fmt.fit_line(7);
+        write(input.into_iter(), &mut output, fmt).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a|bb|c\ndd|e_");
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn styles() {
+        let input = ["-1", "2", "3", "-4"];
+        let mut output = Vec::new();
+        let red = StyleBuilder::default()
+            .foreground(Some(Color::Red))
+            .build()
+            .unwrap();
+        let bold = StyleBuilder::default()
+            .bold(true)
+            .background(Some(Color::Blue))
+            .build()
+            .unwrap();
+        let fmt = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(3, ' ', Anchor::Right)))
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .styles(vec![
+                StyleRule::new(Selector::Matches(regex::Regex::new("^-").unwrap()), red),
+                StyleRule::new(Selector::Column(1), bold),
+            ])
+            .build()
+            .unwrap();
+        write(input.into_iter(), &mut output, fmt).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            " \x1b[31m-1\x1b[0m   \x1b[1;44m2\x1b[0m\n  3  \x1b[31m-4\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn bidi_isolate() {
+        let input = ["שלום", "", "ab"];
+        let mut output = Vec::new();
+        let fmt = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(5, ' ', Anchor::Right)))
+            .bidi_isolate(true)
+            .build()
+            .unwrap();
+        write(input.into_iter(), &mut output, fmt).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            " \u{2068}שלום\u{2069}          \u{2068}ab\u{2069}"
+        );
+    }
+
+    #[test]
+    fn case() {
+        let input = ["hello wORLD", "snake_case", "ß"];
+        let format = |case| {
+            OutFormatBuilder::default()
+                .case(case)
+                .item_separator("|".to_string())
+                .item_prefix("x".to_string())
+                .build()
+                .unwrap()
+        };
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format(Case::Upper)).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "xHELLO WORLD|xSNAKE_CASE|xSS"
+        );
+
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format(Case::Lower)).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "xhello world|xsnake_case|xß"
+        );
+
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format(Case::Title)).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "xHello World|xSnake_Case|xSS"
+        );
+    }
+
+    #[test]
+    fn transform() {
+        let input = ["a", "b", "c"];
+        let fmt = OutFormatBuilder::default()
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .fill_order(FillOrder::ColumnMajor)
+            .case(Case::Upper)
+            .build()
+            .unwrap();
+        let mut writer = ItemWriter::new(fmt).with_transform(|item| Cow::Owned(item.repeat(2)));
+        let mut output = Vec::new();
+        for item in input {
+            writer.write(item, &mut output).unwrap();
+        }
+        writer.finish(&mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "AA CC\nBB");
+    }
+
+    #[test]
+    fn formatter() {
+        let input = ["1", "-4", "2", "-3", "x"];
+        let fmt = OutFormatBuilder::default()
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .fill_order(FillOrder::ColumnMajor)
+            .build()
+            .unwrap();
+        let bold = StyleBuilder::default().bold(true).build().unwrap();
+        let mut writer = ItemWriter::new(fmt).with_formatter(move |item, position| {
+            match (position.line, position.column) {
+                (_, 0) => ItemOverride {
+                    span: Some(2),
+                    pad: Some('0'),
+                    anchor: Some(Anchor::Right),
+                    style: item.starts_with('-').then_some(bold),
+                },
+                (line, _) => ItemOverride {
+                    span: Some(line + 1),
+                    pad: Some('.'),
+                    ..Default::default()
+                },
+            }
+        });
+        let mut output = Vec::new();
+        for item in input {
+            writer.write(item, &mut output).unwrap();
+        }
+        writer.finish(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "01 -3\n\x1b[1m-4\x1b[0m x.\n02"
+        );
+    }
+
+    #[test]
+    fn max_width() {
+        let input = ["aaaaaaa", "b", "cc", "d", "e"];
+        let mut output = Vec::new();
+        let fmt = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(2, '_', Anchor::Left)))
+            .item_separator("|".to_string())
+            .line_separator(Some(LineSeparator::by_width(6, "\n".to_string())))
+            .build()
+            .unwrap();
+        write(input.into_iter(), &mut output, fmt).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "aaaaaaa\nb_|cc\nd_|e_");
+
+        let mut output = Vec::new();
+        let fmt = OutFormatBuilder::default()
+            .span(Some(
+                ItemSpanBuilder::default()
+                    .span(3)
+                    .overflow(OverflowPolicy::Wrap)
+                    .build()
+                    .unwrap(),
+            ))
+            .line_separator(Some(LineSeparator::by_width(8, "\n".to_string())))
+            .build()
+            .unwrap();
+        write(input.into_iter(), &mut output, fmt).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "aaa b  \naaa    \na      \ncc  d  \ne  "
+        );
+    }
+
+    #[test]
+    fn page_separator() {
+        let input = ["a", "b", "c", "d", "e"];
+        let mut output = Vec::new();
+        let fmt = OutFormatBuilder::default()
+            .line_separator(Some(LineSeparator::new(1, "\n".to_string())))
+            .page_separator(Some(PageSeparator::new(2, "\u{c}".to_string())))
+            .header(vec!["h".to_string()])
+            .trailing_separator(true)
+            .build()
+            .unwrap();
+        write(input.into_iter(), &mut output, fmt).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "h\na\nb\n\u{c}c\nd\n\u{c}e\n"
+        );
+    }
+
+    #[test]
+    fn line_prefix() {
+        let input = ["a", "bbbb", "c"];
+        let mut output = Vec::new();
+        let fmt = OutFormatBuilder::default()
+            .span(Some(
+                ItemSpanBuilder::default()
+                    .span(2)
+                    .overflow(OverflowPolicy::Wrap)
+                    .build()
+                    .unwrap(),
+            ))
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .line_prefix("> ".to_string())
+            .header(vec!["h".to_string()])
+            .footer(vec!["f".to_string()])
+            .trailing_separator(true)
+            .build()
+            .unwrap();
+        write(input.into_iter(), &mut output, fmt).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "> h \n> a  bb\n>    bb\n> c \n> f \n"
+        );
+    }
+
+    #[test]
+    fn line_suffix() {
+        let input = ["a", "bbb", "c"];
+        let mut output = Vec::new();
+        let fmt = OutFormatBuilder::default()
+            .span(Some(
+                ItemSpanBuilder::default()
+                    .span(2)
+                    .overflow(OverflowPolicy::Wrap)
+                    .build()
+                    .unwrap(),
+            ))
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .line_suffix(" \\".to_string())
+            .header(vec!["h".to_string()])
+            .build()
+            .unwrap();
+        write(input.into_iter(), &mut output, fmt).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "h  \\\na  bb \\\n   b  \\\nc "
+        );
+    }
+
+    #[test]
+    fn fill_last_line() {
+        let input = ["a", "b", "c", "d", "e"];
+        let format = |fill_order| {
+            OutFormatBuilder::default()
+                .span(Some(ItemSpan::new(2, '_', Anchor::Left)))
+                .item_separator("|".to_string())
+                .line_separator(Some(LineSeparator::new(3, "\n".to_string())))
+                .fill_order(fill_order)
+                .fill_last_line(true)
+                .footer(vec!["f".to_string()])
+                .build()
+                .unwrap()
+        };
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format(FillOrder::RowMajor)).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a_|b_|c_\nd_|e_|__\nf_");
+
+        let mut output = Vec::new();
+        write(input, &mut output, format(FillOrder::ColumnMajor)).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a_|c_|e_\nb_|d_|__\nf_");
+    }
+
+    #[test]
+    fn number() {
+        let input = ["12345", "-0.5", "1e3", "+7.", ".25", "12a", "1e", "-", "x"];
+        let format = |number| {
+            OutFormatBuilder::default()
+                .number(Some(number))
+                .item_separator("|".to_string())
+                .build()
+                .unwrap()
+        };
+        let mut output = Vec::new();
+        let number = NumberFormatBuilder::default()
+            .grouping(Some('_'))
+            .build()
+            .unwrap();
+        write(input.into_iter(), &mut output, format(number)).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "12_345|-0.5|1_000|+7.|.25|12a|1e|-|x"
+        );
+
+        let mut output = Vec::new();
+        let number = NumberFormatBuilder::default()
+            .precision(Some(1))
+            .notation(Notation::Scientific)
+            .build()
+            .unwrap();
+        write(input.into_iter().take(5), &mut output, format(number)).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "1.2e4|-5.0e-1|1.0e3|7.0e0|2.5e-1"
+        );
+
+        let mut output = Vec::new();
+        let number = NumberFormatBuilder::default()
+            .grouping(Some('.'))
+            .decimal_point(',')
+            .build()
+            .unwrap();
+        write(input.into_iter().take(2), &mut output, format(number)).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "12.345|-0,5");
+    }
+
+    #[test]
+    fn number_exact() {
+        let write_number = |input: &[&str], number| {
+            let format = OutFormatBuilder::default()
+                .number(Some(number))
+                .item_separator("|".to_string())
+                .build()
+                .unwrap();
+            let mut output = Vec::new();
+            write(input.iter(), &mut output, format).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        let overflow = [
+            "1e400",
+            "-1e-400",
+            "1e99999999999999999999",
+            "1.5e-3",
+            "0e500",
+        ];
+        let number = NumberFormatBuilder::default()
+            .grouping(Some(','))
+            .build()
+            .unwrap();
+        assert_eq!(
+            write_number(&overflow, number),
+            "1e400|-1e-400|1e99999999999999999999|0.0015|0"
+        );
+        let expanded = write_number(&["1e-300"], number);
+        assert_eq!(expanded.len(), 302);
+        assert!(expanded.starts_with("0.000") && expanded.ends_with("0001"));
+
+        let large = [
+            "12345678901234567890",
+            "-99999999999999999999.995",
+            "0.0049",
+        ];
+        let number = NumberFormatBuilder::default()
+            .precision(Some(2))
+            .build()
+            .unwrap();
+        assert_eq!(
+            write_number(&large, number),
+            "12345678901234567890.00|-100000000000000000000.00|0.00"
+        );
+        let number = NumberFormatBuilder::default()
+            .notation(Notation::Scientific)
+            .build()
+            .unwrap();
+        assert_eq!(
+            write_number(&large, number),
+            "1.234567890123456789e19|-9.9999999999999999999995e19|4.9e-3"
+        );
+        let number = NumberFormatBuilder::default()
+            .precision(Some(2))
+            .notation(Notation::Scientific)
+            .build()
+            .unwrap();
+        assert_eq!(write_number(&large, number), "1.23e19|-1.00e20|4.90e-3");
+    }
+
+    #[cfg(feature = "locale")]
+    #[test]
+    fn locale() {
+        let number = |name| {
+            NumberFormatBuilder::default()
+                .locale(name)
+                .unwrap()
+                .precision(Some(1))
+                .build()
+                .unwrap()
+        };
+        let format = |number| {
+            OutFormatBuilder::default()
+                .number(Some(number))
+                .build()
+                .unwrap()
+        };
+        let mut output = Vec::new();
+        write(["-1234.56"].into_iter(), &mut output, format(number("de"))).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "-1.234,6");
+
+        let mut output = Vec::new();
+        write(["-1234.56"], &mut output, format(number("en_US"))).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "-1,234.6");
+
+        assert!(NumberFormatBuilder::default().locale("xx").is_err());
+    }
+
+    #[test]
+    fn center() {
+        let input = ["a", "bb", "ccc"];
+        let mut output = Vec::new();
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(6, '_', Anchor::Center(Side::Right))))
+            .item_separator("|".to_string())
+            .build()
+            .unwrap();
+        write(input.into_iter(), &mut output, format).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "__a___|__bb__|_ccc__");
+
+        let mut output = Vec::new();
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(6, '_', Anchor::Center(Side::Left))))
+            .item_separator("|".to_string())
+            .build()
+            .unwrap();
+        write(input.into_iter(), &mut output, format).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "___a__|__bb__|__ccc_");
+    }
+
+    #[test]
+    fn decimal() {
+        let input = ["3.14", "42", "1.5", "-10.125", "0,5"];
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(8, '_', Anchor::decimal(3))))
+            .item_separator("|".to_string())
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "___3.14_|______42|___1.5__|_-10.125|_____0,5"
+        );
+
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(
+                6,
+                '_',
+                Anchor::Decimal {
+                    point: ',',
+                    fraction: 2,
+                },
+            )))
+            .item_separator("|".to_string())
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        write(["0,5", "12,25"].into_iter(), &mut output, format).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "__0,5_|_12,25");
+    }
+
+    #[test]
+    fn column_spans() {
+        let input = ["a", "b", "c", "d", "e", "f"];
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(2, '_', Anchor::Left)))
+            .column_spans(vec![
+                ItemSpan::new(3, '_', Anchor::Left),
+                ItemSpan::new(2, '_', Anchor::Right),
+            ])
+            .item_separator("|".to_string())
+            .line_separator(Some(LineSeparator::new(3, "\n".to_string())))
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a__|_b|c__\nd__|_e|f__");
+    }
+
+    #[test]
+    fn column_anchors() {
+        let input = ["a", "b", "c", "d", "e", "f"];
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(3, '_', Anchor::Right)))
+            .column_anchors(vec![Anchor::Left, Anchor::Center(Side::Right)])
+            .item_separator("|".to_string())
+            .line_separator(Some(LineSeparator::new(3, "\n".to_string())))
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "a__|_b_|__c\nd__|_e_|__f"
+        );
+    }
+
+    #[test]
+    fn column_pads() {
+        let input = ["1", "a", "b", "22", "c", "d"];
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(3, '_', Anchor::Right)))
+            .column_spans(vec![
+                ItemSpan::new(4, ' ', Anchor::Right),
+                ItemSpan::new(2, ' ', Anchor::Left),
+            ])
+            .column_anchors(vec![Anchor::Right, Anchor::Right])
+            .column_pads(vec!['0', '.'])
+            .item_separator("|".to_string())
+            .line_separator(Some(LineSeparator::new(3, "\n".to_string())))
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "0001|.a|   b\n0022|.c|   d"
+        );
+    }
+
+    #[cfg(feature = "unicode-width")]
+    #[test]
+    fn cells() {
+        let input = ["日本", "a", "語😊x"];
+        let format = |width| {
+            OutFormatBuilder::default()
+                .span(Some(
+                    ItemSpanBuilder::default()
+                        .span(4)
+                        .pad('_')
+                        .overflow(OverflowPolicy::Truncate)
+                        .build()
+                        .unwrap(),
+                ))
+                .item_separator("|".to_string())
+                .width(width)
+                .build()
+                .unwrap()
+        };
+
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format(Width::Chars)).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "日本__|a___|語😊x_");
+
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format(Width::Cells)).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "日本|a___|語😊");
+    }
+
+    #[cfg(feature = "unicode-segmentation")]
+    #[test]
+    fn graphemes() {
+        let input = ["e\u{301}", "👩\u{200d}👩\u{200d}👦x", "abc"];
+        let format = OutFormatBuilder::default()
+            .span(Some(
+                ItemSpanBuilder::default()
+                    .span(2)
+                    .pad('_')
+                    .overflow(OverflowPolicy::Truncate)
+                    .build()
+                    .unwrap(),
+            ))
+            .item_separator("|".to_string())
+            .width(Width::Graphemes)
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "e\u{301}_|👩\u{200d}👩\u{200d}👦x|ab"
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let input = ["ab", "abcd", "abcdef"];
+        let format = |anchor, overflow| {
+            OutFormatBuilder::default()
+                .span(Some(
+                    ItemSpanBuilder::default()
+                        .span(4)
+                        .pad('_')
+                        .anchor(anchor)
+                        .overflow(overflow)
+                        .build()
+                        .unwrap(),
+                ))
+                .item_separator("|".to_string())
+                .build()
+                .unwrap()
+        };
+
+        let mut output = Vec::new();
+        let fmt = format(Anchor::Left, OverflowPolicy::Overflow);
+        write(input.into_iter(), &mut output, fmt).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "ab__|abcd|abcdef");
+
+        let mut output = Vec::new();
+        let fmt = format(Anchor::Left, OverflowPolicy::Truncate);
+        write(input.into_iter(), &mut output, fmt).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "ab__|abcd|abcd");
+
+        let mut output = Vec::new();
+        let fmt = format(Anchor::Right, OverflowPolicy::Truncate);
+        write(input.into_iter(), &mut output, fmt).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "__ab|abcd|cdef");
+
+        let mut output = Vec::new();
+        let fmt = format(Anchor::Left, OverflowPolicy::Error);
+        let err = write(input.into_iter(), &mut output, fmt).unwrap_err();
+        assert!(matches!(
+            err,
+            LineupError::Overflow {
+                width: 6,
+                span: 4,
+                ..
+            }
+        ));
+        assert_eq!(String::from_utf8(output).unwrap(), "ab__|abcd");
+    }
+
+    #[test]
+    fn ellipsis() {
+        let input = ["ab", "abcd", "abcdef"];
+        let format = |anchor, ellipsis: &str| {
+            OutFormatBuilder::default()
+                .span(Some(
+                    ItemSpanBuilder::default()
+                        .span(4)
+                        .pad('_')
+                        .anchor(anchor)
+                        .overflow(OverflowPolicy::Truncate)
+                        .build()
+                        .unwrap(),
+                ))
+                .ellipsis(ellipsis.to_string())
+                .item_separator("|".to_string())
+                .build()
+                .unwrap()
+        };
+
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format(Anchor::Left, "…")).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "ab__|abcd|abc…");
+
+        let mut output = Vec::new();
+        write(input.into_iter(), &mut output, format(Anchor::Right, "..")).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "__ab|abcd|..ef");
+
+        let mut output = Vec::new();
+        write(input, &mut output, format(Anchor::Left, "......")).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "ab__|abcd|....");
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_writer() {
+        let input = ["a", "bb", "ccc", "d", "ee"];
+        let format = || {
+            OutFormatBuilder::default()
+                .span(Some(ItemSpan::new(3, '_', Anchor::Right)))
+                .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+                .fill_order(FillOrder::ColumnMajor)
+                .header(vec!["h1".to_string(), "h2".to_string()])
+                .build()
+                .unwrap()
+        };
+        let mut expected = Vec::new();
+        write(input.into_iter(), &mut expected, format()).unwrap();
+
+        let mut output = Vec::new();
+        write_async(input.into_iter(), &mut output, format())
+            .await
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            String::from_utf8(expected).unwrap()
+        );
+
+        let mut writer = AsyncItemWriter::from(
+            ItemWriter::new(format()).with_transform(|item| Cow::Owned(item.to_uppercase())),
+        );
+        let mut output = Vec::new();
+        for item in input {
+            writer.write(item, &mut output).await.unwrap();
+        }
+        writer.finish(&mut output).await.unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "_h1 _h2\n__A __D\n_BB _EE\nCCC"
+        );
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::useless_conversion)]
+mod write_aligned_test {
+    use super::*;
+
+    #[test]
+    fn detect_anchors() {
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(1, '_', Anchor::Left)))
+            .column_anchors(vec![Anchor::Center(Side::Right)])
+            .detect_anchors(true)
+            .item_separator("|".to_string())
+            .line_separator(Some(LineSeparator::new(4, ";".to_string())))
+            .header(vec!["id".to_string(), "n".to_string(), "x".to_string()])
+            .build()
+            .unwrap();
+        let input = ["1", "-1.5", "a", "", "333", "2e3", " 7 ", "b"];
+        let mut output = Vec::new();
+        write_aligned(input.into_iter(), &mut output, format).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "_id_|___n|x___;_1__|-1.5|a___|____;333_|_2e3| 7 _|b___"
+        );
+
+        let mut table = Table::new(
+            OutFormatBuilder::default()
+                .detect_anchors(true)
+                .build()
+                .unwrap(),
+        );
+        table.row(["a", "1"]).row(["bb", "10"]);
+        assert_eq!(table.to_string(), "a   1\nbb 10");
+    }
+
+    #[test]
+    fn no_span() {
+        let input = ["a", "bbbb", "cc"];
+        let format = OutFormatBuilder::default()
+            .item_separator("|".to_string())
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        write_aligned(input.into_iter(), &mut output, format).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a   |bbbb|cc  ");
+    }
+
+    #[test]
+    fn owned_items() {
+        let input: Vec<Cow<str>> = vec![Cow::Borrowed("a"), Cow::Owned("bbb".to_string())];
+        let format = OutFormatBuilder::default()
+            .item_separator("|".to_string())
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        write_aligned(input.iter(), &mut output, format).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a  |bbb");
+    }
+
+    #[test]
+    fn column_spans() {
+        let input = ["a", "bbb", "cc", "dddd", "e"];
+        let format = OutFormatBuilder::default()
+            .column_spans(vec![
+                ItemSpan::new(0, '_', Anchor::Left),
+                ItemSpan::new(0, '_', Anchor::Right),
+            ])
+            .item_separator("|".to_string())
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        write_aligned(input.into_iter(), &mut output, format).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "a_|_bbb\ncc|dddd\ne_");
+    }
+
+    #[test]
+    fn header() {
+        let input = ["a", "1", "bb", "22"];
+        let format = OutFormatBuilder::default()
+            .column_spans(vec![
+                ItemSpan::new(0, '_', Anchor::Left),
+                ItemSpan::new(0, '_', Anchor::Right),
+            ])
+            .item_separator("|".to_string())
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .header(vec!["name".to_string(), "n".to_string()])
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        write_aligned(input.into_iter(), &mut output, format).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "name|_n\na___|_1\nbb__|22"
+        );
+    }
+
+    #[test]
+    fn rendered() {
+        let input = ["a", "ß"];
+        let format = OutFormatBuilder::default()
+            .span(Some(ItemSpan::new(1, '_', Anchor::Left)))
+            .case(Case::Upper)
+            .item_prefix("<".to_string())
+            .item_separator("|".to_string())
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        write_aligned(input.into_iter(), &mut output, format).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "<A_|<SS");
+    }
+
+    #[test]
+    fn column_major() {
+        let input = ["a", "bbb", "cc", "d", "eeee"];
+        let format = OutFormatBuilder::default()
+            .column_spans(vec![ItemSpan::new(0, '_', Anchor::Left); 2])
+            .item_separator("|".to_string())
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .fill_order(FillOrder::ColumnMajor)
+            .build()
+            .unwrap();
+        let mut output = Vec::new();
+        write_aligned(input, &mut output, format).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "a__|d___\nbbb|eeee\ncc_"
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let format = OutFormatBuilder::default().build().unwrap();
+        let mut output = Vec::new();
+        write_aligned(std::iter::empty::<&str>(), &mut output, format).unwrap();
+        assert!(output.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod read_test {
+    use super::*;
+
+    #[test]
+    fn pipeline() {
+        let pipeline = Pipeline::default()
+            .map(|item| Cow::Owned(format!(" {item} ")))
+            .trim()
+            .skip(1)
+            .take(2);
+        let items: Vec<_> = pipeline.apply((0..).map(|n| n.to_string())).collect();
+        assert_eq!(items, ["1", "2"]);
+
+        let pipeline = Pipeline::default().take(0);
+        assert_eq!(pipeline.apply(["a"]).next(), None);
+
+        let items: Vec<_> = Pipeline::default().trim().apply([" a", "b "]).collect();
+        assert!(matches!(items[0], Cow::Borrowed("a")));
+
+        for stage in ["trim", "skip=2", "take=0"] {
+            assert_eq!(stage.parse::<Stage>().unwrap().to_string(), stage);
+        }
+        for stage in ["trim=1", "skip", "take=-1", "map"] {
+            assert!(matches!(stage.parse::<Stage>(), Err(LineupError::Stage(_))));
+        }
+    }
+
+    #[test]
+    fn pipeline_sort() {
+        let pipeline: Pipeline = ["take=5", "sort=numeric", "skip=1", "sort=reverse", "take=2"]
+            .iter()
+            .map(|stage| stage.parse::<Stage>().unwrap())
+            .collect();
+        let input = ["3", "b", "1.5", "-2", "a", "0"];
+        let items: Vec<_> = pipeline.apply(input).collect();
+        assert_eq!(items, ["b", "a"]);
+
+        let pipeline = Pipeline::default()
+            .sort(Sort::default())
+            .take(1)
+            .sort(Sort::default());
+        assert_eq!(pipeline.apply(["b", "a"]).collect::<Vec<_>>(), ["a"]);
+
+        let sort = SortBuilder::default()
+            .column(Some(1))
+            .row_length(2)
+            .build()
+            .unwrap();
+        let mut items = vec!["a", "2", "b", "1", "c"];
+        sort.apply(&mut items);
+        assert_eq!(items, ["c", "b", "1", "a", "2"]);
+
+        assert_eq!("sort".parse::<Stage>().unwrap().to_string(), "sort");
+        let stage = "sort=numeric,reverse,column=0,row=3";
+        assert_eq!(stage.parse::<Stage>().unwrap().to_string(), stage);
+        assert!("sort=column".parse::<Stage>().is_err());
+    }
+
+    #[test]
+    fn stats() {
+        let fmt = InFormatBuilder::default()
+            .quote(Some('"'))
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .build()
+            .unwrap();
+        let stats = super::stats("\"a,b\",c\nddd,e\nf", fmt.clone(), Width::Chars).unwrap();
+        assert_eq!((stats.items, stats.lines), (5, 3));
+        assert_eq!((stats.min_width, stats.max_width), (1, 3));
+        assert_eq!(stats.histogram, [(1, 3), (3, 2)].into());
+
+        let (first, second) = ("ab,c\nddd,e\n", "f,gggg");
+        let first_stats = super::stats(first, fmt.clone(), Width::Chars).unwrap();
+        let second_stats = super::stats(second, fmt.clone(), Width::Chars).unwrap();
+        let total = super::stats(&format!("{first}{second}"), fmt.clone(), Width::Chars);
+        assert_eq!(first_stats + second_stats, total.unwrap());
+
+        let stats = super::stats("", fmt, Width::Chars).unwrap();
+        assert_eq!(stats, Stats::default());
+        assert_eq!(
+            stats.to_string(),
+            "items: 0\nlines: 0\nwidth: min 0, max 0, mean 0.00\n"
+        );
+    }
+
+    #[test]
+    fn separator_search() {
+        let fmt = InFormatBuilder::default()
+            .item_separator(ItemSeparator::Explicit("::".to_string()))
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .build()
+            .unwrap();
+        let items: Vec<_> = read("a::b\nc::d\né::f:\ng", fmt.clone()).collect();
+        assert_eq!(items, ["a", "b", "c", "d", "é", "f:", "g"]);
+
+        let mut reader = ItemReader::new("a;b--c;d|e", fmt);
+        for (separator, item) in [(";", "a"), ("--", "b"), (";", "c"), ("|", "d"), (";", "e")] {
+            let separator = ItemSeparator::Explicit(separator.to_string());
+            assert_eq!(reader.try_next_item(&separator), Ok(Some(item)));
+        }
+        assert!(reader.finders.len() <= 2);
+
+        let fmt = InFormatBuilder::default()
+            .mode(InputMode::Csv)
+            .build()
+            .unwrap();
+        let items: Vec<_> = read("a,\"b\nc\"\r\nd", fmt).collect();
+        assert_eq!(items, ["a", "\"b\nc\"", "d"]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn read_parallel() {
+        let inputs = [
+            "",
+            "a",
+            "a,b,c,d,e,f,g,h,i,j",
+            "a,b,c,,d,e,f,g,h,i",
+            ",a,b,c,d,e,f,g,h",
+            "aa,bb,cc,dd,ee,ff,gg,",
+            "aé,bé,cé,dé,eé,fé,,",
+        ];
+        for empty_items in [EmptyItems::Terminate, EmptyItems::Skip, EmptyItems::Yield] {
+            for separator in [",", ",,", "bc"] {
+                let fmt = InFormatBuilder::default()
+                    .item_separator(ItemSeparator::Explicit(separator.to_string()))
+                    .empty_items(empty_items)
+                    .build()
+                    .unwrap();
+                for input in inputs {
+                    let expected: Vec<_> = read(input, fmt.clone()).collect();
+                    for jobs in 1..8 {
+                        let items = super::read_parallel(input, &fmt, jobs).unwrap();
+                        assert_eq!(items, expected, "{input:?} split on {separator:?}");
+                    }
+                }
+            }
+        }
+
+        let fmt = InFormatBuilder::default()
+            .max_item_length(Some(2))
+            .build()
+            .unwrap();
+        let error = super::read_parallel("a,b,c,d,e,f,abc,g", &fmt, 4).unwrap_err();
+        assert!(matches!(
+            error,
+            LineupError::Read(ReadError::ItemTooLong { offset: 12, .. })
+        ));
+        let items = super::read_parallel("a,b,c,,d,e,f,abc,g", &fmt, 4).unwrap();
+        assert_eq!(items, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn check() {
+        let fmt = InFormatBuilder::default()
+            .item_separator(ItemSeparator::ByteCount(2))
+            .byte_rounding(ByteRounding::Error)
+            .build()
+            .unwrap();
+        assert_eq!(super::check("abcd", fmt.clone()), Ok(2));
+        let violation = super::check("ab\nécd", fmt).unwrap_err();
+        assert_eq!(violation.error, ReadError::InvalidBoundary { offset: 4 });
+        assert_eq!((violation.line, violation.column), (2, 2));
+
+        let fmt = InFormatBuilder::default()
+            .max_item_length(Some(2))
+            .build()
+            .unwrap();
+        let violation = super::check("ab,éé,abc", fmt).unwrap_err();
+        assert_eq!((violation.line, violation.column), (1, 7));
+
+        let fmt = InFormatBuilder::default()
+            .expected_items(Some(3))
+            .build()
+            .unwrap();
+        let violation = super::check("a,b\nc", fmt).unwrap_err();
+        assert_eq!(violation.error.offset(), None);
+        assert_eq!((violation.line, violation.column), (2, 2));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn pipeline_regex() {
+        let pipeline: Pipeline = ["keep=^[a-z]+$", "discard=b"]
+            .iter()
+            .map(|stage| stage.parse::<Stage>().unwrap())
+            .collect();
+        let items: Vec<_> = pipeline.apply(["a", "B", "b", "c1", "cc"]).collect();
+        assert_eq!(items, ["a", "cc"]);
+
+        // as from repeated filters, items having to match all of them
+        let regex = |pattern| regex::Regex::new(pattern).unwrap();
+        let pipeline: Pipeline = [
+            Stage::Keep(regex("a")),
+            Stage::Keep(regex("b")),
+            Stage::Discard(regex("c")),
+        ]
+        .into_iter()
+        .collect();
+        let items: Vec<_> = pipeline.apply(["ab", "a", "b", "abc", "ba"]).collect();
+        assert_eq!(items, ["ab", "ba"]);
+        assert_eq!("keep=a=b".parse::<Stage>().unwrap().to_string(), "keep=a=b");
+        assert!("discard=(".parse::<Stage>().is_err());
+    }
+
+    #[test]
+    fn invalid_format() {
+        let fmt = |item_separator, line_separator| {
+            InFormatBuilder::default()
+                .item_separator(item_separator)
+                .line_separator(line_separator)
+                .build()
+        };
+        let error = fmt(ItemSeparator::Explicit(String::new()), None).unwrap_err();
+        assert!(matches!(error, LineupError::Validation(_)));
+        assert!(fmt(ItemSeparator::ByteCount(0), None).is_err());
+        assert!(fmt(ItemSeparator::CharCount(0), None).is_err());
+        assert!(fmt(ItemSeparator::FieldWidths(Vec::new()), None).is_err());
+        let line_separator = LineSeparator::new(0, "\n".to_string());
+        assert!(fmt(ItemSeparator::default(), Some(line_separator)).is_err());
+        let line_separator = LineSeparator::by_length(RecordLength::Chars(0), String::new());
+        assert!(fmt(ItemSeparator::default(), Some(line_separator)).is_err());
+        let line_separator = LineSeparator::new(2, "\n".to_string());
+        assert!(fmt(ItemSeparator::CharCount(1), Some(line_separator)).is_ok());
+        let line_separator = LineSeparator::new(2, String::new());
+        let error = fmt(ItemSeparator::default(), Some(line_separator)).unwrap_err();
+        assert_eq!(error.to_string(), "invalid format: line separator is empty");
+        assert!("cols=2,line=''".parse::<InFormat>().is_err());
+    }
+
+    #[test]
+    fn spec() {
+        let spec = "in:widths=2/3,record=6c,line='',mode=csv,quote_escape=backslash,\
+                    empty=yield,quote='\\'',comment='//',trim,header,skip=1,max=4,expect_line=2";
+        let fmt: InFormat = spec.parse().unwrap();
+        assert_eq!(fmt.item_separator, ItemSeparator::FieldWidths(vec![2, 3]));
+        assert_eq!(fmt.quote, Some('\''));
+        assert_eq!(fmt.max_items, Some(4));
+        assert_eq!(fmt.to_string(), spec);
+
+        let fmt: InFormat = "sep=3c, cols = 2 ,line=';'".parse().unwrap();
+        assert_eq!(fmt.to_string(), "in:sep=3c,cols=2,line=';'");
+        #[cfg(feature = "unicode-segmentation")]
+        {
+            let fmt: InFormat = "sep=3g".parse().unwrap();
+            assert_eq!(fmt.item_separator, ItemSeparator::GraphemeCount(3));
+        }
+        let fmt: InFormat = "in:sep=whitespace;out:sep=' '".parse().unwrap();
+        let items: Vec<_> = read(" a  b\n", fmt).collect();
+        assert_eq!(items, ["a", "b"]);
+
+        assert!("sep=0".parse::<InFormat>().is_err());
+        assert!("sep=''".parse::<InFormat>().is_err());
+        assert!("mode=xml".parse::<InFormat>().is_err());
+        assert!("trim=yes".parse::<InFormat>().is_err());
+        assert!("=1".parse::<InFormat>().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let fmt = InFormatBuilder::default()
+            .item_separator(ItemSeparator::Explicit(",".to_string()))
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .comment(Some("#".to_string()))
+            .build()
+            .unwrap();
+        let json = serde_json::to_string(&fmt).unwrap();
+        let fmt: InFormat = serde_json::from_str(&json).unwrap();
+        let items: Vec<_> = read("#c\na,b\nc,d", fmt).collect();
+        assert_eq!(items, ["a", "b", "c", "d"]);
+
+        // missing settings get their default, invalid ones are an error
+        let fmt: InFormat = serde_json::from_str(r#"{"mode":"Csv"}"#).unwrap();
+        assert_eq!(fmt.mode, InputMode::Csv);
+        assert!(serde_json::from_str::<InFormat>(r#"{"item_separator":{"Explicit":""}}"#).is_err());
+        let json = r#"{"line_separator":{"items_per_line":0,"line_separator":"\n"}}"#;
+        assert!(serde_json::from_str::<InFormat>(json).is_err());
+    }
+
+    #[test]
+    fn reader_explicit() {
+        let input = "a,bb,ccc,,";
+        let mut reader = ItemReader::new(
+            input,
+            InFormatBuilder::default()
+                .item_separator(ItemSeparator::Explicit(",".to_string()))
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(Some("a"), reader.next());
+        assert_eq!(Some("bb"), reader.next());
+        assert_eq!(Some("ccc"), reader.next());
+        assert_eq!(None, reader.next());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn reader_regex() {
+        let input = "a  bb\tccc \t d";
+        let mut reader = ItemReader::new(
+            input,
+            InFormatBuilder::default()
+                .item_separator(ItemSeparator::Regex("[ \t]+".to_string()))
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(Some("a"), reader.next());
+        assert_eq!(Some("bb"), reader.next());
+        assert_eq!(Some("ccc"), reader.next());
+        assert_eq!(Some("d"), reader.next());
+        assert_eq!(None, reader.next());
+
+        let invalid = InFormatBuilder::default()
+            .item_separator(ItemSeparator::Regex("[a".to_string()))
+            .build();
+        assert!(matches!(invalid, Err(LineupError::Validation(_))));
+        assert!(matches!(
+            "in:regex='[a'".parse::<InFormat>(),
+            Err(LineupError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn reader_whitespace() {
+        let input = "  a  bb\tccc \n\t d \n";
+        let mut reader = ItemReader::new(
+            input,
+            InFormatBuilder::default()
+                .item_separator(ItemSeparator::Whitespace)
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(Some("a"), reader.next());
+        assert_eq!(Some("bb"), reader.next());
+        assert_eq!(Some("ccc"), reader.next());
+        assert_eq!(Some("d"), reader.next());
+        assert_eq!(None, reader.next());
+    }
+
+    #[test]
+    fn reader_quoted() {
+        let input = "a,\"b,c\",\"say \"\"hi\"\",\"\nd,\"e\n";
+        let fmt = InFormatBuilder::default()
+            .item_separator(ItemSeparator::Explicit(",".to_string()))
+            .line_separator(Some(LineSeparator::new(3, "\n".to_string())))
+            .quote(Some('"'))
+            .build()
+            .unwrap();
+        let mut reader = ItemReader::new(input, fmt.clone());
+        assert_eq!(Some("a"), reader.next());
+        assert_eq!(Some("\"b,c\""), reader.next());
+        assert_eq!(Some("\"say \"\"hi\"\",\""), reader.next());
+        assert_eq!(Some("d"), reader.next());
+        assert_eq!(Some("\"e\n"), reader.next());
+        assert_eq!(None, reader.next());
+        assert_eq!("b,c", fmt.unquote("\"b,c\""));
+        assert_eq!("say \"hi\",", fmt.unquote("\"say \"\"hi\"\",\""));
+        assert_eq!("\"e\n", fmt.unquote("\"e\n"));
+
+        let fmt = InFormatBuilder::default()
+            .item_separator(ItemSeparator::Whitespace)
+            .quote(Some('\''))
+            .quote_escape(QuoteEscape::Backslash)
+            .build()
+            .unwrap();
+        let mut reader = ItemReader::new("'a \\' b' c", fmt.clone());
+        assert_eq!(Some("'a \\' b'"), reader.next());
+        assert_eq!(Some("c"), reader.next());
+        assert_eq!(None, reader.next());
+        assert_eq!("a ' b", fmt.unquote("'a \\' b'"));
+    }
+
+    #[test]
+    fn reader_escaped() {
+        let input = "a\\,b,c\\\\,d\\\n,e\n";
+        let fmt = InFormatBuilder::default()
+            .item_separator(ItemSeparator::Explicit(",".to_string()))
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .escape(Some('\\'))
+            .build()
+            .unwrap();
+        let mut reader = ItemReader::new(input, fmt.clone());
+        assert_eq!(Some("a\\,b"), reader.next());
+        assert_eq!(Some("c\\\\,d\\\n,e"), reader.next());
+        assert_eq!(None, reader.next());
+        assert_eq!("a,b", fmt.unquote("a\\,b"));
+        assert_eq!("c\\,d\n,e", fmt.unquote("c\\\\,d\\\n,e"));
+    }
+
+    #[test]
+    fn reader_trim() {
+        let input = "a , b ,c\t";
+        let mut reader = ItemReader::new(
+            input,
+            InFormatBuilder::default()
+                .item_separator(ItemSeparator::Explicit(",".to_string()))
+                .trim(true)
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(Some("a"), reader.next());
+        assert_eq!(Some("b"), reader.next());
+        assert_eq!(Some("c"), reader.next());
+        assert_eq!(None, reader.next());
+    }
+
+    #[test]
+    fn reader_skip_empty() {
+        let input = ",a,,b, ,c,,";
+        let mut fmt = InFormatBuilder::default();
+        fmt.item_separator(ItemSeparator::Explicit(",".to_string()))
+            .empty_items(EmptyItems::Skip);
+        let mut reader = ItemReader::new(input, fmt.build().unwrap());
+        assert_eq!(Some("a"), reader.next());
+        assert_eq!(Some("b"), reader.next());
+        assert_eq!(Some(" "), reader.next());
+        assert_eq!(Some("c"), reader.next());
+        assert_eq!(None, reader.next());
+
+        let mut reader = ItemReader::new(input, fmt.trim(true).build().unwrap());
+        assert_eq!(Some("a"), reader.next());
+        assert_eq!(Some("b"), reader.next());
+        assert_eq!(Some("c"), reader.next());
+        assert_eq!(None, reader.next());
+    }
+
+    #[test]
+    fn reader_yield_empty() {
+        let input = ",a,\nb,,\n";
+        let mut reader = ItemReader::new(
+            input,
+            InFormatBuilder::default()
+                .item_separator(ItemSeparator::Explicit(",".to_string()))
+                .line_separator(Some(LineSeparator::new(3, "\n".to_string())))
+                .empty_items(EmptyItems::Yield)
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(Some(""), reader.next());
+        assert_eq!(Some("a"), reader.next());
+        assert_eq!(Some(""), reader.next());
+        assert_eq!(Some("b"), reader.next());
+        assert_eq!(Some(""), reader.next());
+        assert_eq!(Some(""), reader.next());
+        assert_eq!(None, reader.next());
+    }
+
+    #[test]
+    fn reader_csv() {
+        let input = "a,\"b,c\",\r\n\"d\"\"\ne\"\"\",f\n,\n";
+        let fmt = InFormatBuilder::default()
+            .mode(InputMode::Csv)
+            .build()
+            .unwrap();
+        let mut reader = ItemReader::new(input, fmt.clone());
+        assert_eq!(Some("a"), reader.next());
+        assert_eq!(Some("\"b,c\""), reader.next());
+        assert_eq!(Some(""), reader.next());
+        assert_eq!(Some("\"d\"\"\ne\"\"\""), reader.next());
+        assert_eq!(Some("f"), reader.next());
+        assert_eq!(Some(""), reader.next());
+        assert_eq!(Some(""), reader.next());
+        assert_eq!(None, reader.next());
+        assert_eq!("b,c", fmt.unquote("\"b,c\""));
+        assert_eq!("d\"\ne\"", fmt.unquote("\"d\"\"\ne\"\"\""));
+    }
+
+    #[test]
+    fn reader_tsv() {
+        let mut reader = ItemReader::new("a b\t\"c\r\n\td\n", InFormat::tsv());
+        assert_eq!(Some("a b"), reader.next());
+        assert_eq!(Some("\"c"), reader.next());
+        assert_eq!(Some(""), reader.next());
+        assert_eq!(Some("d"), reader.next());
+        assert_eq!(None, reader.next());
+    }
+
+    #[test]
+    fn reader_json() {
+        let input = r#" [["a\"b", 1.5e3], [true, null, "\u00e9\ud83d\ude0a\n"], [], -2] "#;
+        let fmt = InFormatBuilder::default()
+            .mode(InputMode::Json)
+            .build()
+            .unwrap();
+        let items: Vec<_> = ItemReader::new(input, fmt.clone())
+            .map(|item| fmt.unquote(item))
+            .collect();
+        assert_eq!(items, ["a\"b", "1.5e3", "true", "null", "é😊\n", "-2"]);
+
+        let mut reader = ItemReader::new(r#"["a", {"b": 1}]"#, fmt.clone());
+        assert_eq!(Ok(Some("\"a\"")), reader.try_next());
+        assert_eq!(Err(ReadError::InvalidJson { offset: 6 }), reader.try_next());
+        let mut reader = ItemReader::new(r#"["a"#, fmt);
+        assert_eq!(Err(ReadError::InvalidJson { offset: 1 }), reader.try_next());
+    }
+
+    #[test]
+    fn reader_json_lines() {
+        let input = "{\"a\": 1, \"b\": {\"c\": \"x:y\"}}\r\n[2, \"z\"]\n\n{}\n";
+        let fmt = InFormatBuilder::default()
+            .mode(InputMode::JsonLines)
+            .build()
+            .unwrap();
+        let items: Vec<_> = ItemReader::new(input, fmt.clone())
+            .map(|item| fmt.unquote(item))
+            .collect();
+        assert_eq!(items, ["1", "x:y", "2", "z"]);
+
+        let mut reader = ItemReader::new("{1: 2}", fmt);
+        assert_eq!(Err(ReadError::InvalidJson { offset: 1 }), reader.try_next());
+    }
+
+    #[test]
+    fn reader_skip_lines_header() {
+        let input = "generated\n\n\"a,b\",c\r\n1,2\n";
+        let fmt = InFormatBuilder::default()
+            .mode(InputMode::Csv)
+            .skip_lines(2)
+            .header(true)
+            .build()
+            .unwrap();
+        let mut reader = ItemReader::new(input, fmt.clone());
+        assert_eq!(
+            Ok(&["a,b".to_string(), "c".to_string()][..]),
+            reader.header()
+        );
+        assert_eq!(reader.collect::<Vec<_>>(), ["1", "2"]);
+
+        let mut reader = ItemReader::new("only", fmt);
+        assert_eq!(Ok(None), reader.try_next());
+        assert!(reader.header().unwrap().is_empty());
+    }
+
+    #[test]
+    fn reader_comments() {
+        let input = "# size\n#\na 1\n# skipped\nb #2\n// kept\n# end";
+        let fmt = InFormatBuilder::default()
+            .item_separator(ItemSeparator::Explicit(" ".to_string()))
+            .line_separator(Some(LineSeparator::new(2, "\n".to_string())))
+            .comment(Some("#".to_string()))
+            .header(true)
+            .build()
+            .unwrap();
+        let mut reader = ItemReader::new(input, fmt);
+        assert_eq!(Ok(&["a".to_string(), "1".to_string()][..]), reader.header());
+        assert_eq!(reader.collect::<Vec<_>>(), ["b", "#2", "//", "kept"]);
+    }
+
+    #[test]
+    fn reader_skip_max_items() {
+        let fmt = |skip_items, max_items| {
+            InFormatBuilder::default()
+                .item_separator(ItemSeparator::Explicit("::".to_string()))
+                .empty_items(EmptyItems::Skip)
+                .skip_items(skip_items)
+                .max_items(max_items)
+                .build()
+                .unwrap()
+        };
+        let input = "a::::b::c::d::e";
+        let items: Vec<_> = ItemReader::new(input, fmt(1, Some(2))).collect();
+        assert_eq!(items, ["b", "c"]);
+        let items: Vec<_> = ItemReader::new(input, fmt(3, None)).collect();
+        assert_eq!(items, ["d", "e"]);
+        let items: Vec<_> = ItemReader::new(input, fmt(6, Some(1))).collect();
+        assert!(items.is_empty());
+        let items: Vec<_> = ItemReader::new(input, fmt(0, Some(0))).collect();
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn detect_format() {
+        let fmt = crate::detect_format("a,\"b,c\"\n1,2\n3,").unwrap();
+        assert_eq!(fmt.mode, InputMode::Csv);
+        let fmt = crate::detect_format("a\tb,c\n1\t2\n").unwrap();
+        assert_eq!(fmt.mode, InputMode::Tsv);
+        let fmt = crate::detect_format("a|b|c;d\r\n1|2|3\r\n").unwrap();
+        assert_eq!(fmt.item_separator, ItemSeparator::Explicit("|".to_string()));
+        let line_separator = LineSeparator::new(3, "\r\n".to_string());
+        assert_eq!(fmt.line_separator, Some(line_separator));
+        let fmt = crate::detect_format("a  b c\nd,e\n").unwrap();
+        assert_eq!(fmt.item_separator, ItemSeparator::Whitespace);
+        assert!(crate::detect_format("abc").is_none());
+    }
+
+    #[test]
+    fn reader_strip_bom() {
+        let input = "\u{feff}a,b";
+        let items: Vec<_> =
+            ItemReader::new(input, InFormatBuilder::default().build().unwrap()).collect();
+        assert_eq!(items, ["\u{feff}a", "b"]);
+        let fmt = InFormatBuilder::default().strip_bom(true).build().unwrap();
+        let mut reader = ItemReader::new(input, fmt);
+        assert_eq!(Ok(Some("a")), reader.try_next());
+        assert_eq!(Ok(Some("b")), reader.try_next());
+    }
+
+    #[test]
+    fn reader_record_length() {
+        let line_separator = LineSeparator::by_length(RecordLength::Chars(5), "\n".to_string());
+        let fmt = InFormatBuilder::default()
+            .item_separator(ItemSeparator::Explicit(" ".to_string()))
+            .line_separator(Some(line_separator))
+            .build()
+            .unwrap();
+        let items: Vec<_> = ItemReader::new("é b c\nd eee\nff", fmt).collect();
+        assert_eq!(items, ["é", "b", "c", "d", "eee", "ff"]);
+
+        let line_separator = LineSeparator::by_length(RecordLength::Bytes(2), "".to_string());
+        let fmt = InFormatBuilder::default()
+            .line_separator(Some(line_separator))
+            .build()
+            .unwrap();
+        let mut reader = ItemReader::new("abcé", fmt);
+        assert_eq!(Ok(Some("ab")), reader.try_next());
+        assert_eq!(
+            Err(ReadError::InvalidBoundary { offset: 4 }),
+            reader.try_next()
+        );
+    }
+
+    #[test]
+    fn reader_max_item_length() {
+        let fmt = InFormatBuilder::default()
+            .trim(true)
+            .max_item_length(Some(3))
+            .build()
+            .unwrap();
+        let mut reader = ItemReader::new("abc, éé ,  dddd ,e", fmt);
+        assert_eq!(Ok(Some("abc")), reader.try_next());
+        assert_eq!(Ok(Some("éé")), reader.try_next());
+        assert_eq!(
+            Err(ReadError::ItemTooLong {
+                offset: 13,
+                length: 4
+            }),
+            reader.try_next()
+        );
+    }
+
+    #[test]
+    fn reader_expected_items() {
+        let fmt = |expected_items, expected_items_per_line| {
+            InFormatBuilder::default()
+                .mode(InputMode::Csv)
+                .empty_items(EmptyItems::Skip)
+                .expected_items(expected_items)
+                .expected_items_per_line(expected_items_per_line)
+                .build()
+                .unwrap()
+        };
+        let input = "a,b\n\nc,d\ne,f";
+        let items: Vec<_> = ItemReader::new(input, fmt(Some(6), Some(2))).collect();
+        assert_eq!(items, ["a", "b", "c", "d", "e", "f"]);
 
-impl<'i> ItemReader<'i> {
-    pub fn next_item(&mut self, separator: ItemSeparator) -> Option<&'i str> {
-        if self.input.is_empty() {
-            None
-        } else {
-            match &separator {
-                ItemSeparator::Explicit(separator) => match self.input.split_once(separator) {
-                    None => {
-                        let last = self.input;
-                        self.input = "";
-                        Some(last)
-                    }
-                    Some((item, remainder)) => {
-                        self.input = remainder;
-                        if item.is_empty() {
-                            None
-                        } else {
-                            Some(item)
-                        }
-                    }
-                },
-                ItemSeparator::ByteCount(count) => {
-                    if self.input.len() >= *count {
-                        let split = self.input.split_at(*count);
-                        self.input = split.1;
-                        Some(split.0)
-                    } else {
-                        self.input = "";
-                        None
-                    }
-                }
-            }
-        }
+        let mut reader = ItemReader::new(input, fmt(Some(5), None));
+        let error = std::iter::from_fn(|| reader.try_next().transpose()).find_map(Result::err);
+        let expected = ReadError::ItemCount {
+            expected: 5,
+            found: 6,
+        };
+        assert_eq!(Some(expected), error);
+        assert_eq!(expected.to_string(), "expected 5 items, found more");
+
+        let mut reader = ItemReader::new(input, fmt(Some(7), None));
+        let error = std::iter::from_fn(|| reader.try_next().transpose()).find_map(Result::err);
+        let expected = ReadError::ItemCount {
+            expected: 7,
+            found: 6,
+        };
+        assert_eq!(Some(expected), error);
+
+        let mut reader = ItemReader::new("a,b\nc\nd,e", fmt(None, Some(2)));
+        assert_eq!(Ok(Some("a")), reader.try_next());
+        assert_eq!(Ok(Some("b")), reader.try_next());
+        let expected = ReadError::LineItemCount {
+            offset: 4,
+            found: 1,
+        };
+        assert_eq!(Err(expected), reader.try_next());
     }
-}
 
-impl<'i> Iterator for ItemReader<'i> {
-    type Item = &'i str;
-    fn next(&mut self) -> Option<Self::Item> {
-        let separator = {
-            if let Some(line_separator) = &self.fmt.line_separator {
-                if self.items_in_current_line == line_separator.items_per_line - 1 {
-                    self.items_in_current_line = 0;
-                    ItemSeparator::Explicit(line_separator.line_separator.clone())
-                } else {
-                    self.items_in_current_line += 1;
-                    self.fmt.item_separator.clone()
-                }
-            } else {
-                self.fmt.item_separator.clone()
-            }
+    #[test]
+    fn reader_byte_rounding() {
+        let fmt = |byte_rounding| {
+            InFormatBuilder::default()
+                .item_separator(ItemSeparator::ByteCount(2))
+                .partial_chunk(PartialChunk::Yield)
+                .byte_rounding(byte_rounding)
+                .build()
+                .unwrap()
         };
-        self.next_item(separator)
+        let input = "aébc€d";
+        let items: Vec<_> = ItemReader::new(input, fmt(ByteRounding::Forward)).collect();
+        assert_eq!(items, ["aé", "bc", "€", "d"]);
+        let items: Vec<_> = ItemReader::new(input, fmt(ByteRounding::Backward)).collect();
+        assert_eq!(items, ["a", "é", "bc", "€", "d"]);
     }
-}
 
-impl ItemWriter {
-    /// Write input item as per provided format
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// let input = ["😊😊", "👶", "💼💼💼"];
-    /// let format = lineup::OutFormatBuilder::default()
-    ///     .span(Some(lineup::ItemSpan::new(4, '👉', lineup::Anchor::Right)))
-    ///     .item_separator("🖖".to_string())
-    ///     .line_separator(Some(lineup::LineSeparator::new(2, "🔩\n".to_string())))
-    ///     .build()
-    ///     .unwrap();
-    /// let expected = "👉👉😊😊🖖👉👉👉👶🔩\n👉💼💼💼";
-    /// let mut output = vec![0u8; 100 ];
-    /// let mut writer = lineup::ItemWriter::new(format);
-    /// let istream = input.into_iter();
-    /// let mut ostream = output.as_mut_slice();
-    /// for item in istream {
-    ///     writer.write(item, &mut ostream).unwrap();
-    /// }
-    /// let eof = output.iter().position(|x| *x == 0u8).unwrap_or(output.len());
-    /// let output = output.split_at(eof).0;
-    /// assert_eq!(String::from_utf8(output.to_vec()).unwrap(), expected);
-    /// ```
-    ///
-    pub fn write<Out: std::io::Write>(
-        &mut self,
-        item: &str,
-        writer: &mut Out,
-    ) -> Result<(), std::io::Error> {
-        // emit separator from previous input
-        match self.separator {
-            EmittingSeparator::None => {}
-            EmittingSeparator::Item => {
-                writer.write_all(self.fmt.item_separator.as_bytes())?;
-            }
-            EmittingSeparator::Line => {
-                writer.write_all(
-                    self.fmt
-                        .line_separator
-                        .as_ref()
-                        .unwrap()
-                        .line_separator
-                        .as_bytes(),
-                )?;
-            }
-        }
+    #[test]
+    fn reader_fallible() {
+        let fmt = InFormatBuilder::default()
+            .mode(InputMode::Json)
+            .build()
+            .unwrap();
+        let items: Vec<_> = crate::try_read("[1, x, 2]", fmt.clone()).collect();
+        assert_eq!(items, [Ok("1"), Err(ReadError::InvalidJson { offset: 4 })]);
+        let items: Result<Vec<_>, _> = ItemReader::new("[1, 2]", fmt).fallible().collect();
+        assert_eq!(items, Ok(vec!["1", "2"]));
 
-        // write (padded) input
-        let input_chars = item.chars().count();
-        if let Some(span) = self.fmt.span.as_ref() && input_chars < span.span {
-            let pad_count = span.span - input_chars;
-            let pad = String::from_iter(std::iter::repeat(span.pad).take(pad_count));
-            match span.anchor {
-                Anchor::Left => {
-                    writer.write_all(item.as_bytes())?;
-                    writer.write_all(pad.as_bytes())?;
-                }
-                Anchor::Right => {
-                    writer.write_all(pad.as_bytes())?;
-                    writer.write_all(item.as_bytes())?;
-                }
-            };
-        } else {
-            writer.write_all(item.as_bytes())?;
-        }
+        let fmt = InFormatBuilder::default()
+            .max_item_length(Some(2))
+            .build()
+            .unwrap();
+        let items: Vec<_> = read("a,bbbb", fmt.clone()).collect();
+        assert_eq!(items, ["a"]);
+        let mut reader = ItemReader::new("a,bbbb,c", fmt);
+        assert_eq!(reader.by_ref().collect::<Vec<_>>(), ["a"]);
+        let error = ReadError::ItemTooLong {
+            offset: 2,
+            length: 4,
+        };
+        assert_eq!(reader.try_next(), Err(error));
+        assert_eq!(reader.fallible().collect::<Vec<_>>(), [Err(error)]);
+    }
 
-        // decide on separator for next input
-        (self.separator, self.items_in_line) =
-            if let Some(line_separator) = self.fmt.line_separator.as_ref() {
-                if self.items_in_line + 1 < line_separator.items_per_line {
-                    (EmittingSeparator::Item, self.items_in_line + 1)
-                } else {
-                    (EmittingSeparator::Line, 0)
-                }
-            } else {
-                (EmittingSeparator::Item, 0)
-            };
-        Ok(())
+    #[test]
+    fn reader_peek() {
+        let fmt = InFormatBuilder::default()
+            .item_separator(ItemSeparator::ByteCount(2))
+            .build()
+            .unwrap();
+        let mut reader = ItemReader::new("aabbcé", fmt);
+        assert_eq!(Ok(vec!["aa", "bb"]), reader.peek_n(2));
+        assert_eq!(Ok(Some("aa")), reader.try_next());
+        assert_eq!(Ok(Some("bb")), reader.peek());
+        assert_eq!(
+            Err(ReadError::InvalidBoundary { offset: 6 }),
+            reader.peek_n(3)
+        );
+        assert_eq!(Ok(Some("bb")), reader.try_next());
     }
-}
 
-#[cfg(test)]
-mod write_test {
-    use super::*;
+    #[test]
+    fn reader_size_hint() {
+        let fmt = |partial_chunk, skip_items, max_items| {
+            InFormatBuilder::default()
+                .item_separator(ItemSeparator::ByteCount(2))
+                .partial_chunk(partial_chunk)
+                .skip_items(skip_items)
+                .max_items(max_items)
+                .build()
+                .unwrap()
+        };
+        let input = "\u{feff}aabbccdde";
+        let reader = ItemReader::new(&input[3..], fmt(PartialChunk::Drop, 0, None));
+        assert_eq!((4, Some(4)), reader.size_hint());
+        let mut fmt_bom = fmt(PartialChunk::Drop, 0, None);
+        fmt_bom.strip_bom = true;
+        let reader = ItemReader::new(input, fmt_bom).exact_size().unwrap();
+        assert_eq!(4, reader.len());
+        assert_eq!(4, reader.count());
+        let mut reader = ItemReader::new(&input[3..], fmt(PartialChunk::Yield, 1, Some(3)));
+        assert_eq!((3, Some(3)), reader.size_hint());
+        reader.next();
+        reader.next();
+        assert_eq!((1, Some(1)), reader.size_hint());
+        let reader = ItemReader::new(input, InFormatBuilder::default().build().unwrap());
+        assert_eq!((0, None), reader.size_hint());
+        assert!(reader.exact_size().is_none());
+    }
 
     #[test]
-    fn test() {
-        let input = ["001", "01", "1"];
-        let expected = "_001|__01;___1*";
-        let mut output = [0u8; 15];
-        output[14] = b'*';
-        let format = OutFormatBuilder::default()
-            .span(Some(ItemSpan::new(4, '_', Anchor::Right)))
-            .item_separator("|".to_string())
-            .line_separator(Some(LineSeparator::new(2, ";".to_string())))
+    fn reader_bytes() {
+        let input = b"\xffa,b\xe2\x82,\xef\xbf\xbd";
+        let fmt = InFormatBuilder::default()
+            .invalid_utf8(InvalidUtf8::Replace)
             .build()
             .unwrap();
-        write(input.into_iter(), output.as_mut_slice(), format).unwrap();
-        assert_eq!(String::from_utf8(output.to_vec()).unwrap(), expected);
+        let items: Result<Vec<_>, _> = crate::read_bytes(input, fmt).collect();
+        assert_eq!(items.unwrap(), ["\u{fffd}a", "b\u{fffd}", "\u{fffd}"]);
+        let fmt = InFormatBuilder::default().build().unwrap();
+        let items: Vec<_> = crate::read_bytes(&input[1..], fmt.clone()).collect();
+        let error = ReadError::InvalidUtf8 { offset: 3 };
+        assert_eq!(items, [Ok(Cow::Borrowed("a")), Err(error)]);
+        let items: Vec<_> = crate::read_bytes(b"a,b", fmt).collect();
+        assert!(matches!(
+            items[..],
+            [Ok(Cow::Borrowed("a")), Ok(Cow::Borrowed("b"))]
+        ));
     }
 
     #[test]
-    fn example() {
-        let input = ["😊😊", "👶", "💼💼💼"];
-        let format = OutFormatBuilder::default()
-            .span(Some(ItemSpan::new(4, '👉', Anchor::Right)))
-            .item_separator("🖖".to_string())
-            .line_separator(Some(LineSeparator::new(2, "🔩\n".to_string())))
+    fn reader_field_widths() {
+        let input = "AB12é   XYZ\r\nCD34\nEF56ü       skipped\n";
+        let fmt = InFormatBuilder::default()
+            .item_separator(ItemSeparator::FieldWidths(vec![2, 2, 7]))
+            .line_separator(Some(LineSeparator::new(1, "|".to_string())))
             .build()
             .unwrap();
-        let expected = "👉👉😊😊🖖👉👉👉👶🔩\n👉💼💼💼";
-        let mut output = vec![0u8; 100];
-        write(input.into_iter(), output.as_mut_slice(), format).unwrap();
-        let eof = output
-            .iter()
-            .position(|x| *x == 0u8)
-            .unwrap_or(output.len());
-        let output = output.split_at(eof).0;
-        assert_eq!(String::from_utf8(output.to_vec()).unwrap(), expected);
+        let items: Vec<_> = ItemReader::new(input, fmt).collect();
+        assert_eq!(
+            items,
+            ["AB", "12", "é   XYZ", "CD", "34", "", "EF", "56", "ü      "]
+        );
     }
-}
 
-#[cfg(test)]
-mod read_test {
-    use super::*;
+    #[test]
+    fn reader_byte_count() {
+        let input = "aaaabbbbccccddd";
+        let mut reader = ItemReader::new(
+            input,
+            InFormatBuilder::default()
+                .item_separator(ItemSeparator::ByteCount(4))
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(Some("aaaa"), reader.next());
+        assert_eq!(Some("bbbb"), reader.next());
+        assert_eq!(Some("cccc"), reader.next());
+        assert_eq!(None, reader.next());
+    }
 
     #[test]
-    fn reader_explicit() {
-        let input = "a,bb,ccc,,";
+    fn reader_char_count() {
+        let input = "aéb😊ccdd";
         let mut reader = ItemReader::new(
             input,
             InFormatBuilder::default()
-                .item_separator(ItemSeparator::Explicit(",".to_string()))
+                .item_separator(ItemSeparator::CharCount(3))
                 .build()
                 .unwrap(),
         );
-        assert_eq!(Some("a"), reader.next());
-        assert_eq!(Some("bb"), reader.next());
-        assert_eq!(Some("ccc"), reader.next());
+        assert_eq!(Some("aéb"), reader.next());
+        assert_eq!(Some("😊cc"), reader.next());
+        assert_eq!(None, reader.next());
+
+        let mut reader = ItemReader::new(
+            "😊😊",
+            InFormatBuilder::default()
+                .item_separator(ItemSeparator::CharCount(1))
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(Some("😊"), reader.next());
+        assert_eq!(Some("😊"), reader.next());
         assert_eq!(None, reader.next());
     }
 
+    #[cfg(feature = "unicode-segmentation")]
     #[test]
-    fn reader_byte_count() {
-        let input = "aaaabbbbccccddd";
+    fn reader_grapheme_count() {
+        let input = "e\u{301}a👨‍👩‍👧b🇮🇹c";
         let mut reader = ItemReader::new(
             input,
             InFormatBuilder::default()
-                .item_separator(ItemSeparator::ByteCount(4))
+                .item_separator(ItemSeparator::GraphemeCount(2))
+                .build()
+                .unwrap(),
+        );
+        assert_eq!(Some("e\u{301}a"), reader.next());
+        assert_eq!(Some("👨‍👩‍👧b"), reader.next());
+        assert_eq!(Some("🇮🇹c"), reader.next());
+        assert_eq!(None, reader.next());
+    }
+
+    #[test]
+    fn reader_byte_count_invalid_boundary() {
+        let input = "aébbcc";
+        let mut reader = ItemReader::new(
+            input,
+            InFormatBuilder::default()
+                .item_separator(ItemSeparator::ByteCount(2))
+                .line_separator(Some(LineSeparator::new(2, ";".to_string())))
                 .build()
                 .unwrap(),
         );
+        assert_eq!(
+            Err(ReadError::InvalidBoundary { offset: 2 }),
+            reader.try_next()
+        );
+        assert_eq!(
+            "item boundary at byte 2 is within a UTF-8 code point",
+            ReadError::InvalidBoundary { offset: 2 }.to_string()
+        );
+    }
+
+    #[test]
+    fn reader_partial_chunk() {
+        let fmt = |separator, partial_chunk| {
+            InFormatBuilder::default()
+                .item_separator(separator)
+                .partial_chunk(partial_chunk)
+                .build()
+                .unwrap()
+        };
+        let input = "aaaabbbbccc";
+        let mut reader =
+            ItemReader::new(input, fmt(ItemSeparator::ByteCount(4), PartialChunk::Yield));
         assert_eq!(Some("aaaa"), reader.next());
         assert_eq!(Some("bbbb"), reader.next());
-        assert_eq!(Some("cccc"), reader.next());
+        assert_eq!(Some("ccc"), reader.next());
         assert_eq!(None, reader.next());
+
+        let mut reader =
+            ItemReader::new(input, fmt(ItemSeparator::ByteCount(4), PartialChunk::Error));
+        assert_eq!(Ok(Some("aaaa")), reader.try_next());
+        assert_eq!(Ok(Some("bbbb")), reader.try_next());
+        assert_eq!(
+            Err(ReadError::PartialChunk { offset: 8 }),
+            reader.try_next()
+        );
+
+        let mut reader =
+            ItemReader::new("ééé", fmt(ItemSeparator::CharCount(2), PartialChunk::Yield));
+        assert_eq!(Some("éé"), reader.next());
+        assert_eq!(Some("é"), reader.next());
+        assert_eq!(None, reader.next());
+
+        let mut reader = ItemReader::new(
+            "éééé",
+            fmt(ItemSeparator::CharCount(2), PartialChunk::Error),
+        );
+        assert_eq!(Ok(Some("éé")), reader.try_next());
+        assert_eq!(Ok(Some("éé")), reader.try_next());
+        assert_eq!(Ok(None), reader.try_next());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn async_reader() {
+        // items, separators and code points split across reads of the source
+        let source = tokio::io::AsyncReadExt::chain(
+            tokio::io::AsyncReadExt::chain(&b"aa;;b"[..], &b"b;;cc\ndd;;\xc3"[..]),
+            &b"\xa9\xc3\xa9;;ff"[..],
+        );
+        let fmt = InFormatBuilder::default()
+            .item_separator(ItemSeparator::Explicit(";;".to_string()))
+            .line_separator(Some(LineSeparator::new(3, "\n".to_string())))
+            .build()
+            .unwrap();
+        let mut reader = AsyncItemReader::new(source, fmt);
+        let mut items = Vec::new();
+        while let Some(item) = reader.next_item().await.unwrap() {
+            items.push(item);
+        }
+        assert_eq!(items, ["aa", "bb", "cc", "dd", "éé", "ff"]);
+
+        let fmt = InFormatBuilder::default()
+            .item_separator(ItemSeparator::ByteCount(2))
+            .build()
+            .unwrap();
+        let mut reader = AsyncItemReader::new("aaaé".as_bytes(), fmt);
+        assert_eq!(Some("aa".to_string()), reader.next_item().await.unwrap());
+        let error = reader.next_item().await.unwrap_err();
+        assert!(matches!(
+            error,
+            LineupError::Read(ReadError::InvalidBoundary { offset: 4 })
+        ));
+    }
+
+    #[test]
+    fn stream_reader() {
+        // items, separators, code points and header split across reads of the source
+        let source = std::io::Read::chain(
+            std::io::Read::chain(
+                &b"# x
+h1;;h"[..],
+                &b"2
+aa;;b"[..],
+            ),
+            std::io::Read::chain(
+                &b"b;;cc
+dd;;\xc3"[..],
+                &b"\xa9\xc3\xa9;;ff"[..],
+            ),
+        );
+        let fmt = InFormatBuilder::default()
+            .item_separator(ItemSeparator::Explicit(";;".to_string()))
+            .line_separator(Some(LineSeparator::new(3, "\n".to_string())))
+            .comment(Some("#".to_string()))
+            .header(true)
+            .build()
+            .unwrap();
+        let mut reader = StreamItemReader::new(source, fmt);
+        assert_eq!(Some("aa".to_string()), reader.next_item().unwrap());
+        assert_eq!(reader.header(), ["h1", "h2"]);
+        let items: Vec<String> = reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(items, ["bb", "cc", "dd", "éé", "ff"]);
+
+        let fmt = InFormatBuilder::default()
+            .item_separator(ItemSeparator::ByteCount(2))
+            .build()
+            .unwrap();
+        let mut reader = StreamItemReader::new("aaaé".as_bytes(), fmt);
+        assert_eq!(
+            Some(Ok("aa".to_string())),
+            reader.next().map(|i| i.map_err(|_| ()))
+        );
+        assert!(matches!(
+            reader.next(),
+            Some(Err(LineupError::Read(ReadError::InvalidBoundary {
+                offset: 4
+            })))
+        ));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn stream_reader_separated() {
+        /// Source yielding its input, then blocking as a pipe nothing more is written to
+        struct Blocking(&'static [u8]);
+
+        impl std::io::Read for Blocking {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                assert!(!self.0.is_empty(), "source blocks");
+                self.0.read(buf)
+            }
+        }
+
+        // items followed by a whole separator are complete without reading further
+        let csv = InFormatBuilder::default()
+            .mode(InputMode::Csv)
+            .build()
+            .unwrap();
+        let explicit = InFormatBuilder::default()
+            .item_separator(ItemSeparator::Explicit(";;".to_string()))
+            .build()
+            .unwrap();
+        for (input, fmt) in [(&b"a,b\n"[..], csv), (b"a;;b;;", explicit)] {
+            let mut reader = StreamItemReader::new(Blocking(input), fmt);
+            assert_eq!(Some("a".to_string()), reader.next_item().unwrap());
+            assert_eq!(Some("b".to_string()), reader.next_item().unwrap());
+        }
+    }
+
+    #[test]
+    fn stream_reader_lines() {
+        // TSV records split across reads, items told apart by the line they are read from
+        let source = std::io::Read::chain(&b"a\tb\nc\t"[..], &b"d\ne\n"[..]);
+        let mut reader = StreamItemReader::new(source, InFormat::tsv());
+        let mut rows: Vec<(usize, String)> = Vec::new();
+        while let Some(item) = reader.next_item().unwrap() {
+            rows.push((reader.lines(), item));
+        }
+        let rows: Vec<(usize, &str)> = rows.iter().map(|(n, i)| (*n, i.as_str())).collect();
+        assert_eq!(rows, [(1, "a"), (1, "b"), (2, "c"), (2, "d"), (3, "e")]);
     }
 
     #[test]
@@ -438,6 +10390,8 @@ mod read_test {
                 .line_separator(Some(LineSeparator {
                     items_per_line: 3,
                     line_separator: "\n".to_string(),
+                    max_width: None,
+                    record_length: None,
                 }))
                 .build()
                 .unwrap(),
@@ -461,6 +10415,8 @@ mod read_test {
                 .line_separator(Some(LineSeparator {
                     items_per_line: 3,
                     line_separator: ";".to_string(),
+                    max_width: None,
+                    record_length: None,
                 }))
                 .build()
                 .unwrap(),
@@ -1,15 +1,275 @@
 mod config;
 
-use lineup::{read, write};
-use std::io::Read;
+use lineup::{ItemReader, ItemWriter, StreamItemReader};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
 
-fn main() -> Result<(), std::io::Error> {
-    let cfg = config::Config::new();
+fn main() -> Result<(), lineup::LineupError> {
+    let mut cfg = config::Config::new();
+    if cfg.streaming() {
+        return stream(&cfg);
+    }
 
-    let mut istream = cfg.istream();
-    let mut buf = "".to_string();
-    istream.read_to_string(&mut buf)?;
-    let item_reader = read(buf.as_str(), cfg.in_fmt().clone());
-    write(item_reader, cfg.ostream(), cfg.out_format())?;
+    let inputs = cfg.inputs()?;
+    cfg.detect_in_format(inputs.first().map_or("", String::as_str));
+    if cfg.check() {
+        for (input, name) in inputs.iter().zip(cfg.input_names()) {
+            if let Err(violation) = lineup::check(input, cfg.in_fmt().clone()) {
+                match inputs.len() {
+                    1 => eprintln!("{violation}"),
+                    _ => eprintln!("{name}: {violation}"),
+                }
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    let mut format = cfg.out_format();
+    let pipeline = cfg.pipeline();
+    let mut groups = Vec::new();
+    let mut stats = lineup::Stats::default();
+    for input in &inputs {
+        let mut item_reader = ItemReader::new(input.as_str(), cfg.in_fmt().clone());
+        if groups.is_empty() && format.header.is_empty() {
+            format.header = item_reader.header()?.to_vec();
+        }
+        let first_group = groups.len();
+        if cfg.rows() {
+            for row in read_rows(&mut item_reader, &cfg)? {
+                groups.push(pipeline.apply(row).collect());
+            }
+        } else {
+            // stats count the lines read, which parallel reading does not keep
+            #[cfg(feature = "rayon")]
+            let items = match cfg.jobs() {
+                Some(jobs) if !cfg.stats() => lineup::read_parallel(input, cfg.in_fmt(), jobs)?,
+                _ => read(&mut item_reader, &cfg)?,
+            };
+            #[cfg(not(feature = "rayon"))]
+            let items = read(&mut item_reader, &cfg)?;
+            let items: Vec<Cow<str>> = pipeline.apply(items).collect();
+            groups.push(items);
+        }
+        if cfg.stats() {
+            let items = groups[first_group..].iter().flatten();
+            stats += lineup::Stats {
+                lines: item_reader.lines(),
+                ..lineup::Stats::new(items, format.width)
+            };
+        }
+    }
+    if cfg.stats() {
+        let mut ostream = cfg.ostream()?;
+        write!(ostream, "{stats}")?;
+        return Ok(ostream.flush()?);
+    }
+    if let Some(row_length) = cfg.transpose() {
+        let items: Vec<Cow<str>> = groups.into_iter().flatten().collect();
+        let row_length = if row_length > 0 {
+            row_length
+        } else {
+            items.len()
+        };
+        let rows = items.chunks(row_length.max(1)).map(<[_]>::to_vec);
+        let columns = lineup::transpose(rows);
+        format.set_items_per_line(columns.first().map_or(1, Vec::len));
+        groups = vec![columns.into_iter().flatten().collect()];
+    }
+    #[cfg(feature = "encoding")]
+    if cfg.out_encoding().is_some() {
+        let mut output = Vec::new();
+        lineup(&groups, format, &mut output, &cfg)?;
+        let output = String::from_utf8_lossy(&output);
+        let mut ostream = cfg.ostream()?;
+        ostream.write_all(&cfg.encode(&output))?;
+        return Ok(ostream.flush()?);
+    }
+    lineup(&groups, format, cfg.ostream()?, &cfg)
+}
+
+/// Write items as soon as they are read, unquoted, in constant memory; reading stops at the
+/// first error, which is returned once items read so far are written
+fn stream(cfg: &config::Config) -> Result<(), lineup::LineupError> {
+    let ostream = Rc::new(RefCell::new(cfg.ostream()?));
+    let mut sink = SharedOstream(Rc::clone(&ostream));
+    let pipeline = cfg.pipeline();
+    let mut writer: Option<ItemWriter> = None;
+    let mut error = None;
+    for istream in cfg.istreams() {
+        let source = match istream {
+            Ok(source) => source,
+            Err(e) => {
+                error = Some(e.into());
+                break;
+            }
+        };
+        let source = FlushingSource {
+            source,
+            ostream: Rc::clone(&ostream),
+        };
+        let mut reader = StreamItemReader::new(source, cfg.in_fmt().clone());
+        let mut next = reader.next_item().transpose();
+        let writer = match &mut writer {
+            Some(writer) => {
+                writer.end_line(&mut sink)?;
+                writer
+            }
+            None => {
+                let mut format = cfg.out_format();
+                if format.header.is_empty() {
+                    format.header = reader.header().to_vec();
+                }
+                writer.insert(ItemWriter::new(format))
+            }
+        };
+        let items = std::iter::from_fn(|| {
+            let item = next.take().or_else(|| reader.next_item().transpose())?;
+            Some(item.map(|item| (reader.lines(), item)))
+        })
+        .map_while(|item| item.map_err(|e| error = Some(e)).ok())
+        .map(|(line, item)| match cfg.in_fmt().unquote(&item) {
+            Cow::Owned(unquoted) => (line, unquoted),
+            Cow::Borrowed(_) => (line, item),
+        });
+        if cfg.rows() {
+            // a record is written once the next one starts or input ends
+            let mut items = items.peekable();
+            while let Some(&(line, _)) = items.peek() {
+                let mut row = std::iter::from_fn(|| items.next_if(|(next, _)| *next == line));
+                writer.end_line(&mut sink)?;
+                for item in pipeline.apply(row.by_ref().map(|(_, item)| item)) {
+                    writer.write(&item, &mut sink)?;
+                }
+                row.for_each(drop);
+            }
+        } else {
+            for item in pipeline.apply(items.map(|(_, item)| item)) {
+                writer.write(&item, &mut sink)?;
+            }
+        }
+        if error.is_some() {
+            break;
+        }
+    }
+    if let Some(writer) = &mut writer {
+        writer.finish(&mut sink)?;
+    }
+    ostream.borrow_mut().flush()?;
+    error.map_or(Ok(()), Err)
+}
+
+/// Input source flushing output before reading, so that output keeps up with input that may
+/// not end, e.g. from `tail -f`
+struct FlushingSource<R, W> {
+    source: R,
+    ostream: Rc<RefCell<W>>,
+}
+
+impl<R: std::io::Read, W: Write> std::io::Read for FlushingSource<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.ostream.borrow_mut().flush()?;
+        self.source.read(buf)
+    }
+}
+
+/// Output stream shared with a [FlushingSource]
+struct SharedOstream<W>(Rc<RefCell<W>>);
+
+impl<W: Write> Write for SharedOstream<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[std::io::IoSlice<'_>]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.borrow_mut().flush()
+    }
+}
+
+/// Read all items, unquoted
+fn read<'i>(
+    item_reader: &mut ItemReader<'i>,
+    cfg: &config::Config,
+) -> Result<Vec<Cow<'i, str>>, lineup::LineupError> {
+    let items = std::iter::from_fn(|| item_reader.try_next().transpose())
+        .map(|item| item.map(|item| cfg.in_fmt().unquote(item)))
+        .collect::<Result<_, _>>()?;
+    Ok(items)
+}
+
+/// Read all items, unquoted, in rows of those of the same input line
+fn read_rows<'i>(
+    item_reader: &mut ItemReader<'i>,
+    cfg: &config::Config,
+) -> Result<Vec<Vec<Cow<'i, str>>>, lineup::LineupError> {
+    let mut rows: Vec<Vec<Cow<str>>> = Vec::new();
+    let mut line = None;
+    while let Some(item) = item_reader.try_next()? {
+        let item = cfg.in_fmt().unquote(item);
+        match rows.last_mut() {
+            Some(row) if line == Some(item_reader.lines()) => row.push(item),
+            _ => rows.push(vec![item]),
+        }
+        line = Some(item_reader.lines());
+    }
+    Ok(rows)
+}
+
+/// Write items of all `groups`, e.g. input files, to `ostream` as per output `format`
+fn lineup<Item, Out>(
+    groups: &[Vec<Item>],
+    mut format: lineup::OutFormat,
+    ostream: Out,
+    cfg: &config::Config,
+) -> Result<(), lineup::LineupError>
+where
+    Item: AsRef<str>,
+    Out: std::io::Write,
+{
+    let line_width = cfg.line_width();
+    if cfg.auto_span() || line_width.is_some() {
+        let items: Vec<&str> = groups.iter().flatten().map(AsRef::as_ref).collect();
+        if cfg.auto_span() {
+            format.align(&items);
+        }
+        if let Some(line_width) = line_width {
+            format.fit_line(line_width);
+        }
+    }
+    #[cfg(feature = "rayon")]
+    if let (Some(jobs), [items]) = (cfg.jobs(), groups) {
+        let items: Vec<&str> = items.iter().map(AsRef::as_ref).collect();
+        lineup::write_parallel(&items, ostream, format, jobs)?;
+        return Ok(());
+    }
+    write_groups(groups, ostream, format)
+}
+
+/// Write items of each group, e.g. input file, to `ostream` as per output `format`, those of
+/// every group but the first starting a new line
+fn write_groups<Item, Out>(
+    groups: &[Vec<Item>],
+    mut ostream: Out,
+    format: lineup::OutFormat,
+) -> Result<(), lineup::LineupError>
+where
+    Item: AsRef<str>,
+    Out: std::io::Write,
+{
+    let mut writer = ItemWriter::new(format);
+    for (index, items) in groups.iter().enumerate() {
+        if index > 0 {
+            writer.end_line(&mut ostream)?;
+        }
+        for item in items {
+            writer.write(item.as_ref(), &mut ostream)?;
+        }
+    }
+    writer.finish(&mut ostream)?;
     Ok(())
 }